@@ -0,0 +1,170 @@
+use serde::Deserialize;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::Path,
+};
+
+const DEFAULT_LINT_CACHE_MAX_ENTRIES: usize = 256;
+const DEFAULT_SYMBOL_CACHE_MAX_ENTRIES: usize = 256;
+
+/// How to cache a linter's diagnostics so that unchanged documents are not linted again, (ie:
+/// when switching back and forth between buffers). Entries are keyed by [`lint_cache_key`].
+#[derive(Eq, PartialEq, Clone, Debug, Deserialize)]
+pub struct PicklsLintCacheConfig {
+    /// Whether to cache diagnostics at all. Defaults to true.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// The most runs to remember per linter, evicting the least recently used. Defaults to 256.
+    #[serde(default = "default_lint_cache_max_entries")]
+    pub max_entries: usize,
+    /// How long a cached entry stays valid. Defaults to forever.
+    pub ttl_ms: Option<u64>,
+}
+
+/// The key that a linter's diagnostics are cached under: a hash of the document `text`, the fully
+/// substituted `command_line` (including the program and any `prefix_command`), and the working
+/// directory the linter runs in. A change to any of them yields a different key. Keys are only
+/// stable within a single process.
+pub fn lint_cache_key(text: &str, command_line: &[&str], cwd: &Path) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    command_line.hash(&mut hasher);
+    cwd.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// How to cache symbols so that navigating an unchanged document does not run the symbols source
+/// again. Document symbols are keyed by [`symbol_cache_key`], and a document's entries are
+/// dropped when it changes or is closed. Workspace symbols are cached per query until
+/// `workspace_ttl_ms` elapses or a file in the workspace is saved.
+#[derive(Eq, PartialEq, Clone, Debug, Deserialize)]
+pub struct PicklsSymbolCacheConfig {
+    /// Whether to cache symbols at all. Defaults to true.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// The most documents to remember symbols for, evicting the least recently used. Defaults to
+    /// 256.
+    #[serde(default = "default_symbol_cache_max_entries")]
+    pub max_entries: usize,
+    /// How long cached workspace symbols stay valid. When unset, workspace symbols are not
+    /// cached. (Optional)
+    pub workspace_ttl_ms: Option<u64>,
+}
+
+/// The key that a document's symbols are cached under: a hash of its `uri`, its LSP `version`
+/// (or, when the version is unknown, its `text`), and the fully substituted `command_line` that
+/// produces its symbols (ie: the ctags program and arguments). A change to any of them yields a
+/// different key. Keys are only stable within a single process.
+pub fn symbol_cache_key(uri: &str, version: Option<i32>, text: &str, command_line: &[&str]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    uri.hash(&mut hasher);
+    match version {
+        Some(version) => version.hash(&mut hasher),
+        None => text.hash(&mut hasher),
+    }
+    command_line.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn default_symbol_cache_max_entries() -> usize {
+    DEFAULT_SYMBOL_CACHE_MAX_ENTRIES
+}
+
+fn default_lint_cache_max_entries() -> usize {
+    DEFAULT_LINT_CACHE_MAX_ENTRIES
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_lint_cache_defaults() {
+        let cache: PicklsLintCacheConfig = serde_json::from_value(json!({})).expect("valid cache");
+        assert_eq!(
+            cache,
+            PicklsLintCacheConfig {
+                enabled: true,
+                max_entries: 256,
+                ttl_ms: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_symbol_cache_defaults() {
+        let cache: PicklsSymbolCacheConfig =
+            serde_json::from_value(json!({})).expect("valid cache");
+        assert_eq!(
+            cache,
+            PicklsSymbolCacheConfig {
+                enabled: true,
+                max_entries: 256,
+                workspace_ttl_ms: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_lint_cache_key() {
+        let key = |text: &str, command_line: &[&str], cwd: &str| {
+            lint_cache_key(text, command_line, Path::new(cwd))
+        };
+        let command_line = ["ruff", "check", "-"];
+        let base = key("x = 1\n", &command_line, "/repo");
+        assert_eq!(base, key("x = 1\n", &command_line, "/repo"));
+        // A change to any component invalidates the entry.
+        assert_ne!(base, key("x = 2\n", &command_line, "/repo"));
+        assert_ne!(
+            base,
+            key("x = 1\n", &["ruff", "check", "--fix", "-"], "/repo")
+        );
+        assert_ne!(base, key("x = 1\n", &["/opt/ruff", "check", "-"], "/repo"));
+        assert_ne!(base, key("x = 1\n", &command_line, "/repo/src"));
+        // Components are not simply concatenated.
+        assert_ne!(
+            key("", &["ruff", "check -"], "/repo"),
+            key("", &["ruff check", "-"], "/repo")
+        );
+        assert_ne!(
+            key("ruff", &["check", "-"], "/repo"),
+            key("", &["ruff", "check", "-"], "/repo")
+        );
+    }
+
+    #[test]
+    fn test_symbol_cache_key() {
+        let key = |uri: &str, version: Option<i32>, text: &str, command_line: &[&str]| {
+            symbol_cache_key(uri, version, text, command_line)
+        };
+        let uri = "file:///repo/main.py";
+        let command_line = ["ctags", "--output-format=json", "-f", "-"];
+        let base = key(uri, Some(3), "x = 1\n", &command_line);
+        assert_eq!(base, key(uri, Some(3), "x = 1\n", &command_line));
+        // A change to any component invalidates the entry.
+        assert_ne!(
+            base,
+            key("file:///repo/lib.py", Some(3), "x = 1\n", &command_line)
+        );
+        assert_ne!(base, key(uri, Some(4), "x = 1\n", &command_line));
+        assert_ne!(base, key(uri, None, "x = 1\n", &command_line));
+        assert_ne!(base, key(uri, Some(3), "x = 1\n", &["ctags", "-f", "-"]));
+        // A known version stands in for the text, which only counts when the version is unknown.
+        assert_eq!(base, key(uri, Some(3), "x = 2\n", &command_line));
+        assert_ne!(
+            key(uri, None, "x = 1\n", &command_line),
+            key(uri, None, "x = 2\n", &command_line)
+        );
+        // Components are not simply concatenated.
+        assert_ne!(
+            key(uri, None, "", &["ctags", "-f -"]),
+            key(uri, None, "", &["ctags -f", "-"])
+        );
+    }
+}
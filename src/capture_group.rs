@@ -0,0 +1,87 @@
+use crate::PicklsConfigError;
+use regex::{Captures, Match, Regex};
+use serde::Deserialize;
+use std::fmt;
+
+/// A reference to a capture group within a linter's `pattern`. Groups may be referenced by their
+/// 1-based index (ie: `2`) or by name (ie: `"line"` for a pattern containing `(?P<line>\d+)`).
+#[derive(Eq, PartialEq, Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum PicklsCaptureGroup {
+    Index(usize),
+    Name(String),
+}
+
+impl PicklsCaptureGroup {
+    /// Look up this group within a set of captures. Returns None if the group did not participate
+    /// in the match.
+    pub fn get<'h>(&self, caps: &Captures<'h>) -> Option<Match<'h>> {
+        match self {
+            PicklsCaptureGroup::Index(index) => caps.get(*index),
+            PicklsCaptureGroup::Name(name) => caps.name(name),
+        }
+    }
+
+    /// Check that this group exists within `regex`. `field` names the configuration field that
+    /// holds this group, for error reporting.
+    pub fn validate(&self, field: &str, regex: &Regex) -> Result<(), PicklsConfigError> {
+        match self {
+            PicklsCaptureGroup::Index(index) => {
+                let group_count = regex.captures_len() - 1;
+                if *index > group_count {
+                    return Err(PicklsConfigError::new(
+                        field,
+                        format!(
+                            "pattern only captures {group_count} groups but {field} = {index} \
+                            (the largest valid group is {group_count})"
+                        ),
+                    ));
+                }
+            }
+            PicklsCaptureGroup::Name(name) => {
+                if !regex.capture_names().flatten().any(|n| n == name) {
+                    return Err(PicklsConfigError::new(
+                        field,
+                        format!("pattern has no group named '{name}'"),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for PicklsCaptureGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PicklsCaptureGroup::Index(index) => write!(f, "{index}"),
+            PicklsCaptureGroup::Name(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// Where to find the description of a diagnostic.
+#[derive(Eq, PartialEq, Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum PicklsDescriptionMatch {
+    /// The description is captured by a group within `pattern`.
+    Group(PicklsCaptureGroup),
+    /// The description is an entire line of output before the matching line: -1 for the
+    /// _previous_ line of input, -2 for the line before that, and so on.
+    LineOffset(isize),
+}
+
+impl PicklsDescriptionMatch {
+    /// Check that this description match makes sense for `regex`. `field` names the
+    /// configuration field that holds it, for error reporting.
+    pub fn validate(&self, field: &str, regex: &Regex) -> Result<(), PicklsConfigError> {
+        match self {
+            PicklsDescriptionMatch::Group(group) => group.validate(field, regex),
+            PicklsDescriptionMatch::LineOffset(offset) if *offset < 0 => Ok(()),
+            PicklsDescriptionMatch::LineOffset(offset) => Err(PicklsConfigError::new(
+                field,
+                format!("{field} = {offset} must be a group or a negative line offset"),
+            )),
+        }
+    }
+}
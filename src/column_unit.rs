@@ -0,0 +1,169 @@
+use serde::Deserialize;
+
+/// The unit in which a linter reports columns. LSP positions are measured in UTF-16 code units, so
+/// captured columns are converted against the text of the line they refer to.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PicklsColumnUnit {
+    /// Columns count bytes of UTF-8, as reported by many C tools.
+    Utf8Bytes,
+    /// Columns count Unicode scalar values, as reported by clippy.
+    #[default]
+    Chars,
+    /// Columns count UTF-16 code units, the same as LSP.
+    Utf16,
+}
+
+impl PicklsColumnUnit {
+    /// Convert a zero-based `column` measured in this unit into a zero-based UTF-16 offset within
+    /// `line`. Columns beyond the end of the line are clamped to its end, and byte offsets that
+    /// fall inside a multi-byte character are rounded down to its start.
+    pub fn to_utf16(self, line: &str, column: u32) -> u32 {
+        let column = column as usize;
+        let utf16_len = |s: &str| s.encode_utf16().count() as u32;
+        match self {
+            PicklsColumnUnit::Utf8Bytes => {
+                let mut end = column.min(line.len());
+                while !line.is_char_boundary(end) {
+                    end -= 1;
+                }
+                utf16_len(&line[..end])
+            }
+            PicklsColumnUnit::Chars => line
+                .chars()
+                .take(column)
+                .map(|c| c.len_utf16() as u32)
+                .sum(),
+            PicklsColumnUnit::Utf16 => (column as u32).min(utf16_len(line)),
+        }
+    }
+
+    /// Convert the zero-based, exclusive end `column` of a range into a zero-based UTF-16 offset
+    /// within `line`, like [`PicklsColumnUnit::to_utf16`] but rounding up to the end of a
+    /// character that `column` falls inside, so that the range still covers that character.
+    pub fn end_to_utf16(self, line: &str, column: u32) -> u32 {
+        let start = self.to_utf16(line, column);
+        if self.from_utf16(line, start) >= column {
+            return start;
+        }
+        let mut utf16 = 0;
+        for c in line.chars() {
+            utf16 += c.len_utf16() as u32;
+            if utf16 > start {
+                return utf16;
+            }
+        }
+        start
+    }
+
+    /// Convert a zero-based UTF-16 offset within `line` into a zero-based column measured in this
+    /// unit, the inverse of [`PicklsColumnUnit::to_utf16`]. Offsets beyond the end of the line are
+    /// clamped to its end, and offsets that fall inside a surrogate pair are rounded down to the
+    /// start of its character.
+    pub fn from_utf16(self, line: &str, column: u32) -> u32 {
+        let (mut utf16, mut bytes, mut chars) = (0, 0, 0);
+        for c in line.chars() {
+            let next = utf16 + c.len_utf16() as u32;
+            if next > column {
+                break;
+            }
+            utf16 = next;
+            bytes += c.len_utf8() as u32;
+            chars += 1;
+        }
+        match self {
+            PicklsColumnUnit::Utf8Bytes => bytes,
+            PicklsColumnUnit::Chars => chars,
+            PicklsColumnUnit::Utf16 => utf16,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// "a", a 4-byte emoji (2 UTF-16 units), "b", a 3-byte CJK character (1 UTF-16 unit), "c".
+    const LINE: &str = "a\u{1F600}b\u{4E2D}c";
+
+    /// The column of each character of [`LINE`], and of its end, in bytes, chars and UTF-16.
+    const COLUMNS: &[(u32, u32, u32)] = &[
+        (0, 0, 0),
+        (1, 1, 1),
+        (5, 2, 3),
+        (6, 3, 4),
+        (9, 4, 5),
+        (10, 5, 6),
+    ];
+
+    #[test]
+    fn test_to_utf16() {
+        for &(bytes, chars, utf16) in COLUMNS {
+            assert_eq!(PicklsColumnUnit::Utf8Bytes.to_utf16(LINE, bytes), utf16);
+            assert_eq!(PicklsColumnUnit::Chars.to_utf16(LINE, chars), utf16);
+            assert_eq!(PicklsColumnUnit::Utf16.to_utf16(LINE, utf16), utf16);
+        }
+    }
+
+    #[test]
+    fn test_from_utf16() {
+        for &(bytes, chars, utf16) in COLUMNS {
+            assert_eq!(PicklsColumnUnit::Utf8Bytes.from_utf16(LINE, utf16), bytes);
+            assert_eq!(PicklsColumnUnit::Chars.from_utf16(LINE, utf16), chars);
+            assert_eq!(PicklsColumnUnit::Utf16.from_utf16(LINE, utf16), utf16);
+        }
+    }
+
+    #[test]
+    fn test_columns_inside_a_character_round_down() {
+        for byte in 2..5 {
+            assert_eq!(PicklsColumnUnit::Utf8Bytes.to_utf16(LINE, byte), 1);
+        }
+        for byte in 7..9 {
+            assert_eq!(PicklsColumnUnit::Utf8Bytes.to_utf16(LINE, byte), 4);
+        }
+        assert_eq!(PicklsColumnUnit::Utf8Bytes.from_utf16(LINE, 2), 1);
+        assert_eq!(PicklsColumnUnit::Chars.from_utf16(LINE, 2), 1);
+        assert_eq!(PicklsColumnUnit::Utf16.from_utf16(LINE, 2), 1);
+    }
+
+    #[test]
+    fn test_ends_inside_a_character_round_up() {
+        for &(bytes, chars, utf16) in COLUMNS {
+            assert_eq!(PicklsColumnUnit::Utf8Bytes.end_to_utf16(LINE, bytes), utf16);
+            assert_eq!(PicklsColumnUnit::Chars.end_to_utf16(LINE, chars), utf16);
+            assert_eq!(PicklsColumnUnit::Utf16.end_to_utf16(LINE, utf16), utf16);
+        }
+        for byte in 2..5 {
+            assert_eq!(PicklsColumnUnit::Utf8Bytes.end_to_utf16(LINE, byte), 3);
+        }
+        for byte in 7..9 {
+            assert_eq!(PicklsColumnUnit::Utf8Bytes.end_to_utf16(LINE, byte), 5);
+        }
+        assert_eq!(PicklsColumnUnit::Utf16.end_to_utf16(LINE, 2), 3);
+        assert_eq!(PicklsColumnUnit::Chars.end_to_utf16(LINE, u32::MAX), 6);
+    }
+
+    #[test]
+    fn test_columns_past_the_end_are_clamped() {
+        for unit in [
+            PicklsColumnUnit::Utf8Bytes,
+            PicklsColumnUnit::Chars,
+            PicklsColumnUnit::Utf16,
+        ] {
+            assert_eq!(unit.to_utf16(LINE, u32::MAX), 6);
+            assert_eq!(unit.to_utf16("", 3), 0);
+        }
+        assert_eq!(PicklsColumnUnit::Utf8Bytes.from_utf16(LINE, u32::MAX), 10);
+        assert_eq!(PicklsColumnUnit::Chars.from_utf16(LINE, u32::MAX), 5);
+        assert_eq!(PicklsColumnUnit::Utf16.from_utf16(LINE, u32::MAX), 6);
+    }
+
+    #[test]
+    fn test_cjk_line() {
+        let line = "\u{65E5}\u{672C}\u{8A9E} x";
+        assert_eq!(PicklsColumnUnit::Utf8Bytes.to_utf16(line, 9), 3);
+        assert_eq!(PicklsColumnUnit::Chars.to_utf16(line, 4), 4);
+        assert_eq!(PicklsColumnUnit::Utf8Bytes.from_utf16(line, 4), 10);
+    }
+}
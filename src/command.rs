@@ -0,0 +1,95 @@
+/// Split `command` into words the way a POSIX shell would, without any of a shell's expansions.
+/// Words are separated by unquoted whitespace. Within single quotes every character is literal;
+/// within double quotes a backslash escapes only `"` and `\`; elsewhere a backslash escapes any
+/// character. An empty quoted string is an empty word, (ie: `fmt '' -` splits into "fmt", ""
+/// and "-"). Fails on an unterminated quote or a trailing backslash.
+pub fn split_command(command: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(format!("unterminated ' in {command:?}")),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(format!("unterminated \" in {command:?}")),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(format!("unterminated \" in {command:?}")),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => return Err(format!("trailing \\ in {command:?}")),
+            },
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(command: &str) -> Vec<String> {
+        split_command(command).expect("command splits")
+    }
+
+    #[test]
+    fn test_split_whitespace() {
+        assert_eq!(
+            split("  ruff check\t--output-format concise -  "),
+            ["ruff", "check", "--output-format", "concise", "-"]
+        );
+        assert!(split("").is_empty());
+        assert!(split(" \t ").is_empty());
+    }
+
+    #[test]
+    fn test_split_quotes() {
+        assert_eq!(
+            split(r#"'/opt/my tools/lint' --x "a b" c'd e'f"#),
+            ["/opt/my tools/lint", "--x", "a b", "cd ef"]
+        );
+        assert_eq!(split(r#"fmt '' "" -"#), ["fmt", "", "", "-"]);
+        assert_eq!(split(r#"echo '"' "'""#), ["echo", "\"", "'"]);
+        assert_eq!(split("echo '$filename'"), ["echo", "$filename"]);
+    }
+
+    #[test]
+    fn test_split_escapes() {
+        assert_eq!(split(r"a\ b c\\d \'e"), ["a b", r"c\d", "'e"]);
+        assert_eq!(split(r#""x\"y\\z\n""#), [r#"x"y\z\n"#]);
+        assert_eq!(split(r"'no \escapes\'"), [r"no \escapes\"]);
+    }
+
+    #[test]
+    fn test_split_errors() {
+        assert!(split_command("lint 'open").is_err());
+        assert!(split_command(r#"lint "open"#).is_err());
+        assert!(split_command(r#"lint "open\"#).is_err());
+        assert!(split_command(r"lint \").is_err());
+    }
+}
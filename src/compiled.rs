@@ -0,0 +1,131 @@
+use crate::{
+    PicklsConfigError, PicklsErrorFormat, PicklsFormatterConfig, PicklsLinterConfig,
+    PicklsLinterFormat,
+};
+use regex::Regex;
+
+/// A validated linter configuration with all of its regexes compiled, so that they are compiled
+/// once when the configuration is loaded rather than on every run. See
+/// [`PicklsLinterConfig::compile`].
+#[derive(Clone, Debug)]
+pub struct PicklsCompiledLinter {
+    pub config: PicklsLinterConfig,
+    /// The compiled `pattern`, when `format` is "regex" and `pattern` is set.
+    pub regex: Option<Regex>,
+    /// The compiled `errorformat`, when present.
+    pub errorformats: Vec<PicklsErrorFormat>,
+    /// The compiled `ignore_pattern`, when present.
+    pub ignore_regex: Option<Regex>,
+    /// The compiled `continuation_pattern`, when present.
+    pub continuation_regex: Option<Regex>,
+    /// The compiled `note_pattern`, when present.
+    pub note_regex: Option<Regex>,
+    /// The compiled `related.pattern`, when present.
+    pub related_regex: Option<Regex>,
+    /// The compiled `ignore_messages`.
+    pub ignore_message_regexes: Vec<Regex>,
+    /// The compiled `message_rewrites`, paired with their replacements.
+    pub message_rewrites: Vec<(Regex, String)>,
+}
+
+impl PicklsLinterConfig {
+    /// Validate this linter and compile its regexes.
+    pub fn compile(&self) -> Result<PicklsCompiledLinter, PicklsConfigError> {
+        self.validate()?;
+        let regex = match self.format {
+            PicklsLinterFormat::Regex if self.pattern.is_some() => Some(self.regex()?),
+            _ => None,
+        };
+        let related_regex = self
+            .related
+            .as_ref()
+            .map(|related| related.regex().map_err(|error| error.within("related")))
+            .transpose()?;
+        Ok(PicklsCompiledLinter {
+            config: self.clone(),
+            regex,
+            errorformats: self.errorformats()?,
+            ignore_regex: self.ignore_regex()?,
+            continuation_regex: self.continuation_regex()?,
+            note_regex: self.note_regex()?,
+            related_regex,
+            ignore_message_regexes: self.ignore_message_regexes()?,
+            message_rewrites: self.message_rewrite_regexes()?,
+        })
+    }
+}
+
+impl PicklsCompiledLinter {
+    /// Apply `message_rewrites`, then `message_prefix` and `message_suffix`, to a diagnostic's
+    /// message.
+    pub fn rewrite_message(&self, message: &str) -> String {
+        let mut message = self
+            .message_rewrites
+            .iter()
+            .fold(message.to_string(), |message, (regex, replace)| {
+                regex.replace_all(&message, replace.as_str()).into_owned()
+            });
+        if let Some(prefix) = &self.config.message_prefix {
+            message.insert_str(0, prefix);
+        }
+        if let Some(suffix) = &self.config.message_suffix {
+            message.push_str(suffix);
+        }
+        message
+    }
+}
+
+/// A validated formatter configuration with its `stderr_error_pattern` compiled. See
+/// [`PicklsFormatterConfig::compile`].
+#[derive(Clone, Debug)]
+pub struct PicklsCompiledFormatter {
+    pub config: PicklsFormatterConfig,
+    /// The compiled `stderr_error_pattern`, when present.
+    pub stderr_error_regex: Option<Regex>,
+}
+
+impl PicklsFormatterConfig {
+    /// Validate this formatter and compile its `stderr_error_pattern`. Its `fallbacks` are
+    /// validated too, and are compiled on their own when they are needed.
+    pub fn compile(&self) -> Result<PicklsCompiledFormatter, PicklsConfigError> {
+        self.validate()?;
+        Ok(PicklsCompiledFormatter {
+            config: self.clone(),
+            stderr_error_regex: self.stderr_error_regex()?,
+        })
+    }
+}
+
+impl PicklsCompiledFormatter {
+    /// Decide whether a run of this formatter failed, given its exit status (None if it was killed
+    /// by a signal) and what it wrote to stderr. On failure, the returned message includes the
+    /// stderr and is ready to be logged; the buffer must then be left untouched.
+    pub fn check_result(&self, exit_code: Option<i32>, stderr: &str) -> Result<(), String> {
+        let config = &self.config;
+        let name = config.display_name();
+        let stderr = stderr.trim_end();
+        let failure = match exit_code {
+            None => Some("was killed by a signal".to_string()),
+            Some(code) if code != 0 && config.exit_code_indicates_error => {
+                Some(format!("exited with status {code}"))
+            }
+            _ => match &self.stderr_error_regex {
+                Some(regex) => {
+                    if let Some(line) = stderr.lines().find(|line| regex.is_match(line)) {
+                        return Err(format!("formatter {name} reported an error: {line}"));
+                    }
+                    None
+                }
+                None if !stderr.is_empty() && config.stderr_indicates_error => {
+                    Some("wrote to stderr".to_string())
+                }
+                None => None,
+            },
+        };
+        match failure {
+            Some(failure) if stderr.is_empty() => Err(format!("formatter {name} {failure}")),
+            Some(failure) => Err(format!("formatter {name} {failure}: {stderr}")),
+            None => Ok(()),
+        }
+    }
+}
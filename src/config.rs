@@ -1,34 +1,771 @@
-use serde::Deserialize;
-use std::collections::HashMap;
+use crate::{
+    any_file_exists_between, builtin_symbol_kind, deserialize_fallbacks,
+    deserialize_formatter_definitions, deserialize_formatter_diagnostics, deserialize_formatters,
+    deserialize_linter_definitions, deserialize_linters, deserialize_organize_imports,
+    find_local_program, find_program, linter_preset_value, lookup_symbol_kind, matches_any_glob,
+    normalize_newlines, normalize_path, paths_match, search_path, split_command,
+    substituted_variables, unknown_variables, validate_globs, PicklsCaptureGroup, PicklsColumnUnit,
+    PicklsCompiledLinter, PicklsCompiledRegexSymbol, PicklsConfigError, PicklsDescriptionMatch,
+    PicklsDiagnosticTag, PicklsErrorFormat, PicklsLintCacheConfig, PicklsSeverity,
+    PicklsSubstitutions, PicklsSymbolCacheConfig, PicklsSymbolKind, PicklsVersion,
+};
+use glob::Pattern;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    ffi::{OsStr, OsString},
+    path::{Path, PathBuf},
+    str::Utf8Error,
+    time::Duration,
+};
 
 const DEFAULT_CTAGS_TIMEOUT_MS: u64 = 500;
+const DEFAULT_GLOBAL_TIMEOUT_MS: u64 = 1_000;
+const DEFAULT_ANTHROPIC_MAX_TOKENS: u32 = 4_096;
+/// The `--fields` passed to ctags when symbols are `hierarchical`: line numbers, long kind names,
+/// scopes and scope kinds.
+pub const CTAGS_HIERARCHY_FIELDS: &str = "--fields=+nKsZ";
+const DEFAULT_WORKSPACE_SYMBOLS_MAX_RESULTS: usize = 200;
+const DEFAULT_WORKSPACE_SYMBOLS_TIMEOUT_MS: u64 = 5_000;
+const DEFAULT_WORKSPACE_SYMBOLS_EXCLUDE_GLOBS: &[&str] =
+    &["**/.git", "**/node_modules", "**/target"];
+const DEFAULT_LINTER_TIMEOUT_MS: u64 = 30_000;
+const DEFAULT_FORMATTER_TIMEOUT_MS: u64 = 10_000;
+const DEFAULT_DAEMON_IDLE_TIMEOUT_MS: u64 = 600_000;
+const DEFAULT_VERSION_PATTERN: &str = r"(\d+(?:\.\d+)+)";
+const DEFAULT_LOCAL_TOOL_DIRS: &[&str] = &["node_modules/.bin", ".venv/bin", "venv/bin"];
 
-#[derive(Clone, Debug, Deserialize, Default)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct PicklsConfig {
     #[serde(default)]
     pub languages: HashMap<String, PicklsLanguageConfig>,
+    /// How to find symbols, for languages that don't set their own `symbols`. See
+    /// [`PicklsConfig::symbols_for`]. (Optional)
     pub symbols: Option<PicklsSymbolsConfig>,
     #[serde(default)]
     pub ai: PicklsAIConfig,
+    /// The shell used to run linters that set `shell`. The linter's `command` is appended as the
+    /// final argument. Defaults to `["sh", "-c"]`.
+    #[serde(default = "default_shell")]
+    pub shell: Vec<String>,
+    /// Directories to search for programs before the inherited `PATH`, for all linters and
+    /// formatters, (ie: `["~/.local/bin", "$rootdir/node_modules/.bin"]`). See
+    /// [`crate::search_path`].
+    #[serde(default)]
+    pub extra_path: Vec<String>,
+    /// Named linter configurations that linters may build upon via `extends`, or reference by
+    /// name from a language's `linters`. Definitions take precedence over built-in presets of the
+    /// same name, except within the definition itself: a definition called "ruff" that extends
+    /// "ruff" overrides the built-in preset.
+    #[serde(default, deserialize_with = "deserialize_linter_definitions")]
+    pub linter_definitions: HashMap<String, PicklsLinterConfig>,
+    /// Named formatter configurations that formatters may build upon via `extends`, or reference
+    /// by name from a language's `formatters`, (ie: a single "prettier" shared by several
+    /// languages).
+    #[serde(default, deserialize_with = "deserialize_formatter_definitions")]
+    pub formatter_definitions: HashMap<String, PicklsFormatterConfig>,
+    /// The default `max_file_size_kb` for linters that don't set their own. (Optional)
+    pub max_file_size_kb: Option<u64>,
+    /// The default `max_file_size_kb` for formatters that don't set their own. (Optional)
+    pub formatter_max_file_size_kb: Option<u64>,
+    /// The default `cache` for linters that don't set their own. (Optional)
+    pub lint_cache: Option<PicklsLintCacheConfig>,
+    /// The most linter subprocesses to run at once. Further runs wait for a slot, highest
+    /// `priority` first. Languages may set their own limit instead. Defaults to unlimited.
+    pub max_concurrent_linters: Option<usize>,
+    /// Whether to format files as they are saved (via `textDocument/willSaveWaitUntil`), for
+    /// languages that don't set their own `format_on_save`. Either way, explicit
+    /// `textDocument/formatting` requests are served. Editors that implement format-on-save
+    /// themselves send explicit requests, so leave this off for them to avoid formatting twice.
+    /// Defaults to false.
+    pub format_on_save: Option<bool>,
+}
+
+impl Default for PicklsConfig {
+    fn default() -> Self {
+        PicklsConfig {
+            languages: HashMap::new(),
+            symbols: None,
+            ai: PicklsAIConfig::default(),
+            shell: default_shell(),
+            extra_path: Vec::new(),
+            linter_definitions: HashMap::new(),
+            formatter_definitions: HashMap::new(),
+            max_file_size_kb: None,
+            formatter_max_file_size_kb: None,
+            lint_cache: None,
+            max_concurrent_linters: None,
+            format_on_save: None,
+        }
+    }
+}
+
+impl PicklsConfig {
+    /// Expand `extends` throughout this configuration. The returned configuration has no linters
+    /// or formatters with `extends` set, so it should be resolved before it is validated or used.
+    /// Fails on references to unknown definitions and on cycles of `extends`.
+    pub fn resolve(&self) -> Result<PicklsConfig, PicklsConfigError> {
+        let mut config = self.clone();
+        for (name, definition) in &mut config.linter_definitions {
+            *definition = self
+                .resolve_linter(definition)
+                .map_err(|error| error.within(format!("linter_definitions.{name}")))?;
+        }
+        for (name, definition) in &mut config.formatter_definitions {
+            *definition = self
+                .resolve_formatter(definition)
+                .map_err(|error| error.within(format!("formatter_definitions.{name}")))?;
+        }
+        for (language_id, language) in &mut config.languages {
+            for (index, linter) in language.linters.iter_mut().enumerate() {
+                *linter = self.resolve_linter(linter).map_err(|error| {
+                    error.within(format!("languages.{language_id}.linters[{index}]"))
+                })?;
+            }
+            for (index, formatter) in language.formatters.iter_mut().enumerate() {
+                *formatter = self.resolve_formatter(formatter).map_err(|error| {
+                    error.within(format!("languages.{language_id}.formatters[{index}]"))
+                })?;
+            }
+            if let Some(organize_imports) = &mut language.organize_imports {
+                *organize_imports = self.resolve_formatter(organize_imports).map_err(|error| {
+                    error.within(format!("languages.{language_id}.organize_imports"))
+                })?;
+            }
+        }
+        Ok(config)
+    }
+
+    fn resolve_formatter(
+        &self,
+        formatter: &PicklsFormatterConfig,
+    ) -> Result<PicklsFormatterConfig, PicklsConfigError> {
+        let Some(name) = &formatter.extends else {
+            return Ok(formatter.clone());
+        };
+        let mut fields = self.formatter_definition_fields(name, &mut Vec::new())?;
+        fields.extend(formatter.fields.clone());
+        fields.remove("extends");
+        let mut resolved: PicklsFormatterConfig =
+            serde_json::from_value(Value::Object(fields.clone()))
+                .map_err(|error| PicklsConfigError::new("extends", error.to_string()))?;
+        resolved.fields = fields;
+        Ok(resolved)
+    }
+
+    /// The fields of the formatter definition called `name`, with its own `extends` expanded.
+    /// `chain` holds the definitions currently being expanded, to detect cycles.
+    fn formatter_definition_fields(
+        &self,
+        name: &str,
+        chain: &mut Vec<String>,
+    ) -> Result<Map<String, Value>, PicklsConfigError> {
+        if chain.iter().any(|link| link == name) {
+            chain.push(name.to_string());
+            return Err(PicklsConfigError::new(
+                "extends",
+                format!("cycle of formatter definitions: {}", chain.join(" -> ")),
+            ));
+        }
+        let Some(definition) = self.formatter_definitions.get(name) else {
+            return Err(PicklsConfigError::new(
+                "extends",
+                format!("unknown formatter definition '{name}'"),
+            ));
+        };
+        chain.push(name.to_string());
+        let mut fields = match &definition.extends {
+            Some(parent) => self.formatter_definition_fields(parent, chain)?,
+            None => Map::new(),
+        };
+        chain.pop();
+        fields.extend(definition.fields.clone());
+        fields.remove("extends");
+        Ok(fields)
+    }
+
+    fn resolve_linter(
+        &self,
+        linter: &PicklsLinterConfig,
+    ) -> Result<PicklsLinterConfig, PicklsConfigError> {
+        let Some(name) = &linter.extends else {
+            return Ok(linter.clone());
+        };
+        let mut fields = self.linter_definition_fields(name, &mut Vec::new())?;
+        fields.extend(linter.fields.clone());
+        fields.remove("extends");
+        let mut resolved: PicklsLinterConfig =
+            serde_json::from_value(Value::Object(fields.clone()))
+                .map_err(|error| PicklsConfigError::new("extends", error.to_string()))?;
+        resolved.fields = fields;
+        Ok(resolved)
+    }
+
+    /// The fields of the linter definition called `name`, with its own `extends` expanded.
+    /// `chain` holds the definitions currently being expanded, to detect cycles. Presets never
+    /// extend anything, so they are not part of the chain: a name that is already being expanded
+    /// as a definition refers to the preset of that name, if there is one.
+    fn linter_definition_fields(
+        &self,
+        name: &str,
+        chain: &mut Vec<String>,
+    ) -> Result<Map<String, Value>, PicklsConfigError> {
+        let expanding = chain.iter().any(|link| link == name);
+        let definition = match expanding {
+            true => None,
+            false => self.linter_definitions.get(name),
+        };
+        let Some(definition) = definition else {
+            return match linter_preset_value(name) {
+                Some(Value::Object(fields)) => Ok(fields),
+                _ if expanding => {
+                    chain.push(name.to_string());
+                    Err(PicklsConfigError::new(
+                        "extends",
+                        format!("cycle of linter definitions: {}", chain.join(" -> ")),
+                    ))
+                }
+                _ => Err(PicklsConfigError::new(
+                    "extends",
+                    format!("unknown linter definition '{name}'"),
+                )),
+            };
+        };
+        chain.push(name.to_string());
+        let mut fields = match &definition.extends {
+            Some(parent) => self.linter_definition_fields(parent, chain)?,
+            None => Map::new(),
+        };
+        chain.pop();
+        fields.extend(definition.fields.clone());
+        fields.remove("extends");
+        Ok(fields)
+    }
+
+    /// Validate `symbols`, `ai` and every language in this configuration.
+    pub fn validate(&self) -> Result<(), PicklsConfigError> {
+        check_concurrency_limit(self.max_concurrent_linters)?;
+        if let Some(symbols) = &self.symbols {
+            symbols
+                .validate()
+                .map_err(|error| error.within("symbols"))?;
+        }
+        self.ai.validate().map_err(|error| error.within("ai"))?;
+        for (language_id, language) in &self.languages {
+            language
+                .validate()
+                .map_err(|error| error.within(format!("languages.{language_id}")))?;
+        }
+        Ok(())
+    }
+
+    /// The most linter subprocesses to run at once for `language_id`: its own
+    /// `max_concurrent_linters` if set, and otherwise the top-level one. None means unlimited.
+    pub fn max_concurrent_linters_for(&self, language_id: &str) -> Option<usize> {
+        self.languages
+            .get(language_id)
+            .and_then(|language| language.max_concurrent_linters)
+            .or(self.max_concurrent_linters)
+    }
+
+    /// How to find symbols in files of `language_id`: its own `symbols` if set, and otherwise the
+    /// top-level one. None means that the language has no symbols, either because neither is set
+    /// or because the one that applies has the "none" source.
+    pub fn symbols_for(&self, language_id: &str) -> Option<&PicklsSymbolsConfig> {
+        self.languages
+            .get(language_id)
+            .and_then(|language| language.symbols.as_ref())
+            .or(self.symbols.as_ref())
+            .filter(|symbols| symbols.is_enabled())
+    }
+
+    /// Whether pickls advertises document and workspace symbol support: when the top-level
+    /// `symbols` or that of any language is set and enabled per
+    /// [`PicklsSymbolsConfig::is_enabled`].
+    pub fn advertises_symbols(&self) -> bool {
+        self.symbols
+            .iter()
+            .chain(
+                self.languages
+                    .values()
+                    .filter_map(|language| language.symbols.as_ref()),
+            )
+            .any(PicklsSymbolsConfig::is_enabled)
+    }
+
+    /// Whether to format files of `language_id` as they are saved: its own `format_on_save` if
+    /// set, and otherwise the top-level one.
+    pub fn format_on_save_for(&self, language_id: &str) -> bool {
+        self.languages
+            .get(language_id)
+            .and_then(|language| language.format_on_save)
+            .or(self.format_on_save)
+            .unwrap_or(false)
+    }
+
+    /// Validate and compile the linters of every language, keyed by language id. See
+    /// [`PicklsLinterConfig::compile`].
+    pub fn compile_linters(
+        &self,
+    ) -> Result<HashMap<String, Vec<PicklsCompiledLinter>>, PicklsConfigError> {
+        self.languages
+            .iter()
+            .map(|(language_id, language)| {
+                let linters = language
+                    .compile_linters()
+                    .map_err(|error| error.within(format!("languages.{language_id}")))?;
+                Ok((language_id.clone(), linters))
+            })
+            .collect()
+    }
+
+    /// Find problems that don't prevent this configuration from being used but are likely
+    /// mistakes, (ie: misspelled substitution variables).
+    pub fn warnings(&self) -> Vec<PicklsConfigError> {
+        self.languages
+            .iter()
+            .flat_map(|(language_id, language)| {
+                language
+                    .warnings()
+                    .into_iter()
+                    .map(move |warning| warning.within(format!("languages.{language_id}")))
+            })
+            .collect()
+    }
+}
+
+fn default_shell() -> Vec<String> {
+    ["sh", "-c"].into_iter().map(|s| s.to_string()).collect()
+}
+
+fn default_ctags_program() -> String {
+    "ctags".to_string()
 }
 
 fn default_ctags_timeout_ms() -> u64 {
     DEFAULT_CTAGS_TIMEOUT_MS
 }
 
+fn default_global_program() -> String {
+    "global".to_string()
+}
+
+fn default_global_timeout_ms() -> u64 {
+    DEFAULT_GLOBAL_TIMEOUT_MS
+}
+
+fn default_workspace_symbols_max_results() -> usize {
+    DEFAULT_WORKSPACE_SYMBOLS_MAX_RESULTS
+}
+
+fn default_workspace_symbols_timeout_ms() -> u64 {
+    DEFAULT_WORKSPACE_SYMBOLS_TIMEOUT_MS
+}
+
+fn default_workspace_symbols_exclude_globs() -> Vec<String> {
+    DEFAULT_WORKSPACE_SYMBOLS_EXCLUDE_GLOBS
+        .iter()
+        .map(|glob| glob.to_string())
+        .collect()
+}
+
+fn default_tags_file() -> String {
+    "$rootdir/tags".to_string()
+}
+
+fn default_linter_timeout_ms() -> Option<u64> {
+    Some(DEFAULT_LINTER_TIMEOUT_MS)
+}
+
+fn default_formatter_timeout_ms() -> Option<u64> {
+    Some(DEFAULT_FORMATTER_TIMEOUT_MS)
+}
+
 #[derive(Eq, PartialEq, Clone, Debug, Deserialize)]
 pub struct PicklsSymbolsConfig {
-    pub source: PicklsSymbolsSource,
+    /// The sources to find symbols with, tried in order for each document: the first that yields
+    /// at least one symbol wins, and a source that fails (ie: its program is missing or times out)
+    /// falls through to the next with a debug log. A single source may also be given, and
+    /// `source` is accepted as an alias, (ie: `"source": "universal-ctags"`). See
+    /// [`PicklsSymbolsConfig::is_enabled`].
+    #[serde(alias = "source", deserialize_with = "deserialize_symbols_sources")]
+    pub sources: Vec<PicklsSymbolsSource>,
 
-    /// How long to wait for ctags to complete before timing out. Defaults to 500ms.
+    /// How long to wait for ctags to complete before timing out. A language may set its own via
+    /// its `symbols`. Defaults to 500ms.
     #[serde(default = "default_ctags_timeout_ms")]
     pub ctags_timeout_ms: u64,
+
+    /// Find no document symbols in documents larger than this many kilobytes, (ie: SQL dumps or
+    /// generated code), without running any source. The size of the in-memory buffer is checked,
+    /// not the file on disk. See [`PicklsSymbolsConfig::oversized_notice`]. Defaults to
+    /// unlimited.
+    pub max_file_size_kb: Option<u64>,
+
+    /// The ctags to run, (ie: "/opt/homebrew/bin/ctags"). It must be universal-ctags, since the
+    /// output of exuberant-ctags is incompatible; see
+    /// [`PicklsSymbolsConfig::ctags_version_warning`]. If this is not an absolute path, the
+    /// `PATH` will be searched in an OS-defined way. Defaults to "ctags".
+    #[serde(default = "default_ctags_program")]
+    pub ctags_program: String,
+
+    /// Extra arguments for ctags, (ie: "--kinds-python=-i"), passed after the options pickls
+    /// needs and before the directories to index. Options that change the output format, such as
+    /// "-f" or "--excmd", will break parsing.
+    #[serde(default)]
+    pub ctags_extra_args: Vec<String>,
+
+    /// Whether to report document symbols nested within their scopes, (ie: methods within their
+    /// class), rather than as a flat list. ctags is then run with [`CTAGS_HIERARCHY_FIELDS`], and
+    /// symbols are nested via [`crate::nest_symbols`]. Defaults to false.
+    #[serde(default = "default_false")]
+    pub hierarchical: bool,
+
+    /// The only ctags kinds to report as document symbols, (ie: `["function", "class"]`). When
+    /// unset, every kind is reported. See [`PicklsSymbolsConfig::includes_kind`]. (Optional)
+    pub include_kinds: Option<Vec<String>>,
+
+    /// ctags kinds to leave out of document symbols, (ie: `["variable"]`), applied after
+    /// `include_kinds`. (Optional)
+    pub exclude_kinds: Option<Vec<String>>,
+
+    /// The GNU Global to run for the "gnu-global" source. If this is not an absolute path, the
+    /// `PATH` will be searched in an OS-defined way. Defaults to "global".
+    #[serde(default = "default_global_program")]
+    pub global_program: String,
+
+    /// Whether to bring the GNU Global index up to date (via `global -u`) before querying it.
+    /// The index must already exist, since pickls never creates one. Defaults to false.
+    #[serde(default = "default_false")]
+    pub global_auto_update: bool,
+
+    /// How long to wait for each run of GNU Global to complete before timing out. Defaults to
+    /// 1000ms.
+    #[serde(default = "default_global_timeout_ms")]
+    pub global_timeout_ms: u64,
+
+    /// The tags file read by the "tags-file" source, which undergoes the same substitutions as
+    /// linter arguments (only `$rootdir` is meaningful for workspace symbols). Defaults to
+    /// "$rootdir/tags".
+    #[serde(default = "default_tags_file")]
+    pub tags_file: String,
+
+    /// How old the tags file may be before it is considered stale. A stale tags file counts as a
+    /// failure of the "tags-file" source, so the next of `sources` is tried (ie: "universal-ctags"
+    /// to index live); when it is the last source, its stale symbols are used anyway. When unset,
+    /// the tags file is never stale. See [`PicklsSymbolsConfig::tags_file_is_stale`]. (Optional)
+    pub tags_file_max_age_secs: Option<u64>,
+
+    /// The queries used by the "tree-sitter" source.
+    #[serde(default)]
+    pub tree_sitter: PicklsTreeSitterConfig,
+
+    /// The patterns used by the "regex" source, keyed by language id. Languages without
+    /// patterns have no symbols. See [`crate::find_regex_symbols`].
+    #[serde(default)]
+    pub regex: HashMap<String, Vec<PicklsRegexSymbolConfig>>,
+
+    /// How `workspace/symbol` queries are served.
+    #[serde(default)]
+    pub workspace: PicklsWorkspaceSymbolsConfig,
+
+    /// How to cache symbols. When unset, symbols are found again for every request. (Optional)
+    pub cache: Option<PicklsSymbolCacheConfig>,
+
+    /// Symbol kinds for ctags kinds, overriding the built-in [`crate::CTAGS_KIND_MAP`]. Keys are
+    /// either a kind or a kind scoped to a language, (ie: `{"member": "field", "python:member":
+    /// "method"}`). See [`PicklsSymbolsConfig::symbol_kind`]. (Optional)
+    pub kind_map: Option<HashMap<String, PicklsSymbolKind>>,
+}
+
+/// Settings for `workspace/symbol` queries, which search every file of the workspace rather than
+/// a single document.
+#[derive(Eq, PartialEq, Clone, Debug, Deserialize)]
+pub struct PicklsWorkspaceSymbolsConfig {
+    /// The most symbols to return for a query. Defaults to 200.
+    #[serde(default = "default_workspace_symbols_max_results")]
+    pub max_results: usize,
+    /// Paths to leave out of the workspace index, as glob patterns matched against paths relative
+    /// to the workspace folder. Directories that match are not descended into. See
+    /// [`PicklsWorkspaceSymbolsConfig::excludes`]. Defaults to
+    /// `["**/.git", "**/node_modules", "**/target"]`.
+    #[serde(default = "default_workspace_symbols_exclude_globs")]
+    pub exclude_globs: Vec<String>,
+    /// Whether to index the workspace as soon as the server starts, rather than on the first
+    /// query. Defaults to false.
+    #[serde(default = "default_false")]
+    pub index_on_startup: bool,
+    /// How long to wait for the whole workspace to be indexed before timing out. This is separate
+    /// from the per-file timeouts since indexing a workspace takes much longer. Defaults to
+    /// 5000ms.
+    #[serde(default = "default_workspace_symbols_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for PicklsWorkspaceSymbolsConfig {
+    fn default() -> Self {
+        PicklsWorkspaceSymbolsConfig {
+            max_results: DEFAULT_WORKSPACE_SYMBOLS_MAX_RESULTS,
+            exclude_globs: default_workspace_symbols_exclude_globs(),
+            index_on_startup: false,
+            timeout_ms: DEFAULT_WORKSPACE_SYMBOLS_TIMEOUT_MS,
+        }
+    }
+}
+
+impl PicklsWorkspaceSymbolsConfig {
+    /// Check that `max_results` is positive and that `exclude_globs` compile.
+    pub fn validate(&self) -> Result<(), PicklsConfigError> {
+        if self.max_results == 0 {
+            return Err(PicklsConfigError::new(
+                "max_results",
+                "max_results must be at least 1",
+            ));
+        }
+        validate_globs("exclude_globs", &self.exclude_globs)
+    }
+
+    /// Whether `relative_path` (relative to the workspace folder) is left out of the index per
+    /// `exclude_globs`.
+    pub fn excludes(&self, relative_path: &Path) -> bool {
+        matches_any_glob(&self.exclude_globs, relative_path)
+    }
+}
+
+/// A pattern of the "regex" symbols source, matched against each line of a document. Each match
+/// produces a symbol at that line.
+#[derive(Eq, PartialEq, Clone, Debug, Deserialize)]
+pub struct PicklsRegexSymbolConfig {
+    /// Regex that matches a line defining a symbol, (ie: "^(\\w+):" for Justfile recipes).
+    pub pattern: String,
+    /// Regex group (1-indexed or named) that matches the name of the symbol.
+    pub name_match: PicklsCaptureGroup,
+    /// The kind of the symbols this pattern finds, (ie: "function").
+    pub kind: PicklsSymbolKind,
+    /// Regex group (1-indexed or named) that matches the name of the symbol's container.
+    /// (Optional)
+    pub container_match: Option<PicklsCaptureGroup>,
+}
+
+/// Symbol-extraction queries for the "tree-sitter" symbols source.
+#[derive(Eq, PartialEq, Clone, Debug, Default, Deserialize)]
+pub struct PicklsTreeSitterConfig {
+    /// Tree-sitter queries keyed by language id, in the style of tree-sitter's `tags.scm`: each
+    /// pattern captures a definition as `@definition.<kind>` (ie: `@definition.function`) and its
+    /// name as `@name`. Symbols nest within the definitions that contain them in the syntax tree.
+    /// These take precedence over the built-in queries for rust, python, go and javascript, which
+    /// are available with the "tree-sitter" cargo feature. See
+    /// [`PicklsTreeSitterConfig::query_for`].
+    #[serde(default)]
+    pub queries: HashMap<String, String>,
+}
+
+impl PicklsTreeSitterConfig {
+    /// Check that no query is empty.
+    pub fn validate(&self) -> Result<(), PicklsConfigError> {
+        match self
+            .queries
+            .iter()
+            .find(|(_, query)| query.trim().is_empty())
+        {
+            Some((language_id, _)) => Err(PicklsConfigError::new(
+                format!("queries.{language_id}"),
+                "query may not be empty",
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// The query to extract symbols from documents of `language_id`, from `queries` or else the
+    /// built-in queries. None when there is no query for this language, so it has no symbols.
+    pub fn query_for(&self, language_id: &str) -> Option<&str> {
+        self.queries
+            .get(language_id)
+            .map(String::as_str)
+            .or_else(|| builtin_tree_sitter_query(language_id))
+    }
+}
+
+#[cfg(feature = "tree-sitter")]
+fn builtin_tree_sitter_query(language_id: &str) -> Option<&'static str> {
+    match language_id {
+        "rust" => Some(include_str!("queries/rust.scm")),
+        "python" => Some(include_str!("queries/python.scm")),
+        "go" => Some(include_str!("queries/go.scm")),
+        "javascript" | "javascriptreact" => Some(include_str!("queries/javascript.scm")),
+        _ => None,
+    }
+}
+
+#[cfg(not(feature = "tree-sitter"))]
+fn builtin_tree_sitter_query(_language_id: &str) -> Option<&'static str> {
+    None
+}
+
+impl PicklsSymbolsConfig {
+    /// Check that `sources`, `ctags_program` and `global_program` are set, and validate
+    /// `tree_sitter`, `workspace` and `regex`.
+    pub fn validate(&self) -> Result<(), PicklsConfigError> {
+        if self.sources.is_empty() {
+            return Err(PicklsConfigError::new(
+                "sources",
+                "sources must list at least one source",
+            ));
+        }
+        if self.ctags_program.is_empty() {
+            return Err(PicklsConfigError::new(
+                "ctags_program",
+                "ctags_program may not be empty",
+            ));
+        }
+        if self.global_program.is_empty() {
+            return Err(PicklsConfigError::new(
+                "global_program",
+                "global_program may not be empty",
+            ));
+        }
+        self.tree_sitter
+            .validate()
+            .map_err(|error| error.within("tree_sitter"))?;
+        self.workspace
+            .validate()
+            .map_err(|error| error.within("workspace"))?;
+        for (language_id, entries) in &self.regex {
+            for (index, entry) in entries.iter().enumerate() {
+                entry
+                    .compile()
+                    .map_err(|error| error.within(format!("regex.{language_id}[{index}]")))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The notice logged when a document holding `text` gets no symbols because it is larger
+    /// than `max_file_size_kb`, or None when it is not.
+    pub fn oversized_notice(&self, text: &str) -> Option<String> {
+        self.max_file_size_kb
+            .filter(|max_file_size_kb| text.len() as u64 > max_file_size_kb * 1024)
+            .map(|max_file_size_kb| {
+                format!("no symbols: the file is larger than {max_file_size_kb}KB")
+            })
+    }
+
+    /// The path of `tags_file` with `substitutions` applied.
+    pub fn tags_file_path(&self, substitutions: &PicklsSubstitutions) -> PathBuf {
+        PathBuf::from(substitutions.apply(&self.tags_file))
+    }
+
+    /// Whether a tags file last modified `age` ago is stale per `tags_file_max_age_secs`.
+    pub fn tags_file_is_stale(&self, age: Duration) -> bool {
+        self.tags_file_max_age_secs
+            .is_some_and(|max_age_secs| age > Duration::from_secs(max_age_secs))
+    }
+
+    /// Whether any symbols may be found, which is when the first of `sources` is not "none".
+    pub fn is_enabled(&self) -> bool {
+        self.sources
+            .first()
+            .is_some_and(|source| *source != PicklsSymbolsSource::None)
+    }
+
+    /// The sources to try for a document, in order, stopping before any "none".
+    pub fn active_sources(&self) -> impl Iterator<Item = &PicklsSymbolsSource> {
+        self.sources
+            .iter()
+            .take_while(|source| **source != PicklsSymbolsSource::None)
+    }
+
+    /// Validate and compile the "regex" source patterns for `language_id`, which are empty when
+    /// it has none. See [`PicklsRegexSymbolConfig::compile`].
+    pub fn compile_regex_symbols(
+        &self,
+        language_id: &str,
+    ) -> Result<Vec<PicklsCompiledRegexSymbol>, PicklsConfigError> {
+        self.regex
+            .get(language_id)
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .map(|(index, entry)| {
+                entry
+                    .compile()
+                    .map_err(|error| error.within(format!("regex.{language_id}[{index}]")))
+            })
+            .collect()
+    }
+
+    /// The symbol kind of the ctags `kind` in `language` (ie: "Python", matched
+    /// case-insensitively), looking up the language-scoped key and then the bare kind, first in
+    /// `kind_map` and then in the built-in [`crate::CTAGS_KIND_MAP`]. Unmapped kinds are
+    /// variables.
+    pub fn symbol_kind(&self, language: Option<&str>, kind: &str) -> PicklsSymbolKind {
+        let overrides = self.kind_map.iter().flat_map(|kind_map| {
+            kind_map
+                .iter()
+                .map(|(key, symbol_kind)| (key.as_str(), *symbol_kind))
+        });
+        lookup_symbol_kind(overrides, language, kind)
+            .or_else(|| builtin_symbol_kind(language, kind))
+            .unwrap_or(PicklsSymbolKind::Variable)
+    }
+
+    /// Whether symbols of the ctags `kind` (ie: "function") are reported, per `include_kinds` and
+    /// `exclude_kinds`.
+    pub fn includes_kind(&self, kind: &str) -> bool {
+        let listed = |kinds: &Option<Vec<String>>| {
+            kinds
+                .as_ref()
+                .map(|kinds| kinds.iter().any(|listed| listed == kind))
+        };
+        listed(&self.include_kinds).unwrap_or(true) && !listed(&self.exclude_kinds).unwrap_or(false)
+    }
+
+    /// The warning to log when `version_output`, the output of running `ctags_program` with
+    /// "--version", shows that it is not universal-ctags, or None when it is. pickls runs this
+    /// check once per `ctags_program`, before first indexing with it.
+    pub fn ctags_version_warning(&self, version_output: &str) -> Option<String> {
+        (!version_output.contains("Universal Ctags")).then(|| {
+            let first_line = version_output.lines().next().unwrap_or_default().trim();
+            format!(
+                "{program} is not universal-ctags ({first_line}); symbols may be missing",
+                program = self.ctags_program
+            )
+        })
+    }
 }
 
 #[derive(Eq, PartialEq, Clone, Debug, Deserialize)]
 pub enum PicklsSymbolsSource {
     #[serde(rename = "universal-ctags")]
     UniversalCtags,
+    /// Query an existing GNU Global index (GTAGS) in the root directory: `global -fx` for
+    /// document symbols and `global -x` for workspace symbols, parsed with
+    /// [`crate::parse_global_output`].
+    #[serde(rename = "gnu-global")]
+    GnuGlobal,
+    /// Parse documents with tree-sitter and extract hierarchical symbols via the queries in
+    /// `tree_sitter`, without any external program.
+    #[serde(rename = "tree-sitter")]
+    TreeSitter,
+    /// Match the lines of documents against the user-defined patterns in `regex`.
+    #[serde(rename = "regex")]
+    Regex,
+    /// Read an existing tags file, (ie: one generated in CI or by a git hook), via `tags_file`.
+    /// Both the classic and extended formats are supported; see
+    /// [`PicklsSymbolsConfig::parse_tags_line`]. Workspace symbols are found by binary search
+    /// when the file is sorted; see [`crate::find_tags_with_prefix`].
+    #[serde(rename = "tags-file")]
+    TagsFile,
+    /// Find no symbols, (ie: to turn off symbols that a shared base configuration enables). The
+    /// sources after it are never tried. As the first source at the top level, pickls advertises
+    /// no document or workspace symbol support unless a language sets its own `symbols`; for a
+    /// language, symbol requests for its files return no results. See
+    /// [`PicklsConfig::advertises_symbols`].
+    #[serde(rename = "none")]
+    None,
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
@@ -41,52 +778,2132 @@ pub struct PicklsLanguageConfig {
     #[serde(default)]
     pub root_markers: Vec<String>,
 
-    /// All the linters you'd like to run on this language. Each linter runs in
-    /// a subprocess group.
-    #[serde(default)]
-    pub linters: Vec<PicklsLinterConfig>,
+    /// All the linters you'd like to run on this language. Each linter runs in
+    /// a subprocess group. An entry may also be the name of a linter definition, (ie:
+    /// "codespell"), which is shorthand for `{"extends": "codespell"}`.
+    #[serde(default, deserialize_with = "deserialize_linters")]
+    pub linters: Vec<PicklsLinterConfig>,
+
+    /// All the formatters you'd like to run (in order) on this language. Note
+    /// that you'll need to configure your editor to invoke its LSP client to
+    /// cause formatting to occur. Successive formatters that set use_stdin will
+    /// have chained pipes from stdout to stdin to eliminate extra copies. If any
+    /// formatter fails or times out, the whole chain is aborted and the buffer is
+    /// left untouched. An entry may also be the name of a formatter definition,
+    /// (ie: "prettier"), which is shorthand for `{"extends": "prettier"}`.
+    #[serde(default, deserialize_with = "deserialize_formatters")]
+    pub formatters: Vec<PicklsFormatterConfig>,
+
+    /// The command that organizes imports for this language, (ie: isort, goimports or `ruff check
+    /// --select I --fix`), run like a formatter outside of the `formatters` chain to serve the
+    /// "source.organizeImports" code action. Like a formatter entry, it may also be the name of a
+    /// formatter definition. When unset, the code action is not offered for this language.
+    /// (Optional)
+    #[serde(default, deserialize_with = "deserialize_organize_imports")]
+    pub organize_imports: Option<PicklsFormatterConfig>,
+
+    /// The most linter subprocesses to run at once for files of this language, with its own pool
+    /// of slots, instead of the top-level `max_concurrent_linters`. (Optional)
+    pub max_concurrent_linters: Option<usize>,
+
+    /// Whether to drop diagnostics that another linter for this language already reported with
+    /// the same range and message, keeping the one from the linter listed first. See
+    /// [`crate::dedupe_diagnostics`]. Defaults to false.
+    #[serde(default = "default_false")]
+    pub dedupe_diagnostics: bool,
+
+    /// Whether to check that the chain of formatters is idempotent, to catch formatters whose
+    /// settings fight each other. After formatting, each formatter is run again on the chain's
+    /// output (via its `check_args`, when set); if any would change it, a warning from
+    /// [`PicklsLanguageConfig::idempotency_warning`] is logged. The first result is used either
+    /// way. Defaults to false.
+    #[serde(default = "default_false")]
+    pub verify_idempotent: bool,
+
+    /// Whether to report the result of formatting as a minimal set of edits covering only the
+    /// changed regions, rather than as a replacement of the whole document, which preserves the
+    /// cursor, folds and marks in most editors. See [`crate::minimal_text_edits`]. Defaults to
+    /// false.
+    #[serde(default = "default_false")]
+    pub minimal_diff: bool,
+
+    /// The line endings of the formatted document, applied to the output of the chain of
+    /// `formatters` before edits are computed, so that a formatter that emits different line
+    /// endings does not rewrite every line. See [`crate::normalize_newlines`]. Defaults to
+    /// "preserve".
+    #[serde(default)]
+    pub newline: PicklsNewline,
+
+    /// Whether the formatted document must end with a line ending (true) or must not (false),
+    /// applied along with `newline`. When unset, the output of the formatters is left as is.
+    /// (Optional)
+    pub ensure_final_newline: Option<bool>,
+
+    /// Whether to format files of this language as they are saved, instead of the top-level
+    /// `format_on_save`. Formatters stay available to explicit formatting requests either way.
+    /// (Optional)
+    pub format_on_save: Option<bool>,
+
+    /// Whether to prefer a project-local copy of each linter's and formatter's `program` over one
+    /// found via `PATH`. When `program` is a bare name, each directory in `local_tool_dirs` is
+    /// searched within the file's directory and each of its ancestors up to the root directory
+    /// found via `root_markers`, nearest first. pickls caches the result per program and root
+    /// directory. Defaults to false.
+    #[serde(default = "default_false")]
+    pub prefer_local_tools: bool,
+
+    /// The project-relative directories searched when `prefer_local_tools` is set, in order.
+    /// Defaults to `["node_modules/.bin", ".venv/bin", "venv/bin"]`.
+    pub local_tool_dirs: Option<Vec<String>>,
+
+    /// How to find symbols in files of this language, replacing the top-level `symbols` as a
+    /// whole rather than field by field. When unset, the top-level `symbols` is used. (Optional)
+    pub symbols: Option<PicklsSymbolsConfig>,
+}
+
+impl PicklsLanguageConfig {
+    /// The chain of formatters to run for `filename`: those that are active per
+    /// [`PicklsFormatterConfig::is_active`] and apply to it per
+    /// [`PicklsFormatterConfig::applies_to`], in order.
+    pub fn active_formatters<'a>(
+        &'a self,
+        filename: &'a Path,
+        root_dir: &'a Path,
+    ) -> impl Iterator<Item = &'a PicklsFormatterConfig> {
+        let dirname = filename.parent().unwrap_or(root_dir);
+        let relative_path = filename.strip_prefix(root_dir).unwrap_or(filename);
+        self.formatters.iter().filter(move |formatter| {
+            formatter.is_active(dirname, root_dir) && formatter.applies_to(relative_path)
+        })
+    }
+
+    /// The `organize_imports` command to run for `filename`, when it is set, active and applies to
+    /// `filename`, in the same way as [`PicklsLanguageConfig::active_formatters`].
+    pub fn active_organize_imports(
+        &self,
+        filename: &Path,
+        root_dir: &Path,
+    ) -> Option<&PicklsFormatterConfig> {
+        let dirname = filename.parent().unwrap_or(root_dir);
+        let relative_path = filename.strip_prefix(root_dir).unwrap_or(filename);
+        self.organize_imports.as_ref().filter(|organize_imports| {
+            organize_imports.is_active(dirname, root_dir)
+                && organize_imports.applies_to(relative_path)
+        })
+    }
+
+    /// The notice logged when formatting `filename`, which holds `text`, is skipped because one of
+    /// its active formatters is oversized per `max_file_size_kb`, or None when none is. See
+    /// `max_file_size_kb` in [`PicklsFormatterConfig`].
+    pub fn oversized_formatters_notice(
+        &self,
+        filename: &Path,
+        root_dir: &Path,
+        text: &str,
+        default_max_file_size_kb: Option<u64>,
+    ) -> Option<String> {
+        self.active_formatters(filename, root_dir)
+            .find_map(|formatter| {
+                formatter
+                    .exceeded_max_file_size(text, default_max_file_size_kb)
+                    .map(|max_file_size_kb| (formatter.display_name(), max_file_size_kb))
+            })
+            .map(|(name, max_file_size_kb)| {
+                format!("not formatting: the file is larger than {max_file_size_kb}KB for {name}")
+            })
+    }
+
+    /// The warning logged when `verify_idempotent` is set and running the formatters named in
+    /// `changed_by` again would change the chain's output, or None when none would.
+    pub fn idempotency_warning(&self, changed_by: &[&str]) -> Option<String> {
+        (!changed_by.is_empty()).then(|| {
+            format!(
+                "formatters are not idempotent: running {} again would change the result",
+                changed_by.join(", ")
+            )
+        })
+    }
+
+    /// Apply `newline` and `ensure_final_newline` to `formatted`, the output of the chain of
+    /// formatters for `original`. See [`crate::normalize_newlines`].
+    pub fn normalize_formatted(&self, original: &str, formatted: &str) -> String {
+        normalize_newlines(original, formatted, self.newline, self.ensure_final_newline)
+    }
+
+    /// The formatters to run for a range request: those that set `range_args`, in order. See
+    /// `range_args` in [`PicklsFormatterConfig`].
+    pub fn range_formatters(&self) -> impl Iterator<Item = &PicklsFormatterConfig> {
+        self.formatters
+            .iter()
+            .filter(|formatter| formatter.range_args.is_some())
+    }
+
+    /// Validate each linter and formatter and `symbols`, and check that no two linters share a
+    /// name.
+    pub fn validate(&self) -> Result<(), PicklsConfigError> {
+        check_concurrency_limit(self.max_concurrent_linters)?;
+        for (index, formatter) in self.formatters.iter().enumerate() {
+            formatter
+                .validate()
+                .map_err(|error| error.within(format!("formatters[{index}]")))?;
+        }
+        if let Some(organize_imports) = &self.organize_imports {
+            organize_imports
+                .validate()
+                .map_err(|error| error.within("organize_imports"))?;
+        }
+        if let Some(symbols) = &self.symbols {
+            symbols
+                .validate()
+                .map_err(|error| error.within("symbols"))?;
+        }
+        let mut names: HashMap<Cow<str>, usize> = HashMap::new();
+        for (index, linter) in self.linters.iter().enumerate() {
+            let location = linter_location(index, linter);
+            linter.validate().map_err(|error| error.within(&location))?;
+            if let Some(other) = names.insert(linter.display_name(), index) {
+                return Err(PicklsConfigError::new(
+                    "name",
+                    format!(
+                        "linter name '{name}' is already used by linters[{other}]",
+                        name = linter.display_name()
+                    ),
+                )
+                .within(location));
+            }
+        }
+        Ok(())
+    }
+
+    /// The project-local copy of `program` to run for a file in `start_dir`, when
+    /// `prefer_local_tools` is set. See [`crate::find_local_program`].
+    pub fn find_local_tool(
+        &self,
+        program: &str,
+        start_dir: &Path,
+        root_dir: &Path,
+    ) -> Option<PathBuf> {
+        if !self.prefer_local_tools {
+            return None;
+        }
+        let dirs: Vec<&str> = match &self.local_tool_dirs {
+            Some(dirs) => dirs.iter().map(String::as_str).collect(),
+            None => DEFAULT_LOCAL_TOOL_DIRS.to_vec(),
+        };
+        find_local_program(program, &dirs, start_dir, root_dir)
+    }
+
+    /// Find likely mistakes in each linter and formatter. See [`PicklsConfig::warnings`].
+    pub fn warnings(&self) -> Vec<PicklsConfigError> {
+        let linter_warnings = self.linters.iter().enumerate().flat_map(|(index, linter)| {
+            linter
+                .warnings()
+                .into_iter()
+                .map(move |warning| warning.within(linter_location(index, linter)))
+        });
+        let formatter_warnings =
+            self.formatters
+                .iter()
+                .enumerate()
+                .flat_map(|(index, formatter)| {
+                    formatter
+                        .warnings()
+                        .into_iter()
+                        .map(move |warning| warning.within(format!("formatters[{index}]")))
+                });
+        let organize_imports_warnings = self.organize_imports.iter().flat_map(|organize_imports| {
+            organize_imports
+                .warnings()
+                .into_iter()
+                .map(|warning| warning.within("organize_imports"))
+        });
+        linter_warnings
+            .chain(formatter_warnings)
+            .chain(organize_imports_warnings)
+            .collect()
+    }
+
+    /// Validate and compile each linter. See [`PicklsLinterConfig::compile`].
+    pub fn compile_linters(&self) -> Result<Vec<PicklsCompiledLinter>, PicklsConfigError> {
+        self.validate()?;
+        self.linters
+            .iter()
+            .enumerate()
+            .map(|(index, linter)| {
+                linter
+                    .compile()
+                    .map_err(|error| error.within(linter_location(index, linter)))
+            })
+            .collect()
+    }
+}
+
+/// The location of a linter within a language for error reporting, identifying it by both index
+/// and name, (ie: `linters[0](ruff)`).
+fn linter_location(index: usize, linter: &PicklsLinterConfig) -> String {
+    format!("linters[{index}]({name})", name = linter.display_name())
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PicklsLinterConfig {
+    /// The name of a built-in linter configuration to start from, (ie: "ruff", "mypy", "eslint",
+    /// "shellcheck" or "golangci-lint"). Any other fields specified alongside `preset` override
+    /// the preset's values. See [`crate::linter_preset_names`].
+    pub preset: Option<String>,
+    /// The name of another linter definition to start from: either an entry in the top-level
+    /// `linter_definitions` or a built-in preset. Only the fields explicitly set on this linter
+    /// override the definition's values. Expanded by [`PicklsConfig::resolve`].
+    pub extends: Option<String>,
+    /// The fields explicitly set in this linter's configuration, which `extends` overlays onto
+    /// the definition it names.
+    #[serde(skip)]
+    pub(crate) fields: Map<String, Value>,
+    /// The name of this linter, used as the source of its diagnostics, in log messages, and in
+    /// diagnostics about the linter itself (ie: timeouts or spawn failures). Must be unique within
+    /// a language. Defaults to the basename of `program`.
+    pub name: Option<String>,
+    /// Whether this linter runs at all. Defaults to true.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Files that must exist for this linter to run, (ie: ".eslintrc*" or "eslint.config.js").
+    /// Glob patterns are supported. The linter runs if any of these files exists in the linted
+    /// file's directory or any directory between it and the root directory found via
+    /// `root_markers`. When empty, the linter always runs.
+    #[serde(default)]
+    pub require_files: Vec<String>,
+    /// Files to lint, as glob patterns matched against the linted file's path relative to the root
+    /// directory found via `root_markers`, (ie: "apps/web/**"). When empty, all files are
+    /// included. Files must match `include_globs` and must not match `exclude_globs`.
+    #[serde(default)]
+    pub include_globs: Vec<String>,
+    /// Files to skip, as glob patterns matched against the linted file's path relative to the root
+    /// directory found via `root_markers`, (ie: "vendor/**" or "**/*_pb2.py"). Exclusions take
+    /// precedence over `include_globs`. Other linters for the language still run.
+    #[serde(default)]
+    pub exclude_globs: Vec<String>,
+    /// If `program` is not an absolute path, the `PATH` will be searched in an OS-defined way.
+    /// Required unless `command` is set.
+    #[serde(default)]
+    pub program: String,
+    /// Whether to run `command` through the top-level `shell` instead of running `program`
+    /// directly. Useful for pipelines, (ie: "golangci-lint run | grep -v _test.go"). Quoting
+    /// within `command` is the user's responsibility, since substitutions are made into the
+    /// command string before the shell sees it. When `use_stdin` is set, the file contents are
+    /// piped to the shell's stdin. Defaults to false.
+    #[serde(default = "default_false")]
+    pub shell: bool,
+    /// The command to run, as a single string. When `shell` is set, it is passed to the shell as
+    /// is. Otherwise it is split into `program` and `args` by [`crate::split_command`], as a
+    /// shorthand for setting them separately, (ie: "ruff check --output-format concise -").
+    /// Substitutions apply to each word after splitting, so a "$filename" containing spaces
+    /// remains a single argument. Mutually exclusive with `args`, and with `program` unless
+    /// `shell` is set.
+    pub command: Option<String>,
+    /// Arguments to pass to `program`. Use "$filename" wherever the absolute path to the real filename should go.
+    /// Use "$tmpfilename" where Pickls should inject a temp file (if the linter only accepts file
+    /// input). See [`crate::SUBSTITUTION_VARIABLES`] for the other available variables, like "$rootdir".
+    #[serde(default = "Vec::new")]
+    pub args: Vec<String>,
+    /// The suffix of the temp file substituted for "$tmpfilename", (ie: ".tsx" or "_test.go").
+    /// Defaults to the real file's extension, so that tools which key their behavior off of the
+    /// extension treat the temp file like the real one.
+    pub tmpfile_suffix: Option<String>,
+    /// Whether to create the temp file next to the real file instead of in the system temp
+    /// directory, for tools that discover their configuration from sibling files. The temp file
+    /// is removed after every run, including failed and timed-out runs. Defaults to false.
+    #[serde(default = "default_false")]
+    pub tmpfile_in_source_dir: bool,
+    /// A command to run `program` through, (ie: `["poetry", "run"]` or `["npx"]`). It is
+    /// prepended to the command line after substitutions are applied to it and to `args`, and
+    /// the `PATH` search applies to its first element instead of `program`.
+    #[serde(default)]
+    pub prefix_command: Vec<String>,
+    /// Directories to search for `program` (and `prefix_command`) before the top-level
+    /// `extra_path` and the inherited `PATH`, (ie: `["$rootdir/.venv/bin"]`). See
+    /// [`PicklsLinterConfig::search_path`].
+    #[serde(default)]
+    pub extra_path: Vec<String>,
+    /// Environment variables to set for `program`, merged over the inherited environment. Values
+    /// undergo the same substitutions as `args`.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Environment variables to remove from the environment inherited by `program`. Applied
+    /// before `env`.
+    #[serde(default)]
+    pub env_remove: Vec<String>,
+    /// The working directory for `program`. Relative paths are resolved against the root
+    /// directory found via `root_markers`, and the value undergoes the same substitutions as
+    /// `args`. If the resolved directory does not exist, the linter is skipped with a logged
+    /// warning. Defaults to the root directory.
+    pub cwd: Option<String>,
+    /// Whether to use stdin to push the contents of the file to `program` or to rely on the usage
+    /// of "$filename" arg. Defaults to false.
+    #[serde(default = "default_false")]
+    pub use_stdin: bool,
+    /// What to do when the program to run (`program`, or the first element of `prefix_command`)
+    /// cannot be found: "ignore" skips the linter silently, "warn" logs a warning, and
+    /// "diagnostic" also reports a single hint diagnostic at line 0 of each affected file (see
+    /// [`PicklsLinterConfig::missing_program_message`]). The program is looked up once per session.
+    /// Defaults to "warn".
+    #[serde(default)]
+    pub on_missing: PicklsOnMissing,
+    /// How to install the program when it is missing, included in the warning and diagnostic
+    /// about it, (ie: "install with `pipx install ruff`"). (Optional)
+    pub install_hint: Option<String>,
+    /// Arguments that make `program` print its version, (ie: `["--version"]`). When set along
+    /// with `min_version`, the version is checked before the linter's first run, and the result
+    /// is cached per resolved program path. (Optional)
+    #[serde(default)]
+    pub version_args: Vec<String>,
+    /// Regex with one capture group that extracts the version from the output of
+    /// `version_args`. Defaults to the first dotted number, (ie: "0.4.10").
+    pub version_pattern: Option<String>,
+    /// The oldest version of `program` that this configuration works with, (ie: "0.4"). Older
+    /// versions disable the linter with a logged warning and a single informational diagnostic
+    /// (see [`PicklsLinterConfig::check_version`]). Requires `version_args`. (Optional)
+    pub min_version: Option<String>,
+    /// The order in which runs waiting for a slot under `max_concurrent_linters` start, highest
+    /// first, so that cheap linters can jump the queue. Runs of equal priority start in the order
+    /// they were triggered. Defaults to 0.
+    pub priority: Option<i32>,
+    /// Whether this linter checks a single file per run ("file") or an entire project per run
+    /// ("workspace"), (ie: `cargo clippy` or `tsc --noEmit`). Defaults to "file".
+    #[serde(default)]
+    pub scope: PicklsLinterScope,
+    /// How long to wait for `program` to complete before killing its subprocess group. A
+    /// timed-out run reports a single informational diagnostic instead of stale results. Set to
+    /// null to wait indefinitely. Defaults to 30000ms.
+    #[serde(default = "default_linter_timeout_ms")]
+    pub timeout_ms: Option<u64>,
+    /// Resource limits for `program`, to keep a misbehaving linter from taking down the machine.
+    /// (Optional)
+    pub limits: Option<PicklsProcessLimits>,
+    /// Run `program` inside a container instead of on the host. (Optional)
+    pub container: Option<PicklsContainerConfig>,
+    /// The document events that cause this linter to run: any of "open", "save" and "change".
+    /// Defaults to all three.
+    #[serde(default = "default_run_on")]
+    pub run_on: HashSet<PicklsLinterTrigger>,
+    /// How long to wait after a change before running this linter. Runs triggered by a change
+    /// that is superseded by another change within this window are cancelled. Only applies to
+    /// "change" triggers. (Optional)
+    pub debounce_ms: Option<u64>,
+    /// Exit codes that indicate `program` ran successfully, (ie: `[0, 1]` for a linter that exits
+    /// with 1 when it finds problems). When `program` exits with any other code (or is killed by
+    /// a signal), its output is not parsed, and a single error diagnostic containing the first
+    /// lines of its stderr is reported instead. When not specified, output is always parsed.
+    pub success_exit_codes: Option<Vec<i32>>,
+    /// How to interpret the output of `program`. Defaults to "regex".
+    #[serde(default)]
+    pub format: PicklsLinterFormat,
+    /// Regex from which to pull diagnostics from stdout of `program`. The pattern is matched on
+    /// every line of output (or the entire output when `multiline` is set). When there is a match,
+    /// a diagnostic is produced. When `format` is "regex", exactly one of `pattern` or
+    /// `errorformat` is required.
+    pub pattern: Option<String>,
+    /// Regex for lines of output to skip entirely, (ie: summary footers or progress chatter).
+    /// Checked before `pattern` or `errorformat`, on whichever stream is being scanned. When
+    /// `multiline` is set, matches that start on an ignored line are dropped. (Optional)
+    pub ignore_pattern: Option<String>,
+    /// Regex for lines that continue the message of the preceding diagnostic, (ie: mypy's
+    /// "note: ..." lines or indented explanations). After a line matches `pattern`, each following
+    /// line that matches `continuation_pattern` is appended to that diagnostic's description on a
+    /// new line, until a line does not match. Continuation lines are consumed: they never produce
+    /// diagnostics of their own, nor serve as the description line of a later diagnostic.
+    /// (Optional)
+    pub continuation_pattern: Option<String>,
+    /// The most continuation lines to append to a single diagnostic. Further continuation lines
+    /// are still consumed but dropped. Defaults to unlimited.
+    pub max_continuation_lines: Option<usize>,
+    /// How to recognize secondary locations of the preceding diagnostic, (ie: "first defined
+    /// here"), which are attached to it as related information. (Optional)
+    pub related: Option<PicklsRelatedConfig>,
+    /// Regex for lines that annotate the most recent diagnostic matched by `pattern`, (ie:
+    /// clang's and gcc's "note:" lines). Groups are looked up via the same `filename_match`,
+    /// `line_match`, `start_col_match` and `description_match` as `pattern`, so named groups work
+    /// best. A note whose line group participates becomes related information on the diagnostic;
+    /// otherwise its description (or the whole line) is appended to the diagnostic's message on
+    /// a new line. Notes seen before any diagnostic are dropped. Checked before `pattern`.
+    /// (Optional)
+    pub note_pattern: Option<String>,
+    /// Vim-style errorformat strings to use instead of `pattern`, (ie: "%f:%l:%c: %t%*[^:]: %m").
+    /// Each line of output is matched against each errorformat in turn, and the first match wins.
+    /// Multi-line diagnostics may be described with the `%E`, `%W`, `%C` and `%Z` prefixes. The
+    /// `*_match` fields are not used with errorformats; the severity letter from `%t` maps "e" to
+    /// error, "w" to warning, and "i" and "n" to information, and is otherwise resolved just like
+    /// `severity_match`. See [`PicklsErrorFormat`] for the supported items.
+    pub errorformat: Option<Vec<String>>,
+    /// Regex group (1-indexed or named) that matches the filename of the diagnostic.
+    pub filename_match: Option<PicklsCaptureGroup>,
+    /// What to do with diagnostics whose captured filename is not the file being linted: "drop"
+    /// them, "publish" them to the files they name, or "remap_to_current" to attribute them to
+    /// the file being linted. Relative filenames are resolved per `path_resolution`. Linters with
+    /// a "workspace" `scope` always publish. Defaults to "drop".
+    #[serde(default)]
+    pub foreign_files: PicklsForeignFiles,
+    /// How captured filenames are resolved and compared against the file being linted.
+    #[serde(default)]
+    pub path_resolution: PicklsPathResolution,
+    /// Regex group (1-indexed or named) that matches the line number of the diagnostic. When not
+    /// set, or when the group does not participate in a match, the diagnostic is anchored to the
+    /// start of the file with a zero-length range (or spans the file when `whole_file` is set),
+    /// which suits file-level checks like `gofmt -l`. (Optional)
+    pub line_match: Option<PicklsCaptureGroup>,
+    /// Whether diagnostics without a line number span the entire document instead of the
+    /// zero-length range at its start. Defaults to false.
+    #[serde(default = "default_false")]
+    pub whole_file: bool,
+    /// Regex group (1-indexed or named) that matches the ending line number of the diagnostic.
+    /// When the group does not participate in a match, the diagnostic ends on its starting line.
+    /// (Optional)
+    pub end_line_match: Option<PicklsCaptureGroup>,
+    /// Regex group (1-indexed or named) that matches the starting column number of the
+    /// diagnostic. (Optional)
+    pub start_col_match: Option<PicklsCaptureGroup>,
+    /// Regex group (1-indexed or named) that matches the ending column number of the diagnostic.
+    /// The ending column is inclusive: the diagnostic covers the character at that column. When
+    /// the group does not participate in a match, the diagnostic covers the single character at
+    /// its starting column. (Optional)
+    pub end_col_match: Option<PicklsCaptureGroup>,
+    /// Whether the lines matched by `line_match` and `end_line_match` count from 0 or 1. Lines
+    /// that would fall before the start of the document after adjustment (ie: a captured 0 when
+    /// `line_base` is 1) are clamped to the first line. Defaults to 1.
+    #[serde(default = "default_one_based")]
+    pub line_base: u8,
+    /// Whether the columns matched by `start_col_match` and `end_col_match` count from 0 or 1.
+    /// When the starting column group does not participate in a match, the diagnostic starts at
+    /// the beginning of the line. Defaults to 1.
+    #[serde(default = "default_one_based")]
+    pub column_base: u8,
+    /// The unit in which `program` reports columns: "utf8-bytes", "chars" or "utf16". Captured
+    /// columns are converted to LSP positions against the text of the line they refer to. Defaults
+    /// to "chars".
+    #[serde(default)]
+    pub column_unit: PicklsColumnUnit,
+    /// Regex group (1-indexed or named) that matches the severity of the alert. Unknown
+    /// severities will resolve to `default_severity`.
+    pub severity_match: Option<PicklsCaptureGroup>,
+    /// Translations from captured severities to diagnostic severities, (ie: `{"C":
+    /// "information", "refactor": "hint"}`). Lookups are case-insensitive. Captured severities
+    /// that are not in the map fall back to the usual interpretation. (Optional)
+    pub severity_map: Option<HashMap<String, PicklsSeverity>>,
+    /// The severity to use when `severity_match` is not set, when its group does not participate
+    /// in a match, or when the captured severity is not recognized. Defaults to warning.
+    pub default_severity: Option<PicklsSeverity>,
+    /// Severities for particular diagnostic codes, (ie: `{"F821": "error", "E5*": "hint"}`).
+    /// Keys are exact codes or glob patterns; an exact key wins over patterns, and longer
+    /// patterns win over shorter ones. Takes precedence over every other way of determining
+    /// severity. See [`PicklsLinterConfig::resolve_severity`]. (Optional)
+    pub code_severity_overrides: Option<HashMap<String, PicklsSeverity>>,
+    /// Diagnostic codes to drop rather than report, as exact codes or glob patterns, (ie:
+    /// "SC1091" or "no-console"). The number of dropped diagnostics is logged after each run
+    /// (see [`PicklsLinterConfig::suppression_log_message`]).
+    #[serde(default)]
+    pub ignore_codes: Vec<String>,
+    /// Regexes for diagnostic messages to drop rather than report, for tools that don't expose
+    /// codes. A diagnostic is dropped if any of them matches anywhere in its message.
+    #[serde(default)]
+    pub ignore_messages: Vec<String>,
+    /// Regex replacements applied in order to each diagnostic's message, (ie: `{"pattern":
+    /// "^error TS\\d+: ", "replace": ""}`). `ignore_messages` is matched against the message
+    /// before any rewrites. See [`PicklsCompiledLinter::rewrite_message`].
+    #[serde(default)]
+    pub message_rewrites: Vec<PicklsMessageRewrite>,
+    /// Text to prepend to each diagnostic's message, after `message_rewrites`. (Optional)
+    pub message_prefix: Option<String>,
+    /// Text to append to each diagnostic's message, after `message_rewrites`. (Optional)
+    pub message_suffix: Option<String>,
+    /// Regex group (1-indexed or named) that matches the description of the diagnostic. Use -1 to
+    /// indicate that the description is on the _previous_ line of input, or -N for the line N
+    /// lines before the matching line.
+    pub description_match: Option<PicklsDescriptionMatch>,
+    /// The line of output holding the description of the diagnostic, relative to the matching
+    /// line, (ie: 1 for a message printed after a caret line, or -2 for two lines before). The
+    /// entire line is used as the description. Mutually exclusive with `description_match`.
+    /// (Optional)
+    pub description_line_offset: Option<isize>,
+    /// Regex group (1-indexed or named) that matches the rule code of the diagnostic, (ie: "E501"
+    /// or "SC2086"). When the group is empty or does not participate in a match, the diagnostic
+    /// has no code. (Optional)
+    pub code_match: Option<PicklsCaptureGroup>,
+    /// Regex group (1-indexed or named) that matches a documentation link for the diagnostic,
+    /// used as its code description. Captures that are not URLs are dropped. (Optional)
+    pub url_match: Option<PicklsCaptureGroup>,
+    /// A documentation link for the diagnostic's code, used when `url_match` is not set or does
+    /// not capture a URL. "{{code}}" is replaced with the captured code, (ie:
+    /// `https://docs.astral.sh/ruff/rules/{{code}}`). (Optional)
+    pub code_url_template: Option<String>,
+    /// Regex group (1-indexed or named) that matches a string to translate into diagnostic tags
+    /// via `tags_map`. (Optional)
+    pub tags_match: Option<PicklsCaptureGroup>,
+    /// Translations from strings captured by `tags_match` to diagnostic tags, (ie:
+    /// `{"unused": "unnecessary", "deprecated": "deprecated"}`). Lookups are case-insensitive and
+    /// unknown strings add no tag. (Optional)
+    pub tags_map: Option<HashMap<String, PicklsDiagnosticTag>>,
+    /// Diagnostic tags to attach based on the diagnostic's code, (ie: `{"F401": "unnecessary"}`
+    /// to fade out ruff's unused imports). (Optional)
+    pub code_tags: Option<HashMap<String, PicklsDiagnosticTag>>,
+    /// Regex group (1-indexed or named) that matches replacement text for the diagnostic's range.
+    /// When captured, the diagnostic offers a quick fix that replaces its range with this text.
+    /// (Optional)
+    pub fix_text_match: Option<PicklsCaptureGroup>,
+    /// Regex group (1-indexed or named) whose participation in a match marks the diagnostic as
+    /// fixable, (ie: ruff's `[*]` marker). (Optional)
+    pub fixable_match: Option<PicklsCaptureGroup>,
+    /// How to run this linter's autofix, (ie: `ruff check --fix`). When present, a "fix all"
+    /// code action is offered for files this linter applies to. (Optional)
+    pub fix: Option<PicklsFixConfig>,
+    /// How to cache this linter's diagnostics for unchanged documents. Linters with a
+    /// "workspace" `scope` are never cached, since their output depends on files other than the
+    /// document. Defaults to the top-level `lint_cache`, or no caching. (Optional)
+    pub cache: Option<PicklsLintCacheConfig>,
+    /// How to keep `program` running between checks, for tools with a daemon mode like `dmypy`.
+    /// When present, checks are requested from the daemon instead of spawning `program` with
+    /// `args` for every run. (Optional)
+    pub daemon: Option<PicklsDaemonConfig>,
+    /// The most diagnostics to report from a single run. Once exceeded, parsing stops and a single
+    /// informational diagnostic (see [`PicklsLinterConfig::truncation_notice`]) is appended at
+    /// line 0 so that it's clear the results are incomplete. Diagnostics dropped for other files
+    /// or via `ignore_codes` do not count. Defaults to unlimited.
+    pub max_diagnostics: Option<usize>,
+    /// Skip this linter for documents larger than this many kilobytes, (ie: generated or bundled
+    /// files). The size of the in-memory buffer is checked, not the file on disk, so unsaved
+    /// content counts. Defaults to the top-level `max_file_size_kb`, or unlimited.
+    pub max_file_size_kb: Option<u64>,
+    /// Whether to report a single hint diagnostic (see [`PicklsLinterConfig::oversized_notice`])
+    /// when this linter is skipped because of `max_file_size_kb`. Defaults to false.
+    #[serde(default = "default_false")]
+    pub report_oversized: bool,
+    /// Which output streams of `program` to scan for diagnostics: "stdout", "stderr" or "both".
+    /// The older `use_stderr: true` is accepted as "stderr", and `use_stderr: false` as "stdout".
+    /// Defaults to "stdout".
+    #[serde(
+        default,
+        alias = "use_stderr",
+        deserialize_with = "deserialize_output_source"
+    )]
+    pub output_source: PicklsOutputSource,
+    /// How the bytes written by `program` are decoded before they are parsed: "utf-8",
+    /// "utf-8-lossy" or "latin-1". Under "utf-8", output that is not valid UTF-8 is reported
+    /// as an error of this linter instead of being parsed. Defaults to "utf-8-lossy".
+    #[serde(default)]
+    pub output_encoding: PicklsOutputEncoding,
+    /// Whether to match `pattern` against the entire output of `program` rather than line by
+    /// line. When true, `pattern` is compiled such that `^` and `$` match at line boundaries and
+    /// `.` matches newlines, and each non-overlapping match produces a diagnostic. Useful for
+    /// tools like `cargo check` and `mypy` that spread a diagnostic across several lines. Since
+    /// they are matched line by line, `continuation_pattern`, `note_pattern` and `related` cannot
+    /// be used with it. Defaults to false.
+    #[serde(default = "default_false")]
+    pub multiline: bool,
+    /// Dotted path to the array of diagnostics within JSON output, (ie: "results"). Use a `[]`
+    /// segment to flatten nested arrays, (ie: "[].messages" for eslint, which reports an array of
+    /// files, each containing an array of messages). When not specified, the output is expected to
+    /// be a top-level array of diagnostics. Only used when `format` is "json".
+    pub items_path: Option<String>,
+    /// Path within each JSON diagnostic item to the filename of the diagnostic.
+    pub file_path: Option<String>,
+    /// Path to the line number of the diagnostic. Required when `format` is "json".
+    pub line_path: Option<String>,
+    /// Path to the ending line number of the diagnostic. (Optional)
+    pub end_line_path: Option<String>,
+    /// Path to the starting column number of the diagnostic. (Optional)
+    pub start_col_path: Option<String>,
+    /// Path to the ending column number of the diagnostic. (Optional)
+    pub end_col_path: Option<String>,
+    /// Path to the severity of the diagnostic, resolved just like `severity_match`.
+    pub severity_path: Option<String>,
+    /// Path to the description of the diagnostic.
+    pub message_path: Option<String>,
+    /// Path to the rule code of the diagnostic. (Optional)
+    pub code_path: Option<String>,
+}
+
+/// A regex replacement applied to diagnostic messages.
+#[derive(Eq, PartialEq, Clone, Debug, Deserialize)]
+pub struct PicklsMessageRewrite {
+    /// The regex to replace every match of.
+    pub pattern: String,
+    /// The replacement, which may refer to groups of `pattern`, (ie: "$1" or "${name}").
+    /// Defaults to "", removing the matches.
+    #[serde(default)]
+    pub replace: String,
+}
+
+/// Resource limits applied to a spawned subprocess. On Unix, `max_memory_mb` and
+/// `cpu_time_secs` are applied via `setrlimit` and `nice` via `nice(2)`; they are ignored on
+/// platforms without support. A subprocess that exceeds a limit has its process group killed, and
+/// is reported just like a timeout.
+#[derive(Eq, PartialEq, Clone, Debug, Deserialize)]
+pub struct PicklsProcessLimits {
+    /// The most address space the subprocess may use, in megabytes. (Optional)
+    pub max_memory_mb: Option<u64>,
+    /// The most CPU time the subprocess may use, in seconds. (Optional)
+    pub cpu_time_secs: Option<u64>,
+    /// The niceness to run the subprocess with, from -20 (highest priority) to 19 (lowest).
+    /// Negative values usually require elevated privileges. (Optional)
+    pub nice: Option<i8>,
+}
+
+impl PicklsProcessLimits {
+    /// Check that each limit is in range.
+    pub fn validate(&self) -> Result<(), PicklsConfigError> {
+        for (field, limit) in [
+            ("max_memory_mb", self.max_memory_mb),
+            ("cpu_time_secs", self.cpu_time_secs),
+        ] {
+            if limit == Some(0) {
+                return Err(PicklsConfigError::new(
+                    field,
+                    format!("{field} must be at least 1"),
+                ));
+            }
+        }
+        match self.nice {
+            Some(nice) if !(-20..=19).contains(&nice) => Err(PicklsConfigError::new(
+                "nice",
+                format!("nice must be between -20 and 19 but nice = {nice}"),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// How to run a linter inside a Docker or Podman container. The command line becomes the output
+/// of [`PicklsContainerConfig::wrapper`] followed by the linter's usual command line, which then
+/// runs inside the container. Paths substituted into the command line are translated through
+/// `workdir_map` (see [`PicklsContainerConfig::container_substitutions`]), while the paths of
+/// diagnostics are translated back, so `path_resolution` sees host paths. `use_stdin` works as
+/// usual. A linter using "$tmpfilename" must also set `tmpfile_in_source_dir`, so that the temp
+/// file is within a mapped directory.
+#[derive(Eq, PartialEq, Clone, Debug, Deserialize)]
+pub struct PicklsContainerConfig {
+    /// The container engine to run: "docker" or "podman". Defaults to "docker".
+    #[serde(default)]
+    pub engine: PicklsContainerEngine,
+    /// The name of the running container to `exec` in, or the image to `run`, depending on
+    /// `mode`, (ie: "devbox").
+    pub image_or_name: String,
+    /// Whether to "exec" in a running container or to "run" a fresh container from an image,
+    /// removing it afterward. Defaults to "exec".
+    #[serde(default)]
+    pub mode: PicklsContainerMode,
+    /// Host directories and the paths they are mounted at within the container, (ie:
+    /// `{"$rootdir": "/workspace"}`). Host directories may use substitutions. With "run", each is
+    /// also bind-mounted. Required when the linter's command line uses a path variable like
+    /// "$filename".
+    #[serde(default)]
+    pub workdir_map: HashMap<String, String>,
+}
+
+/// A container engine, which are invoked identically.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PicklsContainerEngine {
+    #[default]
+    Docker,
+    Podman,
+}
+
+/// How a linter's container is entered.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PicklsContainerMode {
+    /// `<engine> exec` in an already-running container.
+    #[default]
+    Exec,
+    /// `<engine> run --rm` a new container from an image.
+    Run,
+}
+
+/// The substitution variables holding host paths, which must be translated for a container.
+const PATH_SUBSTITUTION_VARIABLES: &[&str] = &["filename", "tmpfilename", "dirname", "rootdir"];
+
+impl PicklsContainerConfig {
+    /// Check the container's settings. `path_variable` is the first field of the linter and the
+    /// path variable it uses, if any, which requires a `workdir_map`.
+    pub fn validate(&self, path_variable: Option<(&str, &str)>) -> Result<(), PicklsConfigError> {
+        if self.image_or_name.is_empty() {
+            return Err(PicklsConfigError::new(
+                "image_or_name",
+                "image_or_name must not be empty",
+            ));
+        }
+        if let Some(path) = self
+            .workdir_map
+            .values()
+            .find(|path| !path.starts_with('/'))
+        {
+            return Err(PicklsConfigError::new(
+                "workdir_map",
+                format!("container path '{path}' must be absolute"),
+            ));
+        }
+        match path_variable {
+            Some((field, variable)) if self.workdir_map.is_empty() => Err(PicklsConfigError::new(
+                "workdir_map",
+                format!("workdir_map is required since {field} uses ${variable}"),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Translate a host path to its path within the container, via the longest matching entry of
+    /// `workdir_map`. Returns None for paths outside every mapped directory.
+    pub fn map_path(&self, path: &Path, substitutions: &PicklsSubstitutions) -> Option<PathBuf> {
+        self.workdir_map
+            .iter()
+            .filter_map(|(host, container)| {
+                let host = substitutions.apply(host);
+                let rest = path.strip_prefix(&host).ok()?;
+                Some((host.len(), join_relative(Path::new(container), rest)))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, path)| path)
+    }
+
+    /// Translate a path within the container (ie: from the linter's output) back to the host,
+    /// via the longest matching entry of `workdir_map`. Other paths are returned as they are.
+    pub fn unmap_path(&self, path: &Path, substitutions: &PicklsSubstitutions) -> PathBuf {
+        self.workdir_map
+            .iter()
+            .filter_map(|(host, container)| {
+                let rest = path.strip_prefix(container).ok()?;
+                Some((
+                    container.len(),
+                    join_relative(Path::new(&substitutions.apply(host)), rest),
+                ))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map_or_else(|| path.to_path_buf(), |(_, path)| path)
+    }
+
+    /// `substitutions` with each host path translated by [`PicklsContainerConfig::map_path`].
+    /// Paths outside every mapped directory are left as they are.
+    pub fn container_substitutions(
+        &self,
+        substitutions: &PicklsSubstitutions,
+    ) -> PicklsSubstitutions {
+        let map = |value: &Option<String>| {
+            value.as_ref().map(|value| {
+                self.map_path(Path::new(value), substitutions)
+                    .map_or_else(|| value.clone(), |path| path.to_string_lossy().into_owned())
+            })
+        };
+        PicklsSubstitutions {
+            filename: map(&substitutions.filename),
+            tmpfilename: map(&substitutions.tmpfilename),
+            rootdir: map(&substitutions.rootdir),
+            dirname: map(&substitutions.dirname),
+            ..substitutions.clone()
+        }
+    }
+
+    /// The command line that enters the container, to be followed by the linter's own. `cwd` is
+    /// the host working directory, which is translated for the container when mapped.
+    pub fn wrapper(
+        &self,
+        use_stdin: bool,
+        cwd: Option<&Path>,
+        substitutions: &PicklsSubstitutions,
+    ) -> Vec<String> {
+        let engine = match self.engine {
+            PicklsContainerEngine::Docker => "docker",
+            PicklsContainerEngine::Podman => "podman",
+        };
+        let mut wrapper = vec![engine.to_string()];
+        match self.mode {
+            PicklsContainerMode::Exec => wrapper.push("exec".to_string()),
+            PicklsContainerMode::Run => {
+                wrapper.extend(["run".to_string(), "--rm".to_string()]);
+                let mut mounts: Vec<(&String, &String)> = self.workdir_map.iter().collect();
+                mounts.sort();
+                for (host, container) in mounts {
+                    wrapper.push("-v".to_string());
+                    wrapper.push(format!("{}:{container}", substitutions.apply(host)));
+                }
+            }
+        }
+        if use_stdin {
+            wrapper.push("-i".to_string());
+        }
+        if let Some(workdir) = cwd.and_then(|cwd| self.map_path(cwd, substitutions)) {
+            wrapper.push("-w".to_string());
+            wrapper.push(workdir.to_string_lossy().into_owned());
+        }
+        wrapper.push(self.image_or_name.clone());
+        wrapper
+    }
+}
+
+/// `base` joined with `rest`, without a trailing separator when `rest` is empty.
+fn join_relative(base: &Path, rest: &Path) -> PathBuf {
+    match rest.as_os_str().is_empty() {
+        true => base.to_path_buf(),
+        false => base.join(rest),
+    }
+}
+
+/// How to run a linter as a long-lived daemon. The daemon is started by running the linter's
+/// `program` with `start_args` before the first check, and is shut down after `idle_timeout_ms`
+/// without checks (or when pickls exits). Checks are requested in one of two ways:
+///
+/// - With `check_args`, each check runs `program` with `check_args` as a normal run, (ie:
+///   `dmypy check $filename` against the server started by `dmypy start`). `start_args` is
+///   expected to exit once the daemon is ready, and `stop_args`, if present, is run to shut it
+///   down.
+/// - With `check_line`, the process started with `start_args` is itself the daemon. Each check
+///   writes `check_line` to its stdin, and its stdout up to and including the next line that
+///   matches `response_end_pattern` is that check's output. Closing stdin shuts it down.
+///
+/// Either way, the output of each check is parsed just like the output of a normal run. If the
+/// daemon exits unexpectedly, it is restarted on the next check.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PicklsDaemonConfig {
+    /// Arguments to pass to `program` to start the daemon, (ie: `["start"]`).
+    #[serde(default)]
+    pub start_args: Vec<String>,
+    /// Arguments to pass to `program` to request a check. Mutually exclusive with
+    /// `check_line`. Supports the same substitutions as the linter's `args`.
+    pub check_args: Option<Vec<String>>,
+    /// A line to write to the daemon's stdin to request a check, (ie: `"check $filename"`).
+    /// Mutually exclusive with `check_args`. Supports the same substitutions as the linter's
+    /// `args`.
+    pub check_line: Option<String>,
+    /// Regex matching the line that ends the daemon's response to a `check_line`. Required with
+    /// `check_line`.
+    pub response_end_pattern: Option<String>,
+    /// Arguments to pass to `program` to stop a daemon started for `check_args`, (ie:
+    /// `["stop"]`). (Optional)
+    pub stop_args: Option<Vec<String>>,
+    /// How long the daemon may go without a check before it is shut down. Set to null to keep it
+    /// running until pickls exits. Defaults to 600000ms.
+    #[serde(default = "default_daemon_idle_timeout_ms")]
+    pub idle_timeout_ms: Option<u64>,
+}
+
+impl PicklsDaemonConfig {
+    /// The line to write to the daemon's stdin to request a check, (ie: "check /repo/app.py\n"):
+    /// `check_line` with `substitutions` applied and a trailing newline. None with `check_args`.
+    pub fn request_line(&self, substitutions: &PicklsSubstitutions) -> Option<String> {
+        let line = substitutions.apply(self.check_line.as_deref()?);
+        Some(format!("{line}\n"))
+    }
+
+    /// Compile `response_end_pattern`, if present.
+    pub fn response_end_regex(&self) -> Result<Option<Regex>, PicklsConfigError> {
+        compile_optional("response_end_pattern", self.response_end_pattern.as_deref())
+    }
+
+    /// Check that exactly one way of requesting checks is configured.
+    pub fn validate(&self) -> Result<(), PicklsConfigError> {
+        let error = match (&self.check_args, &self.check_line) {
+            (Some(_), Some(_)) => Some((
+                "check_line",
+                "check_args and check_line are mutually exclusive",
+            )),
+            (None, None) => Some(("check_args", "one of check_args or check_line is required")),
+            (None, Some(_)) if self.response_end_pattern.is_none() => Some((
+                "response_end_pattern",
+                "response_end_pattern is required with check_line",
+            )),
+            (Some(_), None) if self.response_end_pattern.is_some() => Some((
+                "response_end_pattern",
+                "response_end_pattern is only used with check_line",
+            )),
+            _ => None,
+        };
+        if let Some((field, message)) = error {
+            return Err(PicklsConfigError::new(field, message));
+        }
+        self.response_end_regex()?;
+        Ok(())
+    }
+}
+
+fn default_daemon_idle_timeout_ms() -> Option<u64> {
+    Some(DEFAULT_DAEMON_IDLE_TIMEOUT_MS)
+}
+
+/// An autofix invocation of a linter's `program`, run with its `prefix_command`, `env` and `cwd`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PicklsFixConfig {
+    /// Arguments to pass to `program` to apply fixes, (ie: `["check", "--fix", "-"]`). These
+    /// undergo the same substitutions as the linter's `args`.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Whether to push the contents of the buffer to `program` via stdin and replace the buffer
+    /// with its stdout. When false, `program` fixes the file in place, so `args` must include
+    /// "$filename", and the file is re-read afterward. Defaults to true.
+    #[serde(default = "default_true")]
+    pub use_stdin: bool,
+}
+
+impl PicklsFixConfig {
+    /// Check that a fix that edits the file in place is told which file to edit.
+    pub fn validate(&self) -> Result<(), PicklsConfigError> {
+        if !self.use_stdin && !self.args.iter().any(|arg| arg.contains("$filename")) {
+            return Err(PicklsConfigError::new(
+                "args",
+                "args must include \"$filename\" when use_stdin is false",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Describes lines of linter output that carry a secondary location for the most recent
+/// diagnostic. Lines that match `pattern` after a diagnostic attach to it as related information.
+/// Lines that match before any diagnostic has been seen are dropped.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PicklsRelatedConfig {
+    /// Regex that matches a line of output carrying a secondary location.
+    pub pattern: String,
+    /// Regex group (1-indexed or named) that matches the filename of the location. Relative
+    /// filenames are resolved per the linter's `path_resolution`. Defaults to the filename of the
+    /// diagnostic.
+    pub filename_match: Option<PicklsCaptureGroup>,
+    /// Regex group (1-indexed or named) that matches the line number of the location.
+    pub line_match: PicklsCaptureGroup,
+    /// Regex group (1-indexed or named) that matches the starting column number of the location.
+    /// (Optional)
+    pub start_col_match: Option<PicklsCaptureGroup>,
+    /// Regex group (1-indexed or named) that matches the message of the location. (Optional)
+    pub description_match: Option<PicklsCaptureGroup>,
+}
+
+impl PicklsRelatedConfig {
+    /// Compile `pattern`.
+    pub fn regex(&self) -> Result<Regex, PicklsConfigError> {
+        Regex::new(&self.pattern)
+            .map_err(|error| PicklsConfigError::new("pattern", error.to_string()))
+    }
+
+    /// Check that `pattern` compiles and that the capture groups referenced exist within it.
+    pub fn validate(&self) -> Result<(), PicklsConfigError> {
+        let regex = self.regex()?;
+        self.line_match.validate("line_match", &regex)?;
+        let groups = [
+            ("filename_match", &self.filename_match),
+            ("start_col_match", &self.start_col_match),
+            ("description_match", &self.description_match),
+        ];
+        for (field, group) in groups {
+            if let Some(group) = group {
+                group.validate(field, &regex)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How much a single run of a linter checks.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PicklsLinterScope {
+    /// Each run checks the file that triggered it.
+    #[default]
+    File,
+    /// Each run checks the whole project within a root directory found via `root_markers`, and
+    /// runs triggered by several files within the same root directory while one is pending are
+    /// coalesced into a single run. Diagnostics are routed to the files named in the output,
+    /// including files that are not open, so the output must name files (ie: via
+    /// `filename_match`). Substitutions that refer to the triggering file, like "$filename", are
+    /// not available, and neither is `use_stdin`.
+    Workspace,
+}
+
+/// What to do when a linter's program cannot be found.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PicklsOnMissing {
+    Ignore,
+    #[default]
+    Warn,
+    Diagnostic,
+}
+
+/// What to do with diagnostics for files other than the one being linted.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PicklsForeignFiles {
+    #[default]
+    Drop,
+    Publish,
+    RemapToCurrent,
+}
+
+/// How a linter's captured filenames are resolved and compared against the file being linted.
+#[derive(Eq, PartialEq, Clone, Debug, Deserialize)]
+pub struct PicklsPathResolution {
+    /// The directory that relative filenames are resolved against. Defaults to "cwd".
+    #[serde(default)]
+    pub base: PicklsPathBase,
+    /// Whether to resolve symlinks before comparing paths, for symlinked checkouts. Defaults to
+    /// false.
+    #[serde(default = "default_false")]
+    pub canonicalize: bool,
+    /// Whether paths that differ only in case refer to the same file. Defaults to true on macOS
+    /// and Windows, and false elsewhere.
+    #[serde(default = "default_case_insensitive")]
+    pub case_insensitive: bool,
+}
+
+impl Default for PicklsPathResolution {
+    fn default() -> Self {
+        PicklsPathResolution {
+            base: PicklsPathBase::default(),
+            canonicalize: false,
+            case_insensitive: default_case_insensitive(),
+        }
+    }
+}
+
+impl PicklsPathResolution {
+    /// Resolve a `captured` filename against the directory chosen by `base`.
+    pub fn resolve(
+        &self,
+        captured: &str,
+        working_dir: &Path,
+        root_dir: &Path,
+        dirname: &Path,
+    ) -> PathBuf {
+        let base = match self.base {
+            PicklsPathBase::Cwd => working_dir,
+            PicklsPathBase::Rootdir => root_dir,
+            PicklsPathBase::Dirname => dirname,
+        };
+        normalize_path(&base.join(captured))
+    }
+
+    /// Whether the resolved path `a` refers to the same file as `b`. See [`paths_match`].
+    pub fn is_same_file(&self, a: &Path, b: &Path) -> bool {
+        paths_match(a, b, self.canonicalize, self.case_insensitive)
+    }
+}
+
+/// The directory that a linter's relative filenames are resolved against.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PicklsPathBase {
+    /// The linter's working directory, per `cwd`.
+    #[default]
+    Cwd,
+    /// The root directory found via `root_markers`.
+    Rootdir,
+    /// The directory containing the file being linted.
+    Dirname,
+}
+
+fn default_case_insensitive() -> bool {
+    cfg!(any(target_os = "macos", target_os = "windows"))
+}
+
+/// The output streams of a linter that are scanned for diagnostics.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PicklsOutputSource {
+    #[default]
+    Stdout,
+    Stderr,
+    /// All of stdout followed by all of stderr. The streams are concatenated rather than
+    /// interleaved, since their relative ordering is not preserved by the pipes they are read
+    /// from. `pattern`, `ignore_pattern` and the rest of the parsing options apply to the lines
+    /// of both.
+    Both,
+}
+
+/// How the output of a linter is decoded into text.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default, Deserialize)]
+pub enum PicklsOutputEncoding {
+    /// Strict UTF-8: fail on invalid sequences.
+    #[serde(rename = "utf-8", alias = "utf8")]
+    Utf8,
+    /// UTF-8, replacing invalid sequences with U+FFFD.
+    #[default]
+    #[serde(rename = "utf-8-lossy", alias = "utf8-lossy")]
+    Utf8Lossy,
+    /// ISO-8859-1, where each byte is the code point of the same value. Never fails.
+    #[serde(rename = "latin-1", alias = "latin1", alias = "iso-8859-1")]
+    Latin1,
+}
+
+impl PicklsOutputEncoding {
+    /// Decode `bytes` per this encoding.
+    pub fn decode<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, str>, Utf8Error> {
+        match self {
+            PicklsOutputEncoding::Utf8 => std::str::from_utf8(bytes).map(Cow::Borrowed),
+            PicklsOutputEncoding::Utf8Lossy => Ok(String::from_utf8_lossy(bytes)),
+            PicklsOutputEncoding::Latin1 => match std::str::from_utf8(bytes) {
+                Ok(text) if text.is_ascii() => Ok(Cow::Borrowed(text)),
+                _ => Ok(Cow::Owned(bytes.iter().map(|&byte| byte as char).collect())),
+            },
+        }
+    }
+}
+
+/// The line endings of formatted documents. See `newline` in [`PicklsLanguageConfig`].
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PicklsNewline {
+    /// Match the dominant line ending of the document before formatting. A document without line
+    /// endings leaves those of the output untouched.
+    #[default]
+    Preserve,
+    Lf,
+    Crlf,
+}
+
+fn deserialize_symbols_sources<'de, D>(
+    deserializer: D,
+) -> Result<Vec<PicklsSymbolsSource>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+    let sources = match value {
+        Value::Array(_) => serde_json::from_value(value),
+        value => serde_json::from_value(value).map(|source| vec![source]),
+    };
+    sources.map_err(serde::de::Error::custom)
+}
+
+fn deserialize_output_source<'de, D>(deserializer: D) -> Result<PicklsOutputSource, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OutputSource {
+        UseStderr(bool),
+        Source(PicklsOutputSource),
+    }
+    Ok(match OutputSource::deserialize(deserializer)? {
+        OutputSource::UseStderr(true) => PicklsOutputSource::Stderr,
+        OutputSource::UseStderr(false) => PicklsOutputSource::Stdout,
+        OutputSource::Source(source) => source,
+    })
+}
+
+/// How the output of a linter is turned into diagnostics.
+#[derive(Eq, PartialEq, Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PicklsLinterFormat {
+    /// Each line of output is matched against `pattern`, and capture groups are pulled out via
+    /// the `*_match` fields. Alternatively, each line is matched against `errorformat`.
+    #[default]
+    Regex,
+    /// The output is a JSON document, and values are pulled out via the `*_path` fields. Each
+    /// `*_path` is a dotted path to a value within a diagnostic item, (ie: "location.row"). A path
+    /// that is not found on the item itself is looked up on its enclosing objects, innermost first,
+    /// which lets per-file values like eslint's "filePath" apply to each of their messages.
+    Json,
+    /// The output is a [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html)
+    /// document. Each result becomes a diagnostic, with `ruleId` as its code, `level` (or else that
+    /// of its rule's `defaultConfiguration`) as its severity, and the first location's region as
+    /// its range. Artifact URIs are percent-decoded, and a run's `columnKind` takes precedence over
+    /// `column_unit`. Results for files other than the one being linted are treated just like a
+    /// mismatched `filename_match`. Output that is not a valid SARIF document is reported as an
+    /// error rather than as zero diagnostics.
+    Sarif,
+    /// The output is a checkstyle XML document, (ie: `<checkstyle><file name="..."><error
+    /// line="..." column="..." severity="..." message="..."/></file></checkstyle>`), as produced
+    /// by tools like phpcs and ktlint. Severities are resolved just like `severity_match`.
+    Checkstyle,
+}
+
+/// A document event that can trigger a linter run.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PicklsLinterTrigger {
+    Open,
+    Save,
+    Change,
+}
+
+fn default_run_on() -> HashSet<PicklsLinterTrigger> {
+    [
+        PicklsLinterTrigger::Open,
+        PicklsLinterTrigger::Save,
+        PicklsLinterTrigger::Change,
+    ]
+    .into_iter()
+    .collect()
+}
+
+impl PicklsLinterConfig {
+    /// The name of this linter, falling back to the basename of `program` (or of the first word
+    /// of `command`).
+    pub fn display_name(&self) -> Cow<'_, str> {
+        match &self.name {
+            Some(name) => Cow::from(name.as_str()),
+            None => program_display_name(&self.program, self.command.as_deref()),
+        }
+    }
+
+    /// The full command line to spawn: `prefix_command`, then `program`, then `args`. When `shell`
+    /// is set, `program` and `args` are replaced by `shell` followed by `command`; otherwise they
+    /// are replaced by the words of `command`, if it is set.
+    pub fn command_line<'a>(&'a self, shell: &'a [String]) -> Vec<Cow<'a, str>> {
+        let command: Vec<Cow<str>> = match (&self.command, self.shell) {
+            (Some(command), true) => shell
+                .iter()
+                .chain(std::iter::once(command))
+                .map(|arg| Cow::from(arg.as_str()))
+                .collect(),
+            (Some(command), false) => split_words(command),
+            (None, _) => std::iter::once(&self.program)
+                .chain(&self.args)
+                .map(|arg| Cow::from(arg.as_str()))
+                .collect(),
+        };
+        prefixed_command_line(&self.prefix_command, command)
+    }
+
+    /// The path of the temp file to substitute for "$tmpfilename" when linting `filename`.
+    /// `temp_dir` is used unless `tmpfile_in_source_dir` is set, and `unique` distinguishes
+    /// concurrent runs, (ie: a process id and counter).
+    pub fn tmpfile_path(&self, filename: &Path, temp_dir: &Path, unique: &str) -> PathBuf {
+        tmpfile_path(
+            filename,
+            self.tmpfile_suffix.as_deref(),
+            self.tmpfile_in_source_dir,
+            temp_dir,
+            unique,
+        )
+    }
+
+    /// Whether this linter is enabled and its `require_files` are satisfied for a file in `dirname`
+    /// with the given root directory.
+    pub fn is_active(&self, dirname: &Path, root_dir: &Path) -> bool {
+        self.enabled
+            && (self.require_files.is_empty()
+                || any_file_exists_between(&self.require_files, dirname, root_dir))
+    }
+
+    /// Whether `relative_path` (relative to the root directory) matches `include_globs` (or
+    /// `include_globs` is empty) and does not match `exclude_globs`.
+    pub fn applies_to(&self, relative_path: &Path) -> bool {
+        (self.include_globs.is_empty() || matches_any_glob(&self.include_globs, relative_path))
+            && !matches_any_glob(&self.exclude_globs, relative_path)
+    }
+
+    /// Whether a run that exited with `exit_code` (None if killed by a signal) should have its
+    /// output parsed, according to `success_exit_codes`.
+    pub fn is_success(&self, exit_code: Option<i32>) -> bool {
+        match &self.success_exit_codes {
+            Some(codes) => exit_code.is_some_and(|code| codes.contains(&code)),
+            None => true,
+        }
+    }
+
+    /// The documentation link for a diagnostic, preferring a captured URL (when it is one) over
+    /// `code_url_template` filled in with the captured code.
+    pub fn code_url(&self, captured_url: Option<&str>, code: Option<&str>) -> Option<String> {
+        captured_url
+            .map(str::trim)
+            .filter(|url| is_url(url))
+            .map(str::to_string)
+            .or_else(|| {
+                let code = code.filter(|code| !code.is_empty())?;
+                Some(self.code_url_template.as_ref()?.replace("{{code}}", code))
+            })
+    }
+
+    /// Whether this linter should run in response to `trigger`.
+    pub fn runs_on(&self, trigger: PicklsLinterTrigger) -> bool {
+        self.run_on.contains(&trigger)
+    }
+
+    /// Resolve the severity of a diagnostic, in order of precedence, from:
+    ///
+    /// 1. its `code` via `code_severity_overrides`,
+    /// 2. its captured severity string via `severity_map`,
+    /// 3. its captured severity string via [`PicklsSeverity::from_name`],
+    /// 4. `default_severity`,
+    /// 5. and finally falling back to a warning.
+    pub fn resolve_severity(&self, captured: Option<&str>, code: Option<&str>) -> PicklsSeverity {
+        let overridden = code.and_then(|code| {
+            let overrides = self.code_severity_overrides.as_ref()?;
+            overrides.get(code).copied().or_else(|| {
+                overrides
+                    .iter()
+                    .filter(|(pattern, _)| code_matches(pattern, code))
+                    .max_by_key(|(pattern, _)| pattern.len())
+                    .map(|(_, severity)| *severity)
+            })
+        });
+        overridden
+            .or_else(|| {
+                captured.and_then(|captured| {
+                    self.severity_map
+                        .iter()
+                        .flatten()
+                        .find(|(key, _)| key.eq_ignore_ascii_case(captured.trim()))
+                        .map(|(_, severity)| *severity)
+                        .or_else(|| PicklsSeverity::from_name(captured))
+                })
+            })
+            .or(self.default_severity)
+            .unwrap_or(PicklsSeverity::Warning)
+    }
+
+    /// Resolve the tags of a diagnostic from the string captured by `tags_match` (if any) via
+    /// `tags_map`, and from its code via `code_tags`.
+    pub fn resolve_tags(
+        &self,
+        captured: Option<&str>,
+        code: Option<&str>,
+    ) -> Vec<PicklsDiagnosticTag> {
+        let mut tags = Vec::new();
+        let captured_tag = captured.and_then(|captured| {
+            self.tags_map
+                .iter()
+                .flatten()
+                .find(|(key, _)| key.eq_ignore_ascii_case(captured.trim()))
+                .map(|(_, tag)| *tag)
+        });
+        let code_tag = code.and_then(|code| self.code_tags.as_ref()?.get(code).copied());
+        for tag in [captured_tag, code_tag].into_iter().flatten() {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
+    /// Decode the bytes `program` wrote to one of its output streams, per `output_encoding`. The
+    /// error names this linter, ready to be reported.
+    pub fn decode_output<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, str>, String> {
+        self.output_encoding.decode(bytes).map_err(|error| {
+            format!(
+                "{}: output is not valid UTF-8 ({error}); consider setting output_encoding",
+                self.display_name()
+            )
+        })
+    }
+
+    /// The output to scan for diagnostics given everything `program` wrote, per
+    /// `output_source`.
+    pub fn output<'a>(&self, stdout: &'a str, stderr: &'a str) -> Cow<'a, str> {
+        match self.output_source {
+            PicklsOutputSource::Stdout => Cow::Borrowed(stdout),
+            PicklsOutputSource::Stderr => Cow::Borrowed(stderr),
+            PicklsOutputSource::Both if stdout.is_empty() => Cow::Borrowed(stderr),
+            PicklsOutputSource::Both if stdout.ends_with('\n') => {
+                Cow::Owned(format!("{stdout}{stderr}"))
+            }
+            PicklsOutputSource::Both => Cow::Owned(format!("{stdout}\n{stderr}")),
+        }
+    }
+
+    /// The file that a diagnostic should be published to given its `captured` filename (if any),
+    /// per `foreign_files` and `path_resolution`, or None if it should be dropped. `current` is
+    /// the absolute path of the file being linted, `working_dir` is the linter's working directory
+    /// and `root_dir` is the root directory found via `root_markers`.
+    pub fn diagnostic_path(
+        &self,
+        captured: Option<&str>,
+        current: &Path,
+        working_dir: &Path,
+        root_dir: &Path,
+    ) -> Option<PathBuf> {
+        let Some(captured) = captured else {
+            return Some(current.to_path_buf());
+        };
+        let dirname = current.parent().unwrap_or(root_dir);
+        let path = self
+            .path_resolution
+            .resolve(captured, working_dir, root_dir, dirname);
+        if self.path_resolution.is_same_file(&path, current) {
+            return Some(current.to_path_buf());
+        }
+        match (self.scope, self.foreign_files) {
+            (PicklsLinterScope::Workspace, _) | (_, PicklsForeignFiles::Publish) => Some(path),
+            (_, PicklsForeignFiles::RemapToCurrent) => Some(current.to_path_buf()),
+            (_, PicklsForeignFiles::Drop) => None,
+        }
+    }
+
+    /// The message logged (and, per `on_missing`, reported) when `program` cannot be found. See
+    /// [`crate::find_program`].
+    pub fn missing_program_message(&self, program: &str) -> String {
+        let name = self.display_name();
+        match &self.install_hint {
+            Some(install_hint) => format!("{name}: could not find '{program}' ({install_hint})"),
+            None => format!("{name}: could not find '{program}'"),
+        }
+    }
+
+    /// Compile `version_pattern`, or the default pattern when it is not set.
+    pub fn version_regex(&self) -> Result<Regex, PicklsConfigError> {
+        let pattern = self
+            .version_pattern
+            .as_deref()
+            .unwrap_or(DEFAULT_VERSION_PATTERN);
+        Regex::new(pattern)
+            .map_err(|error| PicklsConfigError::new("version_pattern", error.to_string()))
+    }
+
+    /// Check the output of running `program` with `version_args` against `min_version`. The
+    /// error describes why the linter is disabled.
+    pub fn check_version(&self, version_output: &str) -> Result<(), String> {
+        let Some(min_version) = self.min_version.as_deref().and_then(PicklsVersion::parse) else {
+            return Ok(());
+        };
+        let name = self.display_name();
+        let version = self
+            .version_regex()
+            .map_err(|error| error.to_string())?
+            .captures(version_output)
+            .and_then(|caps| caps.get(1))
+            .and_then(|version| PicklsVersion::parse(version.as_str()))
+            .ok_or_else(|| {
+                format!("{name}: could not find a version in the output of the version check")
+            })?;
+        if version < min_version {
+            return Err(format!(
+                "{name}: version {version} is older than the required {min_version}"
+            ));
+        }
+        Ok(())
+    }
+
+    /// The cache configuration in effect for this linter given the top-level `lint_cache`, or
+    /// None if its diagnostics should not be cached.
+    pub fn lint_cache<'a>(
+        &'a self,
+        default_lint_cache: Option<&'a PicklsLintCacheConfig>,
+    ) -> Option<&'a PicklsLintCacheConfig> {
+        self.cache
+            .as_ref()
+            .or(default_lint_cache)
+            .filter(|cache| cache.enabled && self.scope == PicklsLinterScope::File)
+    }
+
+    /// Whether this linter should be skipped for a document holding `text`, per
+    /// `max_file_size_kb` or else `default_max_file_size_kb` (the top-level setting).
+    pub fn exceeds_max_file_size(&self, text: &str, default_max_file_size_kb: Option<u64>) -> bool {
+        self.max_file_size_kb
+            .or(default_max_file_size_kb)
+            .is_some_and(|max_file_size_kb| text.len() as u64 > max_file_size_kb * 1024)
+    }
+
+    /// The message of the diagnostic reported when `report_oversized` is set and this linter is
+    /// skipped because of `max_file_size_kb`.
+    pub fn oversized_notice(&self, default_max_file_size_kb: Option<u64>) -> Option<String> {
+        self.max_file_size_kb
+            .or(default_max_file_size_kb)
+            .filter(|_| self.report_oversized)
+            .map(|max_file_size_kb| {
+                format!(
+                    "{name}: skipped because the file is larger than {max_file_size_kb}KB",
+                    name = self.display_name()
+                )
+            })
+    }
+
+    /// Whether diagnostics with `code` are dropped via `ignore_codes`.
+    pub fn ignores_code(&self, code: &str) -> bool {
+        self.ignore_codes
+            .iter()
+            .any(|pattern| code_matches(pattern, code))
+    }
+
+    /// Compile `ignore_messages`.
+    pub fn ignore_message_regexes(&self) -> Result<Vec<Regex>, PicklsConfigError> {
+        self.ignore_messages
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .map_err(|error| PicklsConfigError::new("ignore_messages", error.to_string()))
+            })
+            .collect()
+    }
+
+    /// The compiled patterns of `message_rewrites`, paired with their replacements.
+    pub fn message_rewrite_regexes(&self) -> Result<Vec<(Regex, String)>, PicklsConfigError> {
+        self.message_rewrites
+            .iter()
+            .enumerate()
+            .map(|(index, rewrite)| {
+                let regex = Regex::new(&rewrite.pattern).map_err(|error| {
+                    PicklsConfigError::new("pattern", error.to_string())
+                        .within(format!("message_rewrites[{index}]"))
+                })?;
+                Ok((regex, rewrite.replace.clone()))
+            })
+            .collect()
+    }
+
+    /// The log message noting how many diagnostics from a run were dropped via `ignore_codes`
+    /// and `ignore_messages`.
+    pub fn suppression_log_message(&self, suppressed: usize) -> String {
+        format!(
+            "{name}: suppressed {suppressed} diagnostics via ignore_codes and ignore_messages",
+            name = self.display_name()
+        )
+    }
+
+    /// The message of the diagnostic reported when `max_diagnostics` is reached.
+    pub fn truncation_notice(&self) -> Option<String> {
+        self.max_diagnostics.map(|max_diagnostics| {
+            format!(
+                "{name}: output truncated at {max_diagnostics} diagnostics",
+                name = self.display_name()
+            )
+        })
+    }
+
+    /// Compile `pattern`, honoring the `multiline` setting.
+    pub fn regex(&self) -> Result<Regex, PicklsConfigError> {
+        let pattern = self
+            .pattern
+            .as_deref()
+            .ok_or_else(|| PicklsConfigError::new("pattern", "pattern is required"))?;
+        RegexBuilder::new(pattern)
+            .multi_line(self.multiline)
+            .dot_matches_new_line(self.multiline)
+            .build()
+            .map_err(|error| PicklsConfigError::new("pattern", error.to_string()))
+    }
+
+    /// Compile `ignore_pattern`, if present.
+    pub fn ignore_regex(&self) -> Result<Option<Regex>, PicklsConfigError> {
+        compile_optional("ignore_pattern", self.ignore_pattern.as_deref())
+    }
+
+    /// Compile `continuation_pattern`, if present.
+    pub fn continuation_regex(&self) -> Result<Option<Regex>, PicklsConfigError> {
+        compile_optional("continuation_pattern", self.continuation_pattern.as_deref())
+    }
+
+    /// Compile `note_pattern`, if present.
+    pub fn note_regex(&self) -> Result<Option<Regex>, PicklsConfigError> {
+        compile_optional("note_pattern", self.note_pattern.as_deref())
+    }
+
+    /// Compile `errorformat`, if present.
+    pub fn errorformats(&self) -> Result<Vec<PicklsErrorFormat>, PicklsConfigError> {
+        self.errorformat
+            .iter()
+            .flatten()
+            .map(|format| PicklsErrorFormat::new(format))
+            .collect()
+    }
+
+    /// Check that the fields required by `format` are present and consistent. In "regex" mode,
+    /// `pattern` must compile and the capture groups referenced by this linter must exist within
+    /// it, or else every `errorformat` must compile.
+    pub fn validate(&self) -> Result<(), PicklsConfigError> {
+        self.validate_command()?;
+        check_base("line_base", self.line_base)?;
+        check_base("column_base", self.column_base)?;
+        self.ignore_regex()?;
+        self.continuation_regex()?;
+        self.note_regex()?;
+        if let Some(fix) = &self.fix {
+            fix.validate().map_err(|error| error.within("fix"))?;
+        }
+        if let Some(limits) = &self.limits {
+            limits.validate().map_err(|error| error.within("limits"))?;
+        }
+        if let Some(daemon) = &self.daemon {
+            daemon.validate().map_err(|error| error.within("daemon"))?;
+        }
+        if let Some(container) = &self.container {
+            let path_variable = self.command_templates().find_map(|(field, template)| {
+                substituted_variables(template)
+                    .into_iter()
+                    .find(|variable| PATH_SUBSTITUTION_VARIABLES.contains(variable))
+                    .map(|variable| (field, variable))
+            });
+            container
+                .validate(path_variable)
+                .map_err(|error| error.within("container"))?;
+            let tmpfile_field = self.command_templates().find_map(|(field, template)| {
+                substituted_variables(template)
+                    .contains(&"tmpfilename")
+                    .then_some(field)
+            });
+            if let Some(field) = tmpfile_field.filter(|_| !self.tmpfile_in_source_dir) {
+                return Err(PicklsConfigError::new(
+                    "tmpfile_in_source_dir",
+                    format!(
+                        "tmpfile_in_source_dir is required with a container since {field} uses \
+                         $tmpfilename"
+                    ),
+                ));
+            }
+        }
+        if let Some(related) = &self.related {
+            related
+                .validate()
+                .map_err(|error| error.within("related"))?;
+        }
+        validate_globs("include_globs", &self.include_globs)?;
+        validate_globs("exclude_globs", &self.exclude_globs)?;
+        if let Some(overrides) = &self.code_severity_overrides {
+            let patterns: Vec<String> = overrides.keys().cloned().collect();
+            validate_globs("code_severity_overrides", &patterns)?;
+        }
+        validate_globs("ignore_codes", &self.ignore_codes)?;
+        self.validate_version()?;
+        self.ignore_message_regexes()?;
+        self.message_rewrite_regexes()?;
+        if self.scope == PicklsLinterScope::Workspace {
+            self.validate_workspace_scope()?;
+        }
+        match self.format {
+            PicklsLinterFormat::Regex => self.validate_regex(),
+            PicklsLinterFormat::Json => self.validate_json(),
+            PicklsLinterFormat::Sarif | PicklsLinterFormat::Checkstyle => {
+                self.validate_without_pattern()
+            }
+        }
+    }
+
+    /// Convert a captured line number to a zero-based line, according to `line_base`.
+    pub fn zero_based_line(&self, line: u32) -> u32 {
+        line.saturating_sub(self.line_base.into())
+    }
+
+    /// Convert a captured column number to a zero-based column, according to `column_base`.
+    pub fn zero_based_column(&self, column: u32) -> u32 {
+        column.saturating_sub(self.column_base.into())
+    }
+
+    /// Find likely mistakes in this linter, (ie: unknown `$variables` in fields that undergo
+    /// substitution). A `shell` command is not checked, since it may legitimately refer to shell
+    /// variables. See [`PicklsConfig::warnings`].
+    pub fn warnings(&self) -> Vec<PicklsConfigError> {
+        unknown_variable_warnings(self.templates())
+    }
+
+    /// The fields that undergo substitutions, other than a `shell` command, paired with their
+    /// names.
+    fn templates(&self) -> impl Iterator<Item = (&str, &String)> {
+        self.args
+            .iter()
+            .map(|arg| ("args", arg))
+            .chain(
+                self.command
+                    .iter()
+                    .filter(|_| !self.shell)
+                    .map(|command| ("command", command)),
+            )
+            .chain(
+                self.prefix_command
+                    .iter()
+                    .map(|arg| ("prefix_command", arg)),
+            )
+            .chain(self.env.values().map(|value| ("env", value)))
+            .chain(self.extra_path.iter().map(|dir| ("extra_path", dir)))
+            .chain(self.cwd.iter().map(|cwd| ("cwd", cwd)))
+            .chain(
+                self.fix
+                    .iter()
+                    .flat_map(|fix| &fix.args)
+                    .map(|arg| ("fix.args", arg)),
+            )
+            .chain(
+                self.daemon
+                    .iter()
+                    .flat_map(|daemon| daemon.check_args.iter().flatten())
+                    .map(|arg| ("daemon.check_args", arg)),
+            )
+            .chain(
+                self.daemon
+                    .iter()
+                    .flat_map(|daemon| &daemon.check_line)
+                    .map(|line| ("daemon.check_line", line)),
+            )
+    }
+
+    fn validate_version(&self) -> Result<(), PicklsConfigError> {
+        let version_regex = self.version_regex()?;
+        if version_regex.captures_len() < 2 {
+            return Err(PicklsConfigError::new(
+                "version_pattern",
+                "version_pattern must capture the version in a group",
+            ));
+        }
+        let Some(min_version) = &self.min_version else {
+            return Ok(());
+        };
+        if PicklsVersion::parse(min_version).is_none() {
+            return Err(PicklsConfigError::new(
+                "min_version",
+                format!("'{min_version}' is not a version"),
+            ));
+        }
+        if self.version_args.is_empty() {
+            return Err(PicklsConfigError::new(
+                "version_args",
+                "version_args is required when min_version is set",
+            ));
+        }
+        Ok(())
+    }
+
+    /// The fields that undergo substitutions, including a `shell` command.
+    fn command_templates(&self) -> impl Iterator<Item = (&str, &String)> {
+        self.templates().chain(
+            self.command
+                .iter()
+                .filter(|_| self.shell)
+                .map(|command| ("command", command)),
+        )
+    }
+
+    fn validate_workspace_scope(&self) -> Result<(), PicklsConfigError> {
+        if self.use_stdin {
+            return Err(PicklsConfigError::new(
+                "use_stdin",
+                "use_stdin is not supported when scope is \"workspace\"",
+            ));
+        }
+        for (field, template) in self.command_templates() {
+            if let Some(variable) = substituted_variables(template)
+                .into_iter()
+                .find(|variable| FILE_SUBSTITUTION_VARIABLES.contains(variable))
+            {
+                return Err(PicklsConfigError::new(
+                    field,
+                    format!("${variable} is not available when scope is \"workspace\""),
+                ));
+            }
+        }
+        let filename_field = match self.format {
+            PicklsLinterFormat::Regex if self.errorformat.is_some() => None,
+            PicklsLinterFormat::Regex => self.filename_match.is_none().then_some("filename_match"),
+            PicklsLinterFormat::Json => self.file_path.is_none().then_some("file_path"),
+            PicklsLinterFormat::Sarif | PicklsLinterFormat::Checkstyle => None,
+        };
+        match filename_field {
+            Some(field) => Err(PicklsConfigError::new(
+                field,
+                format!("{field} is required when scope is \"workspace\""),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn validate_command(&self) -> Result<(), PicklsConfigError> {
+        if self.shell {
+            if self.command.is_none() {
+                return Err(PicklsConfigError::new(
+                    "command",
+                    "command is required when shell is set",
+                ));
+            }
+            if !self.args.is_empty() {
+                return Err(PicklsConfigError::new(
+                    "args",
+                    "args and command are mutually exclusive",
+                ));
+            }
+        } else {
+            match &self.command {
+                Some(command) => validate_split_command(command, &self.program, &self.args)?,
+                None if self.program.is_empty() => {
+                    return Err(PicklsConfigError::new(
+                        "program",
+                        "program is required unless command is set",
+                    ));
+                }
+                None => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_regex(&self) -> Result<(), PicklsConfigError> {
+        if self.errorformat.is_some() {
+            if self.pattern.is_some() {
+                return Err(PicklsConfigError::new(
+                    "errorformat",
+                    "pattern and errorformat are mutually exclusive",
+                ));
+            }
+            self.errorformats()?;
+            return Ok(());
+        }
+        let regex = self.regex()?;
+        let groups = [
+            ("line_match", &self.line_match),
+            ("filename_match", &self.filename_match),
+            ("end_line_match", &self.end_line_match),
+            ("start_col_match", &self.start_col_match),
+            ("end_col_match", &self.end_col_match),
+            ("severity_match", &self.severity_match),
+            ("code_match", &self.code_match),
+            ("url_match", &self.url_match),
+            ("tags_match", &self.tags_match),
+            ("fix_text_match", &self.fix_text_match),
+            ("fixable_match", &self.fixable_match),
+        ];
+        for (field, group) in groups {
+            if let Some(group) = group {
+                group.validate(field, &regex)?;
+            }
+        }
+        if let Some(description_match) = &self.description_match {
+            description_match.validate("description_match", &regex)?;
+        }
+        let dependencies = [
+            (
+                "end_line_match",
+                &self.end_line_match,
+                "line_match",
+                &self.line_match,
+            ),
+            (
+                "start_col_match",
+                &self.start_col_match,
+                "line_match",
+                &self.line_match,
+            ),
+            (
+                "end_col_match",
+                &self.end_col_match,
+                "start_col_match",
+                &self.start_col_match,
+            ),
+        ];
+        for (field, group, required, required_group) in dependencies {
+            if group.is_some() && required_group.is_none() {
+                return Err(PicklsConfigError::new(
+                    field,
+                    format!("{field} requires {required}"),
+                ));
+            }
+        }
+        if self.multiline {
+            let line_fields = [
+                ("continuation_pattern", self.continuation_pattern.is_some()),
+                ("note_pattern", self.note_pattern.is_some()),
+                ("related", self.related.is_some()),
+            ];
+            if let Some((field, _)) = line_fields.into_iter().find(|(_, set)| *set) {
+                return Err(PicklsConfigError::new(
+                    field,
+                    format!("{field} is matched line by line, and cannot be used with multiline"),
+                ));
+            }
+        }
+        match self.description_line_offset {
+            Some(_) if self.description_match.is_some() => {
+                return Err(PicklsConfigError::new(
+                    "description_line_offset",
+                    "description_match and description_line_offset are mutually exclusive",
+                ));
+            }
+            Some(0) => {
+                return Err(PicklsConfigError::new(
+                    "description_line_offset",
+                    "description_line_offset must not be 0, use description_match for the \
+                    matching line",
+                ));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn validate_json(&self) -> Result<(), PicklsConfigError> {
+        self.validate_without_pattern()?;
+        if self.line_path.is_none() {
+            return Err(PicklsConfigError::new(
+                "line_path",
+                "line_path is required when format is \"json\"",
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_without_pattern(&self) -> Result<(), PicklsConfigError> {
+        if self.pattern.is_some() {
+            return Err(PicklsConfigError::new(
+                "pattern",
+                "pattern is only used when format is \"regex\"",
+            ));
+        }
+        if self.errorformat.is_some() {
+            return Err(PicklsConfigError::new(
+                "errorformat",
+                "errorformat is only used when format is \"regex\"",
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn is_url(text: &str) -> bool {
+    match text.split_once("://") {
+        Some((scheme, rest)) => {
+            !scheme.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+                && !rest.is_empty()
+                && !rest.contains(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+/// The substitution variables that refer to a particular file, which are not available to
+/// linters with a "workspace" `scope`.
+const FILE_SUBSTITUTION_VARIABLES: &[&str] =
+    &["filename", "tmpfilename", "dirname", "basename", "ext"];
+
+/// Whether `code` is matched by `pattern`, either exactly or as a glob pattern, (ie: "E5*").
+fn code_matches(pattern: &str, code: &str) -> bool {
+    pattern == code || Pattern::new(pattern).is_ok_and(|pattern| pattern.matches(code))
+}
+
+fn compile_optional(
+    field: &str,
+    pattern: Option<&str>,
+) -> Result<Option<Regex>, PicklsConfigError> {
+    pattern
+        .map(|pattern| {
+            Regex::new(pattern).map_err(|error| PicklsConfigError::new(field, error.to_string()))
+        })
+        .transpose()
+}
+
+fn unknown_variable_warnings<'a>(
+    templates: impl Iterator<Item = (&'a str, &'a String)>,
+) -> Vec<PicklsConfigError> {
+    templates
+        .flat_map(|(field, template)| {
+            unknown_variables(template)
+                .into_iter()
+                .map(move |variable| {
+                    PicklsConfigError::new(
+                        field,
+                        format!("unknown substitution variable {variable} in '{template}'"),
+                    )
+                })
+        })
+        .collect()
+}
+
+fn check_concurrency_limit(max_concurrent_linters: Option<usize>) -> Result<(), PicklsConfigError> {
+    match max_concurrent_linters {
+        Some(0) => Err(PicklsConfigError::new(
+            "max_concurrent_linters",
+            "max_concurrent_linters must be at least 1",
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn check_base(field: &str, base: u8) -> Result<(), PicklsConfigError> {
+    match base {
+        0 | 1 => Ok(()),
+        _ => Err(PicklsConfigError::new(
+            field,
+            format!("{field} must be 0 or 1 but {field} = {base}"),
+        )),
+    }
+}
+
+/// The path of a temp file standing in for `filename`, within its directory when `in_source_dir`
+/// is set and within `temp_dir` otherwise. `suffix` defaults to the extension of `filename`.
+fn tmpfile_path(
+    filename: &Path,
+    suffix: Option<&str>,
+    in_source_dir: bool,
+    temp_dir: &Path,
+    unique: &str,
+) -> PathBuf {
+    let dir = match (in_source_dir, filename.parent()) {
+        (true, Some(parent)) => parent,
+        _ => temp_dir,
+    };
+    let stem = filename
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let suffix = match suffix {
+        Some(suffix) => suffix.to_string(),
+        None => filename
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy()))
+            .unwrap_or_default(),
+    };
+    dir.join(format!("{stem}.pickls-{unique}{suffix}"))
+}
+
+/// The words of a `command` that is split into `program` and `args`.
+fn split_words(command: &str) -> Vec<Cow<'_, str>> {
+    split_command(command)
+        .unwrap_or_default()
+        .into_iter()
+        .map(Cow::from)
+        .collect()
+}
 
-    /// All the formatters you'd like to run (in order) on this language. Note
-    /// that you'll need to configure your editor to invoke its LSP client to
-    /// cause formatting to occur. Successive formatters that set use_stdin will
-    /// have chained pipes from stdout to stdin to eliminate extra copies.
-    #[serde(default)]
-    pub formatters: Vec<PicklsFormatterConfig>,
+/// `command` run through `prefix_command`, for linters and formatters alike.
+fn prefixed_command_line<'a>(
+    prefix_command: &'a [String],
+    command: Vec<Cow<'a, str>>,
+) -> Vec<Cow<'a, str>> {
+    prefix_command
+        .iter()
+        .map(|arg| Cow::from(arg.as_str()))
+        .chain(command)
+        .collect()
 }
 
-#[derive(Clone, Debug, Deserialize)]
-pub struct PicklsLinterConfig {
-    /// If `program` is not an absolute path, the `PATH` will be searched in an OS-defined way.
-    pub program: String,
-    /// Arguments to pass to `program`. Use "$filename" wherever the absolute path to the real filename should go.
-    /// Use "$tmpfilename" where Pickls should inject a temp file (if the linter only accepts file
-    /// input).
-    #[serde(default = "Vec::new")]
-    pub args: Vec<String>,
-    /// Whether to use stdin to push the contents of the file to `program` or to rely on the usage
-    /// of "$filename" arg.
-    pub use_stdin: bool,
-    /// Regex from which to pull diagnostics from stdout of `program`. The pattern is matched on
-    /// every line of output. When there is a match, a diagnostic is produced.
-    pub pattern: String,
-    /// Regex group (1-indexed) that matches the filename of the diagnostic.
-    pub filename_match: Option<usize>,
-    /// Regex group (1-indexed) that matches the line number of the diagnostic.
-    pub line_match: usize,
-    /// Regex group (1-indexed) that matches the starting column number of the diagnostic. (Optional)
-    pub start_col_match: Option<usize>,
-    /// Regex group (1-indexed) that matches the ending column number of the diagnostic. (Optional)
-    pub end_col_match: Option<usize>,
-    /// Regex group (1-indexed) that matches the severity of the alert. Unknown severities will
-    /// resolve to warnings.
-    pub severity_match: Option<usize>,
-    /// Regex group (1-indexed) that matches the line number of the diagnostic. Use -1 to indicate
-    /// that the description is on the _previous_ line of input.
-    pub description_match: Option<isize>,
-    /// Whether to scan stderr instead of stdout. Defaults to false. Setting to true will ignore
-    /// stdout.
-    #[serde(default = "default_false")]
-    pub use_stderr: bool,
+/// Check a `command` that is split into `program` and `args`.
+fn validate_split_command(
+    command: &str,
+    program: &str,
+    args: &[String],
+) -> Result<(), PicklsConfigError> {
+    if !program.is_empty() {
+        return Err(PicklsConfigError::new(
+            "command",
+            "program and command are mutually exclusive",
+        ));
+    }
+    if !args.is_empty() {
+        return Err(PicklsConfigError::new(
+            "args",
+            "args and command are mutually exclusive",
+        ));
+    }
+    match split_command(command) {
+        Ok(words) if words.is_empty() => Err(PicklsConfigError::new("command", "command is empty")),
+        Ok(_) => Ok(()),
+        Err(error) => Err(PicklsConfigError::new("command", error)),
+    }
+}
+
+/// The basename of `program`, or of the first word of `command` when it is set. The words of
+/// `command` are split by [`split_command`], so a quoted path containing spaces is one word.
+fn program_display_name<'a>(program: &'a str, command: Option<&str>) -> Cow<'a, str> {
+    fn basename(program: &str) -> &str {
+        Path::new(program)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(program)
+    }
+    match command {
+        Some(command) => {
+            let words = split_command(command).unwrap_or_default();
+            Cow::from(words.first().map_or("", |word| basename(word)).to_string())
+        }
+        None => Cow::from(basename(program)),
+    }
+}
+
+fn default_one_based() -> u8 {
+    1
 }
 
 fn default_false() -> bool {
@@ -99,18 +2916,429 @@ fn default_true() -> bool {
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct PicklsFormatterConfig {
+    /// The name of a formatter definition in the top-level `formatter_definitions` to start
+    /// from. Only the fields explicitly set on this formatter override the definition's values,
+    /// (ie: `{"extends": "prettier", "args": ["--parser", "yaml"]}`). Expanded by
+    /// [`PicklsConfig::resolve`].
+    pub extends: Option<String>,
+    /// The fields explicitly set in this formatter's configuration, which `extends` overlays onto
+    /// the definition it names.
+    #[serde(skip)]
+    pub(crate) fields: Map<String, Value>,
+    /// Whether this formatter runs at all. Defaults to true.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Files that must exist for this formatter to run, (ie: ".prettierrc*"). Glob patterns are
+    /// supported. The formatter runs if any of these files exists in the formatted file's
+    /// directory or any directory between it and the root directory found via `root_markers`.
+    /// When empty, the formatter always runs. A skipped formatter is left out of the chain of
+    /// `formatters`, whose other formatters still run.
+    #[serde(default)]
+    pub require_files: Vec<String>,
+    /// Files to format, as glob patterns matched against the file's path relative to the root
+    /// directory found via `root_markers`, (ie: "web/**"). When empty, all files are included.
+    /// Files must match `include_globs` and must not match `exclude_globs`.
+    #[serde(default)]
+    pub include_globs: Vec<String>,
+    /// Files to leave alone, as glob patterns matched against the file's path relative to the
+    /// root directory found via `root_markers`, (ie: "**/*.pb.go" or "dist/**"). Exclusions take
+    /// precedence over `include_globs`. Other formatters in the chain still run.
+    #[serde(default)]
+    pub exclude_globs: Vec<String>,
     /// If `program` is not an absolute path, the `PATH` will be searched in an OS-defined way.
+    /// Required unless `command` is set.
+    #[serde(default)]
     pub program: String,
-    /// Arguments to pass to `program`. Use "$abspath" wherever the absolute path to the filename should go.
+    /// Arguments to pass to `program`. Use "$filename" wherever the absolute path to the real
+    /// filename should go. Use "$tmpfilename" for formatters that neither read stdin nor write
+    /// stdout: the buffer is written to a temp file, whose path is substituted, and the temp file
+    /// is read back as the formatted result once `program` succeeds. See
+    /// [`crate::SUBSTITUTION_VARIABLES`] for the other available variables.
+    #[serde(default)]
     pub args: Vec<String>,
+    /// The command to run, as a single string split into `program` and `args` by
+    /// [`crate::split_command`], (ie: "black --quiet -"). Substitutions apply to each word after
+    /// splitting. Mutually exclusive with `program` and `args`.
+    pub command: Option<String>,
+    /// A command to run `program` through, (ie: `["npx"]` or `["poetry", "run"]`). It is
+    /// prepended to the command line after substitutions are applied to it and to `args`, and
+    /// receives the piped stdin of the chain. The `PATH` search applies to its first element
+    /// instead of `program`. Any stderr chatter from it counts toward `stderr_indicates_error`,
+    /// so consider `stderr_error_pattern` instead.
+    #[serde(default)]
+    pub prefix_command: Vec<String>,
+    /// Arguments that replace `args` when formatting a range of lines rather than the whole
+    /// file, with "$startline" and "$endline" substituted by the 1-based, inclusive lines of the
+    /// range, (ie: `["--line-ranges", "$startline-$endline", "-"]` for black). A range request
+    /// runs only the formatters of the chain that set `range_args`, and is declined when none
+    /// do. (Optional)
+    pub range_args: Option<Vec<String>>,
+    /// Arguments that replace `args` to check whether the text on stdin is already formatted,
+    /// exiting non-zero if not, (ie: `["--check", "-"]`). When `verify_idempotent` is set for the
+    /// language, this is run on the formatter's own output instead of formatting it a second
+    /// time. (Optional)
+    pub check_args: Option<Vec<String>>,
+    /// Alternative formatters to use when `program` cannot be found, in order, (ie: `black`
+    /// when `ruff` is not installed). The first candidate whose program is found, searching
+    /// `extra_path` and `PATH`, is run in place of this formatter and the rest are skipped. The
+    /// choice is made once per session and logged. See
+    /// [`PicklsFormatterConfig::resolve_fallback`].
+    #[serde(default, deserialize_with = "deserialize_fallbacks")]
+    pub fallbacks: Vec<PicklsFormatterConfig>,
     /// Whether to use stdin to push the contents of the file to `program` or to rely on the usage
     /// of "$filename" arg. Defaults to true.
     #[serde(default = "default_true")]
     pub use_stdin: bool,
+    /// The suffix of the temp file substituted for "$tmpfilename". Defaults to the real file's
+    /// extension. See `tmpfile_suffix` in [`PicklsLinterConfig`].
+    pub tmpfile_suffix: Option<String>,
+    /// Whether to create the temp file next to the real file instead of in the system temp
+    /// directory. The temp file is removed after every run, including failed and timed-out runs.
+    /// Defaults to false.
+    #[serde(default = "default_false")]
+    pub tmpfile_in_source_dir: bool,
     /// If `stderr_indicates_error` is true, then if the formatter writes anything to stderr, the
     /// format run will be considered a failure and aborted. Defaults to false.
     #[serde(default = "default_false")]
     pub stderr_indicates_error: bool,
+    /// If `exit_code_indicates_error` is true, then if the formatter exits with a non-zero status,
+    /// the format run will be considered a failure and aborted. Either this or
+    /// `stderr_indicates_error` is enough to abort a run. See
+    /// [`PicklsCompiledFormatter::check_result`]. Defaults to true.
+    #[serde(default = "default_true")]
+    pub exit_code_indicates_error: bool,
+    /// A regex for the lines of stderr that indicate a failure, (ie: "^\\[error\\]"). When set,
+    /// it takes precedence over `stderr_indicates_error`: stderr without a matching line is
+    /// logged and otherwise ignored. (Optional)
+    pub stderr_error_pattern: Option<String>,
+    /// How to turn the stderr of a failed run into diagnostics, (ie: black's "cannot format
+    /// x.py: Cannot parse: 42:7"), using the output parsing fields of a linter: `pattern`,
+    /// `line_match`, `start_col_match`, `severity_match`, `description_match` and the rest. The
+    /// fields that concern running a program are ignored, `name` defaults to that of this
+    /// formatter and `default_severity` defaults to "error". The diagnostics are cleared by the
+    /// next successful format. Like a linter, it may start from a built-in `preset`. See
+    /// [`PicklsFormatterConfig::compile_diagnostics`]. (Optional)
+    #[serde(default, deserialize_with = "deserialize_formatter_diagnostics")]
+    pub diagnostics: Option<PicklsLinterConfig>,
+    /// Whether to treat blank output as a failure when the input was not blank, so that a broken
+    /// formatter that exits successfully without printing anything cannot empty the buffer. See
+    /// [`PicklsFormatterConfig::check_output`]. Defaults to true.
+    #[serde(default = "default_true")]
+    pub reject_empty_output: bool,
+    /// Treat output that is more than this percentage smaller than the input as a failure, (ie:
+    /// 50 to abort when a formatter drops more than half of the file). (Optional)
+    pub max_shrink_percent: Option<u8>,
+    /// How long to wait for `program` to complete before killing its subprocess group. The
+    /// timeout applies to this formatter alone, not to the whole chain of `formatters`. Set to
+    /// null to wait indefinitely. Defaults to 10000ms.
+    #[serde(default = "default_formatter_timeout_ms")]
+    pub timeout_ms: Option<u64>,
+    /// Refuse to format documents larger than this many kilobytes, (ie: minified bundles). The
+    /// size of the in-memory buffer is checked, not the file on disk. When any formatter in the
+    /// chain of `formatters` is oversized, the whole chain is skipped with a logged notice, so
+    /// that later formatters never see unformatted text as if it were the output of this one. See
+    /// [`PicklsLanguageConfig::oversized_formatters_notice`]. Defaults to the top-level
+    /// `formatter_max_file_size_kb`, or unlimited.
+    pub max_file_size_kb: Option<u64>,
+    /// Environment variables to set for `program`, merged over the inherited environment, (ie:
+    /// `{"NODE_OPTIONS": "--max-old-space-size=4096"}`). Values undergo the same substitutions as
+    /// `args`.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Environment variables to remove from the environment inherited by `program`. Applied
+    /// before `env`.
+    #[serde(default)]
+    pub env_remove: Vec<String>,
+    /// The working directory for `program`, (ie: "$dirname" for tools that discover their
+    /// configuration from it). Relative paths are resolved against the root directory found via
+    /// `root_markers`, and the value undergoes the same substitutions as `args`. If the resolved
+    /// directory does not exist, the formatting run fails. See
+    /// [`PicklsFormatterConfig::working_dir`]. Defaults to the root directory.
+    pub cwd: Option<String>,
+    /// Directories to search for `program` before the top-level `extra_path` and the inherited
+    /// `PATH`. See [`PicklsFormatterConfig::search_path`].
+    #[serde(default)]
+    pub extra_path: Vec<String>,
+}
+
+impl PicklsFormatterConfig {
+    /// Check that exactly one of `program` and `command` is set, that `command` splits, and that
+    /// `stderr_error_pattern`, `diagnostics` and the globs compile, for this formatter and each of
+    /// `fallbacks`.
+    pub fn validate(&self) -> Result<(), PicklsConfigError> {
+        for (index, fallback) in self.fallbacks.iter().enumerate() {
+            let location = format!("fallbacks[{index}]");
+            if !fallback.fallbacks.is_empty() {
+                return Err(PicklsConfigError::new(
+                    "fallbacks",
+                    "fallbacks may not have fallbacks of their own",
+                )
+                .within(location));
+            }
+            fallback
+                .validate()
+                .map_err(|error| error.within(location))?;
+        }
+        self.stderr_error_regex()?;
+        self.compile_diagnostics().transpose()?;
+        if self.max_shrink_percent.is_some_and(|percent| percent > 100) {
+            return Err(PicklsConfigError::new(
+                "max_shrink_percent",
+                "max_shrink_percent must be at most 100",
+            ));
+        }
+        validate_globs("include_globs", &self.include_globs)?;
+        validate_globs("exclude_globs", &self.exclude_globs)?;
+        match &self.command {
+            Some(command) => validate_split_command(command, &self.program, &self.args),
+            None if self.program.is_empty() => Err(PicklsConfigError::new(
+                "program",
+                "program is required unless command is set",
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// The `PATH` to resolve and spawn `program` with: this formatter's `extra_path`, then the
+    /// top-level `extra_path`, then `path`. See [`crate::search_path`].
+    pub fn search_path(
+        &self,
+        extra_path: &[String],
+        substitutions: &PicklsSubstitutions,
+        path: Option<&OsStr>,
+    ) -> Option<OsString> {
+        search_path(
+            self.extra_path.iter().chain(extra_path),
+            substitutions,
+            path,
+        )
+    }
+
+    /// The full command line to spawn: `prefix_command`, then `program` and `args` or the words
+    /// of `command`.
+    pub fn command_line(&self) -> Vec<Cow<'_, str>> {
+        let command = match &self.command {
+            Some(command) => split_words(command),
+            None => std::iter::once(&self.program)
+                .chain(&self.args)
+                .map(|arg| Cow::from(arg.as_str()))
+                .collect(),
+        };
+        prefixed_command_line(&self.prefix_command, command)
+    }
+
+    /// The basename of `program` (or of the first word of `command`), for log messages.
+    pub fn display_name(&self) -> Cow<'_, str> {
+        program_display_name(&self.program, self.command.as_deref())
+    }
+
+    /// The first of this formatter and its `fallbacks` whose program is found on `path` (see
+    /// [`PicklsFormatterConfig::search_path`]), or None when none is installed.
+    pub fn resolve_fallback(&self, path: Option<&OsStr>) -> Option<&PicklsFormatterConfig> {
+        std::iter::once(self)
+            .chain(&self.fallbacks)
+            .find(|candidate| {
+                candidate
+                    .command_line()
+                    .first()
+                    .is_some_and(|program| find_program(program, path).is_some())
+            })
+    }
+
+    /// Validate and compile `diagnostics` as a linter whose program is this formatter's, ready to
+    /// parse the stderr of a failed run via [`PicklsCompiledLinter::parse_output`]. None when
+    /// `diagnostics` is unset.
+    pub fn compile_diagnostics(&self) -> Option<Result<PicklsCompiledLinter, PicklsConfigError>> {
+        let diagnostics = self.diagnostics.as_ref()?;
+        let mut linter = diagnostics.clone();
+        linter.program = self.display_name().to_string();
+        linter.command = None;
+        linter.shell = false;
+        linter.args = Vec::new();
+        linter
+            .name
+            .get_or_insert_with(|| self.display_name().to_string());
+        linter.default_severity.get_or_insert(PicklsSeverity::Error);
+        Some(
+            linter
+                .compile()
+                .map_err(|error| error.within("diagnostics")),
+        )
+    }
+
+    /// The `max_file_size_kb` of this formatter if set, and otherwise `default_max_file_size_kb`
+    /// (the top-level setting), when a document holding `text` exceeds it.
+    pub fn exceeded_max_file_size(
+        &self,
+        text: &str,
+        default_max_file_size_kb: Option<u64>,
+    ) -> Option<u64> {
+        self.max_file_size_kb
+            .or(default_max_file_size_kb)
+            .filter(|max_file_size_kb| text.len() as u64 > max_file_size_kb * 1024)
+    }
+
+    /// Whether this formatter is enabled and its `require_files` are satisfied for a file in
+    /// `dirname` with the given root directory.
+    pub fn is_active(&self, dirname: &Path, root_dir: &Path) -> bool {
+        self.enabled
+            && (self.require_files.is_empty()
+                || any_file_exists_between(&self.require_files, dirname, root_dir))
+    }
+
+    /// Whether `relative_path` (relative to the root directory) matches `include_globs` (or
+    /// `include_globs` is empty) and does not match `exclude_globs`.
+    pub fn applies_to(&self, relative_path: &Path) -> bool {
+        (self.include_globs.is_empty() || matches_any_glob(&self.include_globs, relative_path))
+            && !matches_any_glob(&self.exclude_globs, relative_path)
+    }
+
+    /// The directory to run `program` in, per `cwd`. Fails when it does not exist.
+    pub fn working_dir(
+        &self,
+        substitutions: &PicklsSubstitutions,
+        root_dir: &Path,
+    ) -> Result<PathBuf, String> {
+        let Some(cwd) = &self.cwd else {
+            return Ok(root_dir.to_path_buf());
+        };
+        let dir = root_dir.join(substitutions.apply(cwd));
+        match dir.is_dir() {
+            true => Ok(dir),
+            false => Err(format!(
+                "working directory {} of formatter {} does not exist",
+                dir.display(),
+                self.display_name()
+            )),
+        }
+    }
+
+    /// Decide whether `output`, produced by a successful run of this formatter on `input`, is safe
+    /// to apply, per `reject_empty_output` and `max_shrink_percent`. On failure, the returned
+    /// message is ready to be logged; the buffer must then be left untouched.
+    pub fn check_output(&self, input: &str, output: &str) -> Result<(), String> {
+        let name = self.display_name();
+        if self.reject_empty_output && output.trim().is_empty() && !input.trim().is_empty() {
+            return Err(format!("formatter {name} produced no output"));
+        }
+        let Some(max_shrink_percent) = self.max_shrink_percent else {
+            return Ok(());
+        };
+        let shrink = input.len().saturating_sub(output.len());
+        if !input.is_empty() && shrink * 100 > usize::from(max_shrink_percent) * input.len() {
+            return Err(format!(
+                "formatter {name} shrank the file by {}%, more than max_shrink_percent \
+                 ({max_shrink_percent}%)",
+                shrink * 100 / input.len()
+            ));
+        }
+        Ok(())
+    }
+
+    /// The compiled `stderr_error_pattern`, when present.
+    pub fn stderr_error_regex(&self) -> Result<Option<Regex>, PicklsConfigError> {
+        self.stderr_error_pattern
+            .as_deref()
+            .map(|pattern| {
+                Regex::new(pattern).map_err(|error| {
+                    PicklsConfigError::new("stderr_error_pattern", error.to_string())
+                })
+            })
+            .transpose()
+    }
+
+    /// Whether this formatter reads its result back from the temp file substituted for
+    /// "$tmpfilename" rather than from stdout.
+    pub fn uses_tmpfile(&self) -> bool {
+        self.args
+            .iter()
+            .chain(&self.command)
+            .any(|arg| substituted_variables(arg).contains(&"tmpfilename"))
+    }
+
+    /// The path of the temp file to substitute for "$tmpfilename" when formatting `filename`.
+    /// See [`PicklsLinterConfig::tmpfile_path`].
+    pub fn tmpfile_path(&self, filename: &Path, temp_dir: &Path, unique: &str) -> PathBuf {
+        tmpfile_path(
+            filename,
+            self.tmpfile_suffix.as_deref(),
+            self.tmpfile_in_source_dir,
+            temp_dir,
+            unique,
+        )
+    }
+
+    /// The command line to spawn to format a range of lines: `prefix_command`, the program, then
+    /// `range_args`. Returns None when this formatter does not support ranges.
+    pub fn range_command_line(&self) -> Option<Vec<Cow<'_, str>>> {
+        self.command_line_with(self.range_args.as_ref()?)
+    }
+
+    /// The command line to spawn to check whether already-formatted text would change again:
+    /// `prefix_command`, the program, then `check_args`. Returns None when `check_args` is not
+    /// set.
+    pub fn check_command_line(&self) -> Option<Vec<Cow<'_, str>>> {
+        self.command_line_with(self.check_args.as_ref()?)
+    }
+
+    /// `prefix_command`, then `program` (or the first word of `command`), then `args`.
+    /// Find likely mistakes in this formatter and its `fallbacks`, (ie: unknown `$variables` in
+    /// fields that undergo substitution). See [`PicklsConfig::warnings`].
+    pub fn warnings(&self) -> Vec<PicklsConfigError> {
+        let fallback_warnings = self
+            .fallbacks
+            .iter()
+            .enumerate()
+            .flat_map(|(index, fallback)| {
+                fallback
+                    .warnings()
+                    .into_iter()
+                    .map(move |warning| warning.within(format!("fallbacks[{index}]")))
+            });
+        unknown_variable_warnings(self.templates())
+            .into_iter()
+            .chain(fallback_warnings)
+            .collect()
+    }
+
+    /// The fields that undergo substitutions, paired with their names.
+    fn templates(&self) -> impl Iterator<Item = (&str, &String)> {
+        self.args
+            .iter()
+            .map(|arg| ("args", arg))
+            .chain(self.command.iter().map(|command| ("command", command)))
+            .chain(
+                self.prefix_command
+                    .iter()
+                    .map(|arg| ("prefix_command", arg)),
+            )
+            .chain(
+                self.range_args
+                    .iter()
+                    .flatten()
+                    .map(|arg| ("range_args", arg)),
+            )
+            .chain(
+                self.check_args
+                    .iter()
+                    .flatten()
+                    .map(|arg| ("check_args", arg)),
+            )
+            .chain(self.env.values().map(|value| ("env", value)))
+            .chain(self.extra_path.iter().map(|dir| ("extra_path", dir)))
+            .chain(self.cwd.iter().map(|cwd| ("cwd", cwd)))
+    }
+
+    fn command_line_with<'a>(&'a self, args: &'a [String]) -> Option<Vec<Cow<'a, str>>> {
+        let program = match &self.command {
+            Some(command) => Cow::from(split_command(command).ok()?.into_iter().next()?),
+            None => Cow::from(self.program.as_str()),
+        };
+        let command = std::iter::once(program)
+            .chain(args.iter().map(|arg| Cow::from(arg.as_str())))
+            .collect();
+        Some(prefixed_command_line(&self.prefix_command, command))
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
@@ -122,6 +3350,26 @@ pub struct PicklsAIConfig {
     pub inline_assist_prompt_template: String,
     pub openai: Option<OpenAIConfig>,
     pub ollama: Option<OllamaConfig>,
+    pub anthropic: Option<AnthropicConfig>,
+    pub azure_openai: Option<AzureOpenAIConfig>,
+}
+
+impl PicklsAIConfig {
+    /// Check that `azure_openai` is set and valid when it is the `inline_assist_provider`. It is
+    /// not checked otherwise, so an unused or partial `azure_openai` does not stop the
+    /// configuration from loading.
+    pub fn validate(&self) -> Result<(), PicklsConfigError> {
+        match (&self.inline_assist_provider, &self.azure_openai) {
+            (PicklsAIProvider::AzureOpenAI, None) => Err(PicklsConfigError::new(
+                "azure_openai",
+                "azure_openai is required when inline_assist_provider is \"azure-openai\"",
+            )),
+            (PicklsAIProvider::AzureOpenAI, Some(azure_openai)) => azure_openai
+                .validate()
+                .map_err(|error| error.within("azure_openai")),
+            _ => Ok(()),
+        }
+    }
 }
 
 /// Ollama is a AI model driver that can be run locally.
@@ -146,6 +3394,9 @@ pub enum PicklsAIProvider {
     #[default]
     OpenAI,
     Ollama,
+    Anthropic,
+    #[serde(rename = "azure-openai")]
+    AzureOpenAI,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -166,6 +3417,149 @@ impl Default for OpenAIConfig {
     }
 }
 
+/// Anthropic's Claude models, via the Messages API.
+///
+/// API docs are [here](https://docs.anthropic.com/en/api/messages).
+/// curl https://api.anthropic.com/v1/messages \
+///   -H "x-api-key: $ANTHROPIC_API_KEY" -H "anthropic-version: 2023-06-01" \
+///   -H "content-type: application/json" -d '{
+///   "model": "claude-3-5-sonnet-latest",
+///   "max_tokens": 4096,
+///   "system": "You are a good robot.",
+///   "messages": [{"role": "user", "content": "Why is the sky blue?"}]
+/// }'
+#[derive(Clone, Debug, Deserialize)]
+pub struct AnthropicConfig {
+    /// The Anthropic model to use, (ie: "claude-3-5-sonnet-latest")
+    pub model: String,
+    /// The command to run to print the Anthropic API key. (If None, will look at
+    /// $ANTHROPIC_API_KEY)
+    #[serde(default = "default_anthropic_api_key_cmd")]
+    pub api_key_cmd: Vec<String>,
+    /// Defaults to https://api.anthropic.com/v1/messages.
+    #[serde(default = "default_anthropic_api_address")]
+    pub api_address: String,
+    /// The value of the `anthropic-version` header. Defaults to "2023-06-01".
+    #[serde(default = "default_anthropic_version")]
+    pub anthropic_version: String,
+    /// The most tokens to generate, which the Messages API requires. Defaults to 4096.
+    #[serde(default = "default_anthropic_max_tokens")]
+    pub max_tokens: u32,
+}
+
+impl Default for AnthropicConfig {
+    fn default() -> Self {
+        AnthropicConfig {
+            model: "claude-3-5-sonnet-latest".to_string(),
+            api_key_cmd: default_anthropic_api_key_cmd(),
+            api_address: default_anthropic_api_address(),
+            anthropic_version: default_anthropic_version(),
+            max_tokens: DEFAULT_ANTHROPIC_MAX_TOKENS,
+        }
+    }
+}
+
+impl AnthropicConfig {
+    /// The body of a Messages API request for `prompt`. The `system_prompt` goes in the top-level
+    /// `system` parameter, since the Messages API has no system role.
+    pub fn messages_request(&self, system_prompt: &str, prompt: &str) -> Value {
+        serde_json::json!({
+            "model": self.model,
+            "max_tokens": self.max_tokens,
+            "system": system_prompt,
+            "messages": [{ "role": "user", "content": prompt }],
+        })
+    }
+}
+
+/// OpenAI models deployed through Azure, which are addressed by resource endpoint, deployment name
+/// and API version, and authenticated with an `api-key` header rather than a bearer token.
+///
+/// API docs are [here](https://learn.microsoft.com/en-us/azure/ai-services/openai/reference).
+/// curl "$ENDPOINT/openai/deployments/$DEPLOYMENT/chat/completions?api-version=$API_VERSION" \
+///   -H "api-key: $AZURE_OPENAI_API_KEY" -H "content-type: application/json" -d '{
+///   "messages": [{"role": "user", "content": "Why is the sky blue?"}]
+/// }'
+#[derive(Clone, Debug, Deserialize)]
+pub struct AzureOpenAIConfig {
+    /// The resource endpoint, (ie: "https://my-resource.openai.azure.com").
+    #[serde(default)]
+    pub endpoint: String,
+    /// The name of the model deployment, as shown in the Azure portal, (ie: "gpt-4o-prod"). This
+    /// is the deployment's own name, which may differ from the model's.
+    #[serde(default)]
+    pub deployment: String,
+    /// The API version to request, (ie: "2024-06-01").
+    #[serde(default)]
+    pub api_version: String,
+    /// The command to run to print the Azure OpenAI key. (If None, will look at
+    /// $AZURE_OPENAI_API_KEY)
+    #[serde(default = "default_azure_openai_api_key_cmd")]
+    pub api_key_cmd: Vec<String>,
+}
+
+impl AzureOpenAIConfig {
+    /// Check that `endpoint`, `deployment` and `api_version` are set.
+    pub fn validate(&self) -> Result<(), PicklsConfigError> {
+        if self.endpoint.is_empty() {
+            return Err(PicklsConfigError::new(
+                "endpoint",
+                "endpoint is required (ie: \"https://my-resource.openai.azure.com\")",
+            ));
+        }
+        if self.deployment.is_empty() {
+            return Err(PicklsConfigError::new(
+                "deployment",
+                "deployment is required: use the name of the model deployment from the Azure \
+                 portal, which may differ from the model's name",
+            ));
+        }
+        if self.api_version.is_empty() {
+            return Err(PicklsConfigError::new(
+                "api_version",
+                "api_version is required (ie: \"2024-06-01\")",
+            ));
+        }
+        Ok(())
+    }
+
+    /// The URL of the chat completions API of `deployment`.
+    pub fn chat_completions_url(&self) -> String {
+        format!(
+            "{endpoint}/openai/deployments/{deployment}/chat/completions?api-version={api_version}",
+            endpoint = self.endpoint.trim_end_matches('/'),
+            deployment = self.deployment,
+            api_version = self.api_version,
+        )
+    }
+}
+
+fn default_azure_openai_api_key_cmd() -> Vec<String> {
+    ["sh", "-c", "echo $AZURE_OPENAI_API_KEY"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+fn default_anthropic_api_key_cmd() -> Vec<String> {
+    ["sh", "-c", "echo $ANTHROPIC_API_KEY"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+fn default_anthropic_api_address() -> String {
+    "https://api.anthropic.com/v1/messages".to_string()
+}
+
+fn default_anthropic_version() -> String {
+    "2023-06-01".to_string()
+}
+
+fn default_anthropic_max_tokens() -> u32 {
+    DEFAULT_ANTHROPIC_MAX_TOKENS
+}
+
 fn default_openai_api_key_cmd() -> Vec<String> {
     ["sh", "-c", "echo $OPENAI_API_KEY"]
         .into_iter()
@@ -187,3 +3581,1151 @@ fn default_inline_assist_system_prompt() -> String {
         to replace code in the editor."
         .to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PicklsCompiledFormatter;
+    use serde_json::{json, Value};
+
+    fn linter(config: Value) -> PicklsLinterConfig {
+        serde_json::from_value(config).expect("valid linter config")
+    }
+
+    fn formatter(config: Value) -> PicklsFormatterConfig {
+        serde_json::from_value(config).expect("valid formatter config")
+    }
+
+    fn language(config: Value) -> PicklsLanguageConfig {
+        serde_json::from_value(config).expect("valid language config")
+    }
+
+    #[test]
+    fn test_display_name_of_quoted_command() {
+        let command = "'/opt/my tools/lint' --x";
+        assert_eq!(linter(json!({"command": command})).display_name(), "lint");
+        assert_eq!(
+            formatter(json!({"command": command})).display_name(),
+            "lint"
+        );
+        assert_eq!(
+            linter(json!({"program": "/usr/bin/ruff"})).display_name(),
+            "ruff"
+        );
+        assert_eq!(
+            linter(json!({"name": "mine", "command": command})).display_name(),
+            "mine"
+        );
+    }
+
+    #[test]
+    fn test_command_is_exclusive_with_program_and_args() {
+        let command = "ruff check '$filename'";
+        let linter = |mut config: Value| {
+            config["pattern"] = json!(r"^(\d+): (.*)$");
+            config["line_match"] = json!(1);
+            linter(config)
+        };
+        assert!(linter(json!({"command": command})).validate().is_ok());
+        assert!(linter(json!({"program": "ruff", "command": command}))
+            .validate()
+            .is_err());
+        assert!(linter(json!({"command": command, "args": ["-"]}))
+            .validate()
+            .is_err());
+        assert!(formatter(json!({"program": "ruff", "command": command}))
+            .validate()
+            .is_err());
+        assert!(linter(json!({"command": "ruff 'open"})).validate().is_err());
+        assert_eq!(
+            linter(json!({"command": command})).command_line(&[]),
+            ["ruff", "check", "$filename"]
+        );
+    }
+
+    #[test]
+    fn test_linter_include_and_exclude_globs() {
+        let applies = |config: Value, path: &str| linter(config).applies_to(Path::new(path));
+        let everywhere = json!({"program": "eslint"});
+        assert!(applies(everywhere.clone(), "apps/web/src/index.js"));
+        assert!(applies(everywhere, "packages/legacy/x.js"));
+
+        let include = json!({"program": "eslint", "include_globs": ["apps/web/**"]});
+        assert!(applies(include.clone(), "apps/web/src/index.js"));
+        assert!(!applies(include.clone(), "packages/legacy/x.js"));
+        assert!(!applies(include, "apps/webby/index.js"));
+
+        let both = json!({
+            "program": "eslint",
+            "include_globs": ["apps/web/**", "packages/**"],
+            "exclude_globs": ["**/dist/**", "packages/legacy/**"],
+        });
+        assert!(applies(both.clone(), "apps/web/src/index.js"));
+        assert!(applies(both.clone(), "packages/new/index.js"));
+        assert!(!applies(both.clone(), "apps/web/dist/bundle.js"));
+        assert!(!applies(both.clone(), "packages/legacy/x.js"));
+        assert!(!applies(both, "tools/build.js"));
+
+        let exclude = json!({"program": "eslint", "exclude_globs": ["**/*.min.js"]});
+        assert!(!applies(exclude.clone(), "vendor/jquery.min.js"));
+        assert!(applies(exclude, "vendor/jquery.js"));
+    }
+
+    #[test]
+    fn test_tmpfile_path() {
+        let filename = Path::new("/repo/src/App.test.tsx");
+        let temp_dir = Path::new("/tmp");
+        let path = |config: Value| linter(config).tmpfile_path(filename, temp_dir, "12-3");
+        assert_eq!(
+            path(json!({"program": "eslint"})),
+            Path::new("/tmp/App.test.pickls-12-3.tsx")
+        );
+        let suffixed = path(json!({"program": "go", "tmpfile_suffix": "_test.go"}));
+        assert!(suffixed.to_string_lossy().ends_with("_test.go"));
+        assert_eq!(suffixed.parent(), Some(temp_dir));
+        assert_eq!(
+            path(json!({"program": "eslint", "tmpfile_in_source_dir": true})),
+            Path::new("/repo/src/App.test.pickls-12-3.tsx")
+        );
+        assert_eq!(
+            linter(json!({"program": "shellcheck"})).tmpfile_path(
+                Path::new("/repo/bin/run"),
+                temp_dir,
+                "1"
+            ),
+            Path::new("/tmp/run.pickls-1")
+        );
+        assert_eq!(
+            formatter(json!({"program": "prettier", "tmpfile_suffix": ".mjs"}))
+                .tmpfile_path(filename, temp_dir, "9"),
+            Path::new("/tmp/App.test.pickls-9.mjs")
+        );
+    }
+
+    fn container(config: Value) -> PicklsContainerConfig {
+        serde_json::from_value(config).expect("valid container config")
+    }
+
+    /// A container mapping the root directory, a vendored directory within it, and a tools
+    /// directory outside it.
+    fn mapped_container(mode: &str) -> PicklsContainerConfig {
+        container(json!({
+            "engine": "podman",
+            "image_or_name": "devbox",
+            "mode": mode,
+            "workdir_map": {
+                "$rootdir": "/workspace",
+                "$rootdir/vendor": "/vendor",
+                "/opt/tools": "/tools",
+            },
+        }))
+    }
+
+    #[test]
+    fn test_container_map_path() {
+        let container = mapped_container("exec");
+        let substitutions =
+            PicklsSubstitutions::new(Path::new("/repo/src/app.py"), Path::new("/repo"));
+        let map = |path: &str| container.map_path(Path::new(path), &substitutions);
+        assert_eq!(
+            map("/repo/src/app.py"),
+            Some("/workspace/src/app.py".into())
+        );
+        assert_eq!(map("/repo"), Some("/workspace".into()));
+        // The longest matching host directory wins, whichever order the map is in.
+        assert_eq!(
+            map("/repo/vendor/lib/x.py"),
+            Some("/vendor/lib/x.py".into())
+        );
+        assert_eq!(map("/opt/tools/bin/ruff"), Some("/tools/bin/ruff".into()));
+        // Prefixes match whole path components only.
+        assert_eq!(map("/repository/app.py"), None);
+        assert_eq!(map("/tmp/pickls-1.py"), None);
+
+        let unmap = |path: &str| container.unmap_path(Path::new(path), &substitutions);
+        assert_eq!(
+            unmap("/workspace/src/app.py"),
+            Path::new("/repo/src/app.py")
+        );
+        assert_eq!(
+            unmap("/vendor/lib/x.py"),
+            Path::new("/repo/vendor/lib/x.py")
+        );
+        assert_eq!(
+            unmap("/workspace/vendor/y.py"),
+            Path::new("/repo/vendor/y.py")
+        );
+        assert_eq!(
+            unmap("/usr/lib/python3/z.py"),
+            Path::new("/usr/lib/python3/z.py")
+        );
+        assert_eq!(unmap("src/app.py"), Path::new("src/app.py"));
+    }
+
+    #[test]
+    fn test_container_substitutions() {
+        let container = mapped_container("exec");
+        let substitutions = PicklsSubstitutions {
+            tmpfilename: Some("/tmp/pickls-1.py".to_string()),
+            ..PicklsSubstitutions::new(Path::new("/repo/src/app.py"), Path::new("/repo"))
+        };
+        let mapped = container.container_substitutions(&substitutions);
+        assert_eq!(mapped.filename.as_deref(), Some("/workspace/src/app.py"));
+        assert_eq!(mapped.dirname.as_deref(), Some("/workspace/src"));
+        assert_eq!(mapped.rootdir.as_deref(), Some("/workspace"));
+        // Paths outside every mapped directory, and variables that are not paths, are kept.
+        assert_eq!(mapped.tmpfilename.as_deref(), Some("/tmp/pickls-1.py"));
+        assert_eq!(mapped.basename, substitutions.basename);
+        assert_eq!(mapped.ext.as_deref(), Some("py"));
+    }
+
+    #[test]
+    fn test_container_wrapper() {
+        let substitutions =
+            PicklsSubstitutions::new(Path::new("/repo/src/app.py"), Path::new("/repo"));
+        let cwd = Some(Path::new("/repo/src"));
+        assert_eq!(
+            mapped_container("exec").wrapper(true, cwd, &substitutions),
+            ["podman", "exec", "-i", "-w", "/workspace/src", "devbox"]
+        );
+        // A fresh container bind-mounts each mapped directory, in a stable order. Without stdin
+        // there is no "-i", and an unmapped working directory is left to the image.
+        assert_eq!(
+            mapped_container("run").wrapper(false, Some(Path::new("/tmp")), &substitutions),
+            [
+                "podman",
+                "run",
+                "--rm",
+                "-v",
+                "/repo:/workspace",
+                "-v",
+                "/repo/vendor:/vendor",
+                "-v",
+                "/opt/tools:/tools",
+                "devbox",
+            ]
+        );
+        let docker = container(json!({"image_or_name": "devbox"}));
+        assert_eq!(
+            docker.wrapper(false, None, &substitutions),
+            ["docker", "exec", "devbox"]
+        );
+    }
+
+    #[test]
+    fn test_container_path_variables_require_a_mapping() {
+        let error = linter(json!({
+            "program": "ruff",
+            "args": ["check", "$filename"],
+            "pattern": r"^(\d+): (.*)$",
+            "line_match": 1,
+            "container": {"image_or_name": "devbox"},
+        }))
+        .validate()
+        .unwrap_err();
+        assert_eq!(
+            (error.location.as_str(), error.field.as_str()),
+            ("container", "workdir_map")
+        );
+        assert_eq!(
+            error.message,
+            "workdir_map is required since args uses $filename"
+        );
+        // Reading stdin needs no mapping.
+        assert!(linter(json!({
+            "program": "ruff",
+            "args": ["check", "-"],
+            "pattern": r"^(\d+): (.*)$",
+            "line_match": 1,
+            "container": {"image_or_name": "devbox"},
+        }))
+        .validate()
+        .is_ok());
+        let error =
+            container(json!({"image_or_name": "devbox", "workdir_map": {"$rootdir": "src"}}))
+                .validate(None)
+                .unwrap_err();
+        assert_eq!(error.message, "container path 'src' must be absolute");
+    }
+
+    #[test]
+    fn test_container_tmpfilename_requires_tmpfile_in_source_dir() {
+        let mut config = json!({
+            "program": "tsc",
+            "args": ["--noEmit", "$tmpfilename"],
+            "pattern": r"^(\d+): (.*)$",
+            "line_match": 1,
+            "container": {"image_or_name": "devbox", "workdir_map": {"$rootdir": "/workspace"}},
+        });
+        let error = linter(config.clone()).validate().unwrap_err();
+        assert_eq!(error.field, "tmpfile_in_source_dir");
+        assert_eq!(
+            error.message,
+            "tmpfile_in_source_dir is required with a container since args uses $tmpfilename"
+        );
+        config["tmpfile_in_source_dir"] = json!(true);
+        assert!(linter(config).validate().is_ok());
+    }
+
+    fn daemon(config: Value) -> PicklsDaemonConfig {
+        serde_json::from_value(config).expect("valid daemon config")
+    }
+
+    #[test]
+    fn test_daemon_deserialization() {
+        let dmypy = daemon(json!({
+            "start_args": ["start", "--", "--strict"],
+            "check_args": ["check", "$filename"],
+            "stop_args": ["stop"],
+            "idle_timeout_ms": 30000,
+        }));
+        assert_eq!(dmypy.start_args, ["start", "--", "--strict"]);
+        assert_eq!(
+            dmypy.check_args,
+            Some(vec!["check".into(), "$filename".into()])
+        );
+        assert_eq!(dmypy.stop_args, Some(vec!["stop".into()]));
+        assert_eq!(dmypy.idle_timeout_ms, Some(30000));
+        assert!(dmypy.validate().is_ok());
+        assert_eq!(dmypy.request_line(&PicklsSubstitutions::default()), None);
+
+        // start_args defaults to none, and idle_timeout_ms to ten minutes unless set to null.
+        let server = daemon(json!({
+            "check_line": "check $filename",
+            "response_end_pattern": "^done$",
+        }));
+        assert!(server.start_args.is_empty());
+        assert_eq!(server.idle_timeout_ms, Some(600_000));
+        assert!(server.validate().is_ok());
+        let forever = daemon(json!({"check_args": [], "idle_timeout_ms": null}));
+        assert_eq!(forever.idle_timeout_ms, None);
+    }
+
+    #[test]
+    fn test_daemon_request_modes_are_exclusive() {
+        let cases = [
+            (
+                json!({"check_args": ["check"], "check_line": "check", "response_end_pattern": "^$"}),
+                "check_line",
+                "check_args and check_line are mutually exclusive",
+            ),
+            (
+                json!({"start_args": ["start"]}),
+                "check_args",
+                "one of check_args or check_line is required",
+            ),
+            (
+                json!({"check_line": "check $filename"}),
+                "response_end_pattern",
+                "response_end_pattern is required with check_line",
+            ),
+            (
+                json!({"check_args": ["check"], "response_end_pattern": "^done$"}),
+                "response_end_pattern",
+                "response_end_pattern is only used with check_line",
+            ),
+        ];
+        for (config, field, message) in cases {
+            let error = daemon(config).validate().unwrap_err();
+            assert_eq!(
+                (error.field.as_str(), error.message.as_str()),
+                (field, message)
+            );
+        }
+        let error = daemon(json!({"check_line": "check", "response_end_pattern": "(["}))
+            .validate()
+            .unwrap_err();
+        assert_eq!(error.field, "response_end_pattern");
+        // Errors are reported within the linter's daemon.
+        let error = linter(json!({
+            "program": "dmypy",
+            "pattern": r"^(\d+): (.*)$",
+            "line_match": 1,
+            "daemon": {"start_args": ["start"]},
+        }))
+        .validate()
+        .unwrap_err();
+        assert_eq!(
+            (error.location.as_str(), error.field.as_str()),
+            ("daemon", "check_args")
+        );
+    }
+
+    #[test]
+    fn test_daemon_request_line() {
+        let server = daemon(json!({
+            "check_line": "{\"check\": \"$filename\", \"root\": \"$rootdir\"}",
+            "response_end_pattern": "^\\}$",
+        }));
+        let substitutions =
+            PicklsSubstitutions::new(Path::new("/repo/src/app.py"), Path::new("/repo"));
+        assert_eq!(
+            server.request_line(&substitutions).as_deref(),
+            Some("{\"check\": \"/repo/src/app.py\", \"root\": \"/repo\"}\n")
+        );
+        // The protocol line is checked for unknown variables like any other template.
+        let warnings = linter(json!({
+            "program": "server",
+            "pattern": r"^(\d+): (.*)$",
+            "line_match": 1,
+            "daemon": {"check_line": "check $filenme", "response_end_pattern": "^done$"},
+        }))
+        .warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].field, "daemon.check_line");
+    }
+
+    fn config(config: Value) -> PicklsConfig {
+        serde_json::from_value(config).expect("valid config")
+    }
+
+    #[test]
+    fn test_extends_cycle() {
+        let error = config(json!({
+            "linter_definitions": {
+                "a": {"extends": "b"},
+                "b": {"extends": "c"},
+                "c": {"extends": "a"},
+            },
+            "languages": {"python": {"linters": ["a"]}},
+        }))
+        .resolve()
+        .unwrap_err();
+        assert_eq!(error.field, "extends");
+        // Definitions are resolved in no particular order, so the cycle may start at any of them.
+        let cycles = ["a -> b -> c -> a", "b -> c -> a -> b", "c -> a -> b -> c"];
+        assert!(cycles
+            .iter()
+            .any(|cycle| error.message == format!("cycle of linter definitions: {cycle}")));
+
+        let error = config(json!({
+            "linter_definitions": {"mine": {"extends": "mine"}},
+        }))
+        .resolve()
+        .unwrap_err();
+        assert_eq!(error.message, "cycle of linter definitions: mine -> mine");
+    }
+
+    #[test]
+    fn test_definition_overrides_preset_of_the_same_name() {
+        let ruff_args = json!(["check", "--select", "E", "-"]);
+        for definition in [
+            json!({"extends": "ruff", "args": ruff_args}),
+            json!({"preset": "ruff", "args": ruff_args}),
+        ] {
+            let resolved = config(json!({
+                "linter_definitions": {
+                    "ruff": definition,
+                    "strict-ruff": {"extends": "ruff", "name": "strict-ruff"},
+                },
+                "languages": {"python": {"linters": ["ruff", "strict-ruff"]}},
+            }))
+            .resolve()
+            .expect("no cycle");
+            let preset = PicklsLinterConfig::preset("ruff").unwrap();
+            for linter in &resolved.languages["python"].linters {
+                assert_eq!(linter.args, ["check", "--select", "E", "-"]);
+                assert_eq!(linter.pattern, preset.pattern);
+                assert_eq!(linter.program, "ruff");
+            }
+            assert_eq!(
+                resolved.languages["python"].linters[1].display_name(),
+                "strict-ruff"
+            );
+        }
+    }
+
+    #[test]
+    fn test_extends_unknown_definition() {
+        let error = config(json!({
+            "languages": {"python": {"linters": [{"extends": "nope"}]}},
+        }))
+        .resolve()
+        .unwrap_err();
+        assert_eq!(error.location, "languages.python.linters[0]");
+        assert_eq!(error.message, "unknown linter definition 'nope'");
+    }
+
+    #[test]
+    fn test_foreign_files() {
+        let current = Path::new("/repo/src/main.rs");
+        let (working_dir, root_dir) = (Path::new("/repo/sub"), Path::new("/repo"));
+        let path = |foreign_files: &str, captured: &str| {
+            linter(json!({"program": "lint", "foreign_files": foreign_files})).diagnostic_path(
+                Some(captured),
+                current,
+                working_dir,
+                root_dir,
+            )
+        };
+        for foreign_files in ["drop", "publish", "remap_to_current"] {
+            for captured in ["/repo/src/main.rs", "../src/main.rs", "./../src/./main.rs"] {
+                assert_eq!(path(foreign_files, captured).as_deref(), Some(current));
+            }
+        }
+        assert_eq!(path("drop", "/repo/src/lib.rs"), None);
+        assert_eq!(path("drop", "lib.rs"), None);
+        assert_eq!(
+            path("publish", "/repo/src/lib.rs").as_deref(),
+            Some(Path::new("/repo/src/lib.rs"))
+        );
+        assert_eq!(
+            path("publish", "lib.rs").as_deref(),
+            Some(Path::new("/repo/sub/lib.rs"))
+        );
+        assert_eq!(path("remap_to_current", "lib.rs").as_deref(), Some(current));
+        assert_eq!(
+            path("remap_to_current", "/elsewhere/lib.rs").as_deref(),
+            Some(current)
+        );
+    }
+
+    #[test]
+    fn test_workspace_scope_publishes_foreign_files() {
+        let linter = linter(json!({"program": "lint", "scope": "workspace"}));
+        assert_eq!(
+            linter
+                .diagnostic_path(
+                    Some("src/lib.rs"),
+                    Path::new("/repo/src/main.rs"),
+                    Path::new("/repo"),
+                    Path::new("/repo"),
+                )
+                .as_deref(),
+            Some(Path::new("/repo/src/lib.rs"))
+        );
+    }
+
+    #[test]
+    fn test_azure_openai_is_only_validated_when_selected() {
+        let ai = |config: Value| -> PicklsAIConfig {
+            serde_json::from_value(config).expect("valid ai config")
+        };
+        let partial = json!({"endpoint": "https://my-resource.openai.azure.com"});
+        assert!(ai(json!({
+            "inline_assist_provider": "anthropic",
+            "azure_openai": partial,
+        }))
+        .validate()
+        .is_ok());
+        let error = ai(json!({
+            "inline_assist_provider": "azure-openai",
+            "azure_openai": partial,
+        }))
+        .validate()
+        .unwrap_err();
+        assert_eq!(
+            (error.location.as_str(), error.field.as_str()),
+            ("azure_openai", "deployment")
+        );
+        assert!(ai(json!({"inline_assist_provider": "azure-openai"}))
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_check_version() {
+        let ruff = linter(json!({
+            "program": "ruff",
+            "version_args": ["--version"],
+            "min_version": "0.4",
+        }));
+        assert!(ruff.check_version("ruff 0.4.10\n").is_ok());
+        assert!(ruff.check_version("ruff 0.4\n").is_ok());
+        assert_eq!(
+            ruff.check_version("ruff 0.3.7\n"),
+            Err("ruff: version 0.3.7 is older than the required 0.4".to_string())
+        );
+        assert_eq!(
+            ruff.check_version("ruff, unknown version"),
+            Err("ruff: could not find a version in the output of the version check".to_string())
+        );
+        let node = linter(json!({
+            "program": "eslint",
+            "version_args": ["--version"],
+            "version_pattern": r"^v(\S+)",
+            "min_version": "v9",
+        }));
+        assert!(node.check_version("v9.1.0").is_ok());
+        assert!(node.check_version("v8.57.0").is_err());
+        let unchecked = linter(json!({"program": "ruff", "version_args": ["--version"]}));
+        assert!(unchecked.check_version("ruff 0.0.1").is_ok());
+        let invalid = |config: Value| linter(config).validate_version().is_err();
+        assert!(invalid(json!({"program": "ruff", "min_version": "0.4"})));
+        assert!(invalid(json!({
+            "program": "ruff",
+            "version_args": ["--version"],
+            "min_version": "latest",
+        })));
+        assert!(invalid(
+            json!({"program": "ruff", "version_pattern": r"\d+"})
+        ));
+    }
+
+    #[test]
+    fn test_lint_cache() {
+        let default: PicklsLintCacheConfig =
+            serde_json::from_value(json!({"max_entries": 16})).expect("valid cache");
+        let own = json!({"max_entries": 4, "ttl_ms": 1000});
+        let max_entries = |config: Value, default: Option<&PicklsLintCacheConfig>| {
+            linter(config)
+                .lint_cache(default)
+                .map(|cache| cache.max_entries)
+        };
+        assert_eq!(max_entries(json!({"program": "ruff"}), None), None);
+        assert_eq!(
+            max_entries(json!({"program": "ruff"}), Some(&default)),
+            Some(16)
+        );
+        assert_eq!(
+            max_entries(json!({"program": "ruff", "cache": own}), Some(&default)),
+            Some(4)
+        );
+        assert_eq!(
+            max_entries(
+                json!({"program": "ruff", "cache": {"enabled": false}}),
+                Some(&default)
+            ),
+            None
+        );
+        // Workspace-scoped linters check more than the document, so are never cached.
+        assert_eq!(
+            max_entries(
+                json!({"program": "mypy", "scope": "workspace", "cache": own}),
+                Some(&default)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_formatter_timeout_applies_per_stage() {
+        let language = language(json!({
+            "formatters": [
+                {"program": "isort", "args": ["-"]},
+                {"program": "prettierd", "args": ["$filename"], "timeout_ms": 500},
+                {"program": "black", "args": ["-"], "timeout_ms": null},
+            ],
+        }));
+        let (filename, root_dir) = (Path::new("/repo/app.py"), Path::new("/repo"));
+        let timeouts: Vec<_> = language
+            .active_formatters(filename, root_dir)
+            .map(|formatter| (formatter.display_name(), formatter.timeout_ms))
+            .collect();
+        assert_eq!(
+            timeouts,
+            [
+                ("isort".into(), Some(DEFAULT_FORMATTER_TIMEOUT_MS)),
+                ("prettierd".into(), Some(500)),
+                ("black".into(), None),
+            ]
+        );
+        // A stage killed at its timeout fails, aborting the chain, whatever its other settings.
+        let killed = compiled_formatter(json!({
+            "program": "prettierd",
+            "exit_code_indicates_error": false,
+        }));
+        assert_eq!(
+            killed.check_result(None, ""),
+            Err("formatter prettierd was killed by a signal".to_string())
+        );
+    }
+
+    fn compiled_formatter(config: Value) -> PicklsCompiledFormatter {
+        formatter(config).compile().expect("valid formatter")
+    }
+
+    #[test]
+    fn test_formatter_check_result() {
+        // Each combination of the two flags, against each combination of a failing exit status
+        // and output on stderr.
+        let warning = "Warning: can't set `wrap_comments = true`, unstable features are only \
+                       available in nightly channel.";
+        for exit_code_indicates_error in [false, true] {
+            for stderr_indicates_error in [false, true] {
+                let rustfmt = compiled_formatter(json!({
+                    "program": "rustfmt",
+                    "exit_code_indicates_error": exit_code_indicates_error,
+                    "stderr_indicates_error": stderr_indicates_error,
+                }));
+                let flags = (exit_code_indicates_error, stderr_indicates_error);
+                assert!(rustfmt.check_result(Some(0), "").is_ok(), "{flags:?}");
+                assert_eq!(
+                    rustfmt.check_result(Some(0), warning).is_err(),
+                    stderr_indicates_error,
+                    "{flags:?}"
+                );
+                assert_eq!(
+                    rustfmt.check_result(Some(1), "").is_err(),
+                    exit_code_indicates_error,
+                    "{flags:?}"
+                );
+                assert_eq!(
+                    rustfmt.check_result(Some(1), warning).is_err(),
+                    exit_code_indicates_error || stderr_indicates_error,
+                    "{flags:?}"
+                );
+                assert!(rustfmt.check_result(None, "").is_err(), "{flags:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_formatter_check_result_messages() {
+        let rustfmt =
+            compiled_formatter(json!({"program": "rustfmt", "stderr_indicates_error": true}));
+        let stderr = "error: expected one of `.`, `;`, `?`, `}`, or an operator, found `let`\n";
+        assert_eq!(
+            rustfmt.check_result(Some(1), stderr),
+            Err(format!(
+                "formatter rustfmt exited with status 1: {}",
+                stderr.trim_end()
+            ))
+        );
+        assert_eq!(
+            rustfmt.check_result(Some(2), ""),
+            Err("formatter rustfmt exited with status 2".to_string())
+        );
+        assert_eq!(
+            rustfmt.check_result(Some(0), "Warning: unstable features\n"),
+            Err("formatter rustfmt wrote to stderr: Warning: unstable features".to_string())
+        );
+        // stderr_error_pattern takes precedence over stderr_indicates_error, but not over the
+        // exit status.
+        let prettier = compiled_formatter(json!({
+            "program": "prettier",
+            "stderr_indicates_error": true,
+            "stderr_error_pattern": r"^\[error\]",
+        }));
+        assert!(prettier.check_result(Some(0), "[warn] app.js\n").is_ok());
+        assert_eq!(
+            prettier.check_result(Some(0), "[warn] app.js\n[error] app.js: SyntaxError\n"),
+            Err("formatter prettier reported an error: [error] app.js: SyntaxError".to_string())
+        );
+        assert_eq!(
+            prettier.check_result(Some(2), "[warn] app.js\n"),
+            Err("formatter prettier exited with status 2: [warn] app.js".to_string())
+        );
+        // An invalid stderr_error_pattern fails to compile, rather than matching nothing.
+        let error = formatter(json!({"program": "prettier", "stderr_error_pattern": "(["}))
+            .compile()
+            .unwrap_err();
+        assert_eq!(error.field, "stderr_error_pattern");
+    }
+
+    #[test]
+    fn test_formatter_rejects_empty_output() {
+        let black = formatter(json!({"program": "black"}));
+        assert!(black.reject_empty_output);
+        assert_eq!(
+            black.check_output("x = 1\n", ""),
+            Err("formatter black produced no output".to_string())
+        );
+        assert!(black.check_output("x = 1\n", " \n\n").is_err());
+        // Blank input may format to blank output.
+        assert!(black.check_output("\n\n", "").is_ok());
+        let black = formatter(json!({"program": "black", "reject_empty_output": false}));
+        assert!(black.check_output("x = 1\n", "").is_ok());
+    }
+
+    #[test]
+    fn test_formatter_max_shrink_percent() {
+        let minifier = formatter(json!({"program": "minify", "max_shrink_percent": 25}));
+        let input = "x".repeat(100);
+        // Shrinking by exactly the limit is allowed, and by any more is not.
+        assert!(minifier.check_output(&input, &"x".repeat(75)).is_ok());
+        assert_eq!(
+            minifier.check_output(&input, &"x".repeat(74)),
+            Err(
+                "formatter minify shrank the file by 26%, more than max_shrink_percent (25%)"
+                    .to_string()
+            )
+        );
+        assert!(minifier.check_output(&input, &"x".repeat(300)).is_ok());
+        // Without a limit, any shrinkage short of empty output is fine.
+        let unlimited = formatter(json!({"program": "minify"}));
+        assert!(unlimited.check_output(&input, "x").is_ok());
+        let error = formatter(json!({"program": "minify", "max_shrink_percent": 101}))
+            .validate()
+            .unwrap_err();
+        assert_eq!(error.message, "max_shrink_percent must be at most 100");
+    }
+
+    #[test]
+    fn test_formatter_chain_skips_excluded_formatter() {
+        let language = language(json!({
+            "formatters": [
+                {"program": "goimports"},
+                {"program": "gofumpt", "exclude_globs": ["**/*.pb.go", "vendor/**"]},
+                {"program": "golines", "include_globs": ["cmd/**", "internal/**"]},
+            ],
+        }));
+        let root_dir = Path::new("/repo");
+        let chain = |filename: &str| -> Vec<String> {
+            let filename = root_dir.join(filename);
+            language
+                .active_formatters(&filename, root_dir)
+                .map(|formatter| formatter.display_name().into_owned())
+                .collect()
+        };
+        assert_eq!(
+            chain("cmd/server/main.go"),
+            ["goimports", "gofumpt", "golines"]
+        );
+        // The middle formatter is skipped, and the last one follows the first directly.
+        assert_eq!(chain("internal/api/api.pb.go"), ["goimports", "golines"]);
+        assert_eq!(chain("vendor/x/y.go"), ["goimports"]);
+        assert_eq!(chain("tools.go"), ["goimports", "gofumpt"]);
+    }
+
+    #[test]
+    fn test_formatter_chain_skips_oversized_file() {
+        // An oversized first stage skips the whole chain, even though the rest would accept it.
+        let (filename, root_dir) = (Path::new("/repo/app.js"), Path::new("/repo"));
+        let notice = |prettier: Value, text: &str| {
+            language(json!({"formatters": [prettier, {"program": "eslint_d"}]}))
+                .oversized_formatters_notice(filename, root_dir, text, Some(1024))
+        };
+        let prettier = json!({"program": "prettier", "max_file_size_kb": 1});
+        assert_eq!(notice(prettier.clone(), &"x".repeat(1024)), None);
+        assert_eq!(
+            notice(prettier, &"x".repeat(1025)).as_deref(),
+            Some("not formatting: the file is larger than 1KB for prettier")
+        );
+        // A formatter that does not apply to the file does not skip the chain.
+        let excluded =
+            json!({"program": "prettier", "max_file_size_kb": 1, "exclude_globs": ["*.js"]});
+        assert_eq!(notice(excluded, &"x".repeat(4096)), None);
+    }
+
+    #[test]
+    fn test_formatter_max_file_size_defaults_to_top_level() {
+        let config = config(json!({
+            "formatter_max_file_size_kb": 4,
+            "languages": {"python": {"formatters": [
+                {"program": "isort"},
+                {"program": "black", "max_file_size_kb": 8},
+            ]}},
+        }));
+        let default_max_file_size_kb = config.formatter_max_file_size_kb;
+        let [isort, black] = &config.languages["python"].formatters[..] else {
+            panic!("two formatters");
+        };
+        let (small, large) = ("x".repeat(4 * 1024 + 1), "x".repeat(8 * 1024 + 1));
+        // The first stage inherits the top-level default, which the second overrides.
+        assert_eq!(
+            isort.exceeded_max_file_size(&small, default_max_file_size_kb),
+            Some(4)
+        );
+        assert_eq!(
+            black.exceeded_max_file_size(&small, default_max_file_size_kb),
+            None
+        );
+        assert_eq!(black.exceeded_max_file_size(&large, Some(100)), Some(8));
+        assert_eq!(isort.exceeded_max_file_size(&large, None), None);
+        let (filename, root_dir) = (Path::new("/repo/app.py"), Path::new("/repo"));
+        assert_eq!(
+            config.languages["python"]
+                .oversized_formatters_notice(filename, root_dir, &small, default_max_file_size_kb)
+                .as_deref(),
+            Some("not formatting: the file is larger than 4KB for isort")
+        );
+    }
+
+    #[test]
+    fn test_normalize_formatted() {
+        let original = "a\r\nb\nc\r\n";
+        assert_eq!(
+            language(json!({})).normalize_formatted(original, "a\nb\nc"),
+            "a\r\nb\r\nc"
+        );
+        let language = language(json!({"newline": "lf", "ensure_final_newline": true}));
+        assert_eq!(
+            language.normalize_formatted(original, "a\r\nb\nc"),
+            "a\nb\nc\n"
+        );
+    }
+
+    #[test]
+    fn test_unknown_symbols_source_lists_valid_sources() {
+        for sources in [json!("gtags"), json!(["universal-ctags", "gtags"])] {
+            let error = serde_json::from_value::<PicklsSymbolsConfig>(json!({"sources": sources}))
+                .expect_err("gtags is not a source")
+                .to_string();
+            assert!(error.contains("unknown variant `gtags`"), "{error}");
+            for source in [
+                "universal-ctags",
+                "gnu-global",
+                "tree-sitter",
+                "regex",
+                "tags-file",
+            ] {
+                assert!(error.contains(&format!("`{source}`")), "{error}");
+            }
+        }
+        let symbols: PicklsSymbolsConfig =
+            serde_json::from_value(json!({"source": "gnu-global"})).expect("valid symbols config");
+        assert_eq!(symbols.sources, [PicklsSymbolsSource::GnuGlobal]);
+    }
+
+    #[test]
+    fn test_symbol_kind() {
+        let symbols: PicklsSymbolsConfig =
+            serde_json::from_value(json!({"source": "universal-ctags"})).expect("valid symbols");
+        assert_eq!(
+            symbols.symbol_kind(Some("Python"), "member"),
+            PicklsSymbolKind::Method
+        );
+        assert_eq!(
+            symbols.symbol_kind(Some("Go"), "talias"),
+            PicklsSymbolKind::TypeParameter
+        );
+        // Unmapped kinds are variables.
+        assert_eq!(
+            symbols.symbol_kind(Some("Go"), "label"),
+            PicklsSymbolKind::Variable
+        );
+        assert_eq!(symbols.symbol_kind(None, ""), PicklsSymbolKind::Variable);
+        let symbols: PicklsSymbolsConfig = serde_json::from_value(json!({
+            "source": "universal-ctags",
+            "kind_map": {"member": "field", "python:member": "property", "label": "key"},
+        }))
+        .expect("valid symbols");
+        // A scoped override beats an unscoped one, which in turn beats the built-in scoped key.
+        assert_eq!(
+            symbols.symbol_kind(Some("python"), "member"),
+            PicklsSymbolKind::Property
+        );
+        assert_eq!(
+            symbols.symbol_kind(Some("C"), "member"),
+            PicklsSymbolKind::Field
+        );
+        assert_eq!(
+            symbols.symbol_kind(Some("Go"), "label"),
+            PicklsSymbolKind::Key
+        );
+        // Kinds without an override still fall back to the built-in map.
+        assert_eq!(
+            symbols.symbol_kind(Some("Go"), "talias"),
+            PicklsSymbolKind::TypeParameter
+        );
+        let symbols: PicklsSymbolsConfig = serde_json::from_value(json!({
+            "source": "universal-ctags",
+            "kind_map": {"member": "field"},
+        }))
+        .expect("valid symbols");
+        assert_eq!(
+            symbols.symbol_kind(Some("Python"), "member"),
+            PicklsSymbolKind::Field
+        );
+        assert!(serde_json::from_value::<PicklsSymbolsConfig>(json!({
+            "source": "universal-ctags",
+            "kind_map": {"member": "attribute"},
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_tree_sitter_source() {
+        let symbols: PicklsSymbolsConfig = serde_json::from_value(json!({
+            "source": "tree-sitter",
+            "tree_sitter": {"queries": {"lua": "(function_declaration name: (_) @name) @definition.function"}},
+        }))
+        .expect("valid symbols config");
+        assert_eq!(symbols.sources, [PicklsSymbolsSource::TreeSitter]);
+        assert!(symbols.validate().is_ok());
+        assert!(symbols.tree_sitter.query_for("lua").is_some());
+        let symbols: PicklsSymbolsConfig = serde_json::from_value(json!({
+            "source": "tree-sitter",
+            "tree_sitter": {"queries": {"lua": " \n"}},
+        }))
+        .expect("valid symbols config");
+        assert_eq!(
+            symbols.validate().map_err(|error| error.to_string()),
+            Err("invalid tree_sitter.queries.lua: query may not be empty".to_string())
+        );
+    }
+
+    #[test]
+    fn test_tree_sitter_query_for() {
+        let defaults = PicklsTreeSitterConfig::default();
+        for language_id in ["rust", "python", "go", "javascript", "javascriptreact"] {
+            let query = defaults.query_for(language_id);
+            if cfg!(feature = "tree-sitter") {
+                let query = query.expect("built-in query");
+                assert!(query.contains("@definition."), "{language_id}: {query}");
+                assert!(query.contains("@name"), "{language_id}: {query}");
+            } else {
+                assert_eq!(query, None, "{language_id}");
+            }
+        }
+        assert_eq!(defaults.query_for("lua"), None);
+        assert_eq!(defaults.query_for("Rust"), None);
+    }
+
+    #[test]
+    fn test_tree_sitter_query_overrides() {
+        let tree_sitter: PicklsTreeSitterConfig = serde_json::from_value(json!({
+            "queries": {
+                "rust": "(function_item name: (identifier) @name) @definition.function",
+                "lua": "(function_declaration name: (_) @name) @definition.function",
+            },
+        }))
+        .expect("valid tree-sitter config");
+        assert_eq!(
+            tree_sitter.query_for("rust"),
+            Some("(function_item name: (identifier) @name) @definition.function")
+        );
+        assert_eq!(
+            tree_sitter.query_for("lua"),
+            Some("(function_declaration name: (_) @name) @definition.function")
+        );
+        // Languages without an override keep their built-in query, if any.
+        assert_eq!(
+            tree_sitter.query_for("python"),
+            PicklsTreeSitterConfig::default().query_for("python")
+        );
+    }
+
+    #[test]
+    fn test_symbols_for() {
+        let config = config(json!({
+            "symbols": {"source": "universal-ctags", "exclude_kinds": ["variable"]},
+            "languages": {
+                "python": {"symbols": {"source": "universal-ctags", "include_kinds": ["class"]}},
+                "go": {"symbols": {"sources": ["gnu-global", "universal-ctags"]}},
+                "c": {},
+            },
+        }));
+        let sources = |language_id: &str| {
+            config
+                .symbols_for(language_id)
+                .map(|symbols| symbols.sources.clone())
+        };
+        let include_kinds = |language_id: &str| {
+            config
+                .symbols_for(language_id)
+                .and_then(|symbols| symbols.include_kinds.clone())
+        };
+        assert_eq!(include_kinds("python"), Some(vec!["class".to_string()]));
+        assert_eq!(
+            sources("go"),
+            Some(vec![
+                PicklsSymbolsSource::GnuGlobal,
+                PicklsSymbolsSource::UniversalCtags
+            ])
+        );
+        // Languages without their own symbols, listed or not, inherit the top-level ones whole.
+        for language_id in ["c", "rust"] {
+            let symbols = config.symbols_for(language_id).expect("inherited symbols");
+            assert_eq!(symbols.exclude_kinds, Some(vec!["variable".to_string()]));
+        }
+        let config = PicklsConfig {
+            symbols: None,
+            ..config
+        };
+        assert!(config.symbols_for("c").is_none());
+        assert!(config.symbols_for("python").is_some());
+    }
+
+    #[test]
+    fn test_none_symbols_source() {
+        let symbols: PicklsSymbolsConfig =
+            serde_json::from_value(json!({"source": "none"})).expect("valid symbols config");
+        assert_eq!(symbols.sources, [PicklsSymbolsSource::None]);
+        assert!(!symbols.is_enabled());
+        let symbols: PicklsSymbolsConfig =
+            serde_json::from_value(json!({"sources": ["universal-ctags", "none", "gnu-global"]}))
+                .expect("valid symbols config");
+        assert!(symbols.is_enabled());
+        assert_eq!(
+            symbols.active_sources().collect::<Vec<_>>(),
+            [&PicklsSymbolsSource::UniversalCtags]
+        );
+        assert!(serde_json::from_value::<PicklsSymbolsConfig>(json!({"source": "None"})).is_err());
+    }
+
+    #[test]
+    fn test_advertises_symbols() {
+        let ctags = json!({"source": "universal-ctags"});
+        let none = json!({"source": "none"});
+        assert!(!config(json!({})).advertises_symbols());
+        assert!(config(json!({"symbols": ctags})).advertises_symbols());
+        assert!(!config(json!({"symbols": none})).advertises_symbols());
+        // A language that opts in is enough, and one that opts out leaves the others alone.
+        let opted_in = config(json!({
+            "symbols": none,
+            "languages": {"c": {"symbols": ctags}, "markdown": {}},
+        }));
+        assert!(opted_in.advertises_symbols());
+        assert!(opted_in.symbols_for("c").is_some());
+        assert!(opted_in.symbols_for("markdown").is_none());
+        let opted_out = config(json!({
+            "symbols": ctags,
+            "languages": {"markdown": {"symbols": none}},
+        }));
+        assert!(opted_out.advertises_symbols());
+        assert!(opted_out.symbols_for("markdown").is_none());
+        assert!(opted_out.symbols_for("python").is_some());
+    }
+
+    #[test]
+    fn test_anthropic_provider() {
+        let ai: PicklsAIConfig = serde_json::from_value(json!({
+            "inline_assist_provider": "anthropic",
+            "anthropic": {"model": "claude-3-5-haiku-latest"},
+        }))
+        .expect("valid ai config");
+        assert!(matches!(
+            ai.inline_assist_provider,
+            PicklsAIProvider::Anthropic
+        ));
+        assert!(ai.validate().is_ok());
+        let anthropic = ai.anthropic.expect("anthropic is set");
+        assert_eq!(anthropic.model, "claude-3-5-haiku-latest");
+        assert_eq!(
+            anthropic.api_key_cmd,
+            ["sh", "-c", "echo $ANTHROPIC_API_KEY"]
+        );
+        assert_eq!(
+            anthropic.api_address,
+            "https://api.anthropic.com/v1/messages"
+        );
+        assert_eq!(anthropic.anthropic_version, "2023-06-01");
+        assert_eq!(anthropic.max_tokens, DEFAULT_ANTHROPIC_MAX_TOKENS);
+        for name in ["Anthropic", "claude"] {
+            let provider = serde_json::from_value::<PicklsAIProvider>(json!(name));
+            assert!(provider.is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_anthropic_messages_request() {
+        let anthropic: AnthropicConfig = serde_json::from_value(json!({
+            "model": "claude-3-5-sonnet-latest",
+            "api_address": "https://llm-proxy.internal/v1/messages",
+            "anthropic_version": "2024-01-01",
+            "max_tokens": 1024,
+        }))
+        .expect("valid anthropic config");
+        assert_eq!(
+            anthropic.api_address,
+            "https://llm-proxy.internal/v1/messages"
+        );
+        assert_eq!(anthropic.anthropic_version, "2024-01-01");
+        // The system prompt is a top-level parameter, never a message.
+        assert_eq!(
+            anthropic.messages_request("You are a good robot.", "Why is the sky blue?"),
+            json!({
+                "model": "claude-3-5-sonnet-latest",
+                "max_tokens": 1024,
+                "system": "You are a good robot.",
+                "messages": [{"role": "user", "content": "Why is the sky blue?"}],
+            })
+        );
+        assert_eq!(
+            AnthropicConfig::default().messages_request("", "x")["model"],
+            "claude-3-5-sonnet-latest"
+        );
+    }
+}
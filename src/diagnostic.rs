@@ -0,0 +1,171 @@
+use crate::{PicklsDiagnosticTag, PicklsSeverity};
+use serde::Serialize;
+use std::{collections::HashMap, path::PathBuf};
+
+/// A zero-based position within a document. `character` is measured in UTF-16 code units, as in
+/// an LSP position, whatever `column_unit` the linter reports columns in.
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug, Default, Serialize)]
+pub struct PicklsPosition {
+    pub line: u32,
+    pub character: u32,
+}
+
+/// A range within a document. `end` is exclusive.
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug, Default, Serialize)]
+pub struct PicklsRange {
+    pub start: PicklsPosition,
+    pub end: PicklsPosition,
+}
+
+/// A secondary location of a diagnostic, (ie: "first defined here").
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+pub struct PicklsRelatedInformation {
+    pub file: PathBuf,
+    pub range: PicklsRange,
+    pub message: String,
+}
+
+/// A diagnostic produced from a linter's output. See [`crate::PicklsLinterConfig::parse_output`].
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+pub struct PicklsDiagnostic {
+    /// The file the diagnostic belongs to.
+    pub file: PathBuf,
+    pub range: PicklsRange,
+    pub severity: PicklsSeverity,
+    pub code: Option<String>,
+    /// A documentation link for `code`.
+    pub code_url: Option<String>,
+    /// The name of the linter that produced the diagnostic.
+    pub source: String,
+    pub message: String,
+    pub tags: Vec<PicklsDiagnosticTag>,
+    pub related: Vec<PicklsRelatedInformation>,
+    /// Replacement text for `range`, offered as a quick fix.
+    pub fix_text: Option<String>,
+    /// Whether the linter's autofix can fix this diagnostic.
+    pub fixable: bool,
+}
+
+/// Drop each diagnostic that duplicates one from a different linter earlier in `diagnostics`,
+/// where diagnostics duplicate each other when their file, range and message are all identical.
+/// Given diagnostics in the order their linters are listed, the first linter wins. Duplicates
+/// from a single linter are all kept. See `dedupe_diagnostics` in
+/// [`crate::PicklsLanguageConfig`].
+pub fn dedupe_diagnostics(diagnostics: &mut Vec<PicklsDiagnostic>) {
+    let mut sources: HashMap<(PathBuf, PicklsRange, String), String> = HashMap::new();
+    diagnostics.retain(|diagnostic| {
+        let key = (
+            diagnostic.file.clone(),
+            diagnostic.range,
+            diagnostic.message.clone(),
+        );
+        let source = sources
+            .entry(key)
+            .or_insert_with(|| diagnostic.source.clone());
+        *source == diagnostic.source
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(source: &str, line: u32, message: &str) -> PicklsDiagnostic {
+        let position = PicklsPosition { line, character: 0 };
+        PicklsDiagnostic {
+            file: PathBuf::from("/repo/app.py"),
+            range: PicklsRange {
+                start: position,
+                end: PicklsPosition {
+                    line,
+                    character: 10,
+                },
+            },
+            severity: PicklsSeverity::Warning,
+            code: None,
+            code_url: None,
+            source: source.to_string(),
+            message: message.to_string(),
+            tags: Vec::new(),
+            related: Vec::new(),
+            fix_text: None,
+            fixable: false,
+        }
+    }
+
+    fn deduped(mut diagnostics: Vec<PicklsDiagnostic>) -> Vec<(String, u32, String)> {
+        dedupe_diagnostics(&mut diagnostics);
+        diagnostics
+            .into_iter()
+            .map(|d| (d.source, d.range.start.line, d.message))
+            .collect()
+    }
+
+    fn summary(source: &str, line: u32, message: &str) -> (String, u32, String) {
+        (source.to_string(), line, message.to_string())
+    }
+
+    #[test]
+    fn test_first_linter_wins() {
+        let unused = "`os` imported but unused";
+        let diagnostics = vec![
+            diagnostic("ruff", 0, unused),
+            diagnostic("ruff", 4, "line too long"),
+            diagnostic("pylint", 0, unused),
+            diagnostic("pylint", 4, "Line too long (120/100)"),
+            diagnostic("mypy", 0, unused),
+        ];
+        assert_eq!(
+            deduped(diagnostics),
+            [
+                summary("ruff", 0, unused),
+                summary("ruff", 4, "line too long"),
+                summary("pylint", 4, "Line too long (120/100)"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_key_is_file_range_and_message() {
+        let mut other_file = diagnostic("pylint", 0, "unused");
+        other_file.file = PathBuf::from("/repo/other.py");
+        let mut other_range = diagnostic("pylint", 0, "unused");
+        other_range.range.end.character = 3;
+        let mut other_severity = diagnostic("pylint", 0, "unused");
+        other_severity.severity = PicklsSeverity::Error;
+        other_severity.code = Some("W0611".to_string());
+        let diagnostics = vec![
+            diagnostic("ruff", 0, "unused"),
+            other_file,
+            other_range,
+            diagnostic("pylint", 1, "unused"),
+            other_severity,
+        ];
+        let sources: Vec<_> = deduped(diagnostics)
+            .into_iter()
+            .map(|(source, line, _)| (source, line))
+            .collect();
+        assert_eq!(
+            sources,
+            [
+                ("ruff".to_string(), 0),
+                ("pylint".to_string(), 0),
+                ("pylint".to_string(), 0),
+                ("pylint".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicates_from_one_linter_are_kept() {
+        let diagnostics = vec![
+            diagnostic("ruff", 0, "unused"),
+            diagnostic("ruff", 0, "unused"),
+            diagnostic("pylint", 0, "unused"),
+        ];
+        assert_eq!(
+            deduped(diagnostics),
+            [summary("ruff", 0, "unused"), summary("ruff", 0, "unused")]
+        );
+    }
+}
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// Extra metadata about a diagnostic, mirroring LSP's `DiagnosticTag`. Editors typically render
+/// unnecessary code faded out and deprecated code struck through.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PicklsDiagnosticTag {
+    Unnecessary,
+    Deprecated,
+}
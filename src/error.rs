@@ -0,0 +1,51 @@
+use std::fmt;
+
+/// An error found while validating a pickls configuration.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PicklsConfigError {
+    /// Where in the configuration the offending field lives, (ie:
+    /// `languages.python.linters[0](ruff)`). Empty when the error was found at the top level of
+    /// the value being validated.
+    pub location: String,
+    /// The offending configuration field, (ie: "end_line_match").
+    pub field: String,
+    /// A human readable description of the problem.
+    pub message: String,
+}
+
+impl PicklsConfigError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        PicklsConfigError {
+            location: String::new(),
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+
+    /// Prefix the location of this error with the location of its enclosing value, (ie:
+    /// `linters[0]`).
+    pub fn within(mut self, location: impl fmt::Display) -> Self {
+        self.location = if self.location.is_empty() {
+            location.to_string()
+        } else {
+            format!("{location}.{}", self.location)
+        };
+        self
+    }
+}
+
+impl fmt::Display for PicklsConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.location.is_empty() {
+            write!(f, "invalid {}: {}", self.field, self.message)
+        } else {
+            write!(
+                f,
+                "invalid {}.{}: {}",
+                self.location, self.field, self.message
+            )
+        }
+    }
+}
+
+impl std::error::Error for PicklsConfigError {}
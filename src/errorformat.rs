@@ -0,0 +1,303 @@
+use crate::PicklsConfigError;
+use regex::Regex;
+
+/// The role an errorformat line plays in building a diagnostic. See Vim's `:help errorformat`.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum PicklsErrorFormatKind {
+    /// A single-line diagnostic (no prefix flag).
+    Single,
+    /// The first line of a multi-line diagnostic (`%E`, `%W`, `%I`, `%N` or `%A`). `%E`, `%W`,
+    /// `%I` and `%N` carry an implied type letter.
+    Start(Option<char>),
+    /// A continuation line of a multi-line diagnostic (`%C`).
+    Continuation,
+    /// The final line of a multi-line diagnostic (`%Z`).
+    End,
+    /// A general message line that is not part of a diagnostic (`%G`).
+    General,
+}
+
+/// A compiled errorformat string. Matches are read back through the named groups "file",
+/// "line", "end_line", "col", "end_col", "type", "code", "message" and "pointer".
+#[derive(Clone, Debug)]
+pub struct PicklsErrorFormat {
+    pub kind: PicklsErrorFormatKind,
+    /// `%-` lines contribute nothing to the message, though their other items, (ie: a `%p`
+    /// column), still apply.
+    pub ignore: bool,
+    /// `%+` lines contribute the entire matched line to the message.
+    pub whole_line: bool,
+    pub regex: Regex,
+}
+
+impl PicklsErrorFormat {
+    /// Compile a Vim-style errorformat string, (ie: "%f:%l:%c: %t%*[^:]: %m"). Supports the
+    /// `%f`, `%l`, `%e`, `%c`, `%v`, `%k`, `%t`, `%n`, `%m`, `%r` and `%p` items, `%*` scanf-style
+    /// skips, the `%E`, `%W`, `%I`, `%N`, `%A`, `%C`, `%Z` and `%G` prefixes with their `%-`/`%+`
+    /// modifiers, and the `%.`, `%#`, `%^`, `%$`, `%[`, `%\` and `%%` escapes. All other characters
+    /// match literally.
+    pub fn new(format: &str) -> Result<Self, PicklsConfigError> {
+        let error = |message: String| PicklsConfigError::new("errorformat", message);
+        let mut chars = format.chars().peekable();
+        let mut ignore = false;
+        let mut whole_line = false;
+        let mut kind = PicklsErrorFormatKind::Single;
+
+        // Parse the optional prefix flags, (ie: "%-G" or "%+C").
+        let mut prefix = chars.clone();
+        if prefix.next() == Some('%') {
+            let mut modifier = None;
+            if let Some(&c @ ('-' | '+')) = prefix.peek() {
+                modifier = Some(c);
+                prefix.next();
+            }
+            let flag = match prefix.next() {
+                Some(c @ ('E' | 'W' | 'I' | 'N')) => {
+                    Some(PicklsErrorFormatKind::Start(Some(c.to_ascii_lowercase())))
+                }
+                Some('A') => Some(PicklsErrorFormatKind::Start(None)),
+                Some('C') => Some(PicklsErrorFormatKind::Continuation),
+                Some('Z') => Some(PicklsErrorFormatKind::End),
+                Some('G') => Some(PicklsErrorFormatKind::General),
+                Some(c @ ('O' | 'P' | 'Q' | 'D' | 'X')) => {
+                    return Err(error(format!(
+                        "%{c} (directory and file stack tracking) is not supported in '{format}'"
+                    )));
+                }
+                _ => None,
+            };
+            match (flag, modifier) {
+                (Some(flag), _) => {
+                    kind = flag;
+                    ignore = modifier == Some('-');
+                    whole_line = modifier == Some('+');
+                    chars = prefix;
+                }
+                (None, Some(_)) => {
+                    return Err(error(format!(
+                        "%- and %+ must be followed by a prefix flag in '{format}'"
+                    )));
+                }
+                (None, None) => {}
+            }
+        }
+
+        let mut pattern = String::from("^");
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                pattern.push_str(&regex::escape(&c.to_string()));
+                continue;
+            }
+            match chars.next() {
+                Some('f') => pattern.push_str(r"(?P<file>.+?)"),
+                Some('l') => pattern.push_str(r"(?P<line>\d+)"),
+                Some('e') => pattern.push_str(r"(?P<end_line>\d+)"),
+                Some('c') | Some('v') => pattern.push_str(r"(?P<col>\d+)"),
+                Some('k') => pattern.push_str(r"(?P<end_col>\d+)"),
+                Some('t') => pattern.push_str(r"(?P<type>.)"),
+                Some('n') => pattern.push_str(r"(?P<code>\d+)"),
+                Some('m') => pattern.push_str(r"(?P<message>.*)"),
+                Some('r') => pattern.push_str(r"(?P<message>.*)"),
+                Some('p') => pattern.push_str(r"(?P<pointer>[-\t .]*)"),
+                Some('s') => pattern.push_str(r".+"),
+                Some('*') => match chars.next() {
+                    Some('[') => {
+                        push_class(&mut pattern, &mut chars)
+                            .ok_or_else(|| error(format!("unterminated %*[ in '{format}'")))?;
+                        pattern.push('+');
+                    }
+                    Some('d') => pattern.push_str(r"\d+"),
+                    Some('s') => pattern.push_str(r"\S+"),
+                    Some('c') => pattern.push('.'),
+                    other => {
+                        return Err(error(format!(
+                            "unsupported scanf item %*{} in '{format}'",
+                            other.map(String::from).unwrap_or_default()
+                        )));
+                    }
+                },
+                Some('.') => pattern.push('.'),
+                Some('#') => pattern.push('*'),
+                Some('^') => pattern.push('^'),
+                Some('$') => pattern.push('$'),
+                Some('[') => push_class(&mut pattern, &mut chars)
+                    .ok_or_else(|| error(format!("unterminated %[ in '{format}'")))?,
+                Some('~') => pattern.push('~'),
+                Some('\\') => {
+                    pattern.push('\\');
+                    if let Some(c) = chars.next() {
+                        pattern.push(c);
+                    }
+                }
+                Some('%') => pattern.push('%'),
+                Some(other) => {
+                    return Err(error(format!("unsupported item %{other} in '{format}'")));
+                }
+                None => return Err(error(format!("trailing % in '{format}'"))),
+            }
+        }
+        pattern.push('$');
+        let regex = Regex::new(&pattern).map_err(|e| {
+            error(format!(
+                "could not compile '{format}' [regex={pattern}]: {e}"
+            ))
+        })?;
+        Ok(PicklsErrorFormat {
+            kind,
+            ignore,
+            whole_line,
+            regex,
+        })
+    }
+}
+
+/// Copy a character class through to `pattern` verbatim, up to and including its closing `]`.
+fn push_class(pattern: &mut String, chars: &mut impl Iterator<Item = char>) -> Option<()> {
+    pattern.push('[');
+    for c in chars {
+        pattern.push(c);
+        if c == ']' {
+            return Some(());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(format: &str) -> PicklsErrorFormat {
+        PicklsErrorFormat::new(format).expect("valid errorformat")
+    }
+
+    /// The named groups of `format` that participate in matching `line`.
+    fn groups(format: &str, line: &str) -> Vec<(&'static str, String)> {
+        let format = compile(format);
+        let caps = format.regex.captures(line).expect("line matches");
+        [
+            "file", "line", "end_line", "col", "end_col", "type", "code", "message", "pointer",
+        ]
+        .into_iter()
+        .filter_map(|name| Some((name, caps.name(name)?.as_str().to_string())))
+        .collect()
+    }
+
+    #[test]
+    fn test_gcc_style_format() {
+        assert_eq!(
+            groups(
+                "%f:%l:%c: %t%*[^:]: %m",
+                "src/main.c:12:5: warning: unused variable 'x'"
+            ),
+            [
+                ("file", "src/main.c".to_string()),
+                ("line", "12".to_string()),
+                ("col", "5".to_string()),
+                ("type", "w".to_string()),
+                ("message", "unused variable 'x'".to_string()),
+            ]
+        );
+        let format = compile("%f:%l:%c: %t%*[^:]: %m");
+        assert_eq!(format.kind, PicklsErrorFormatKind::Single);
+        assert!(!format.regex.is_match("src/main.c:12: warning: no column"));
+    }
+
+    #[test]
+    fn test_items() {
+        assert_eq!(
+            groups("%f|%l-%e|%v-%k| E%n %r", "a.py|3-4|2-9| E501 too long"),
+            [
+                ("file", "a.py".to_string()),
+                ("line", "3".to_string()),
+                ("end_line", "4".to_string()),
+                ("col", "2".to_string()),
+                ("end_col", "9".to_string()),
+                ("code", "501".to_string()),
+                ("message", "too long".to_string()),
+            ]
+        );
+        assert_eq!(
+            groups("%p^", "      ^"),
+            [("pointer", "      ".to_string())]
+        );
+        let escapes = compile("%%%.%#%*d%*s%*c[%[0-9]]%\\d%s");
+        assert!(escapes.regex.is_match("%xyz42abc![7]3anything"));
+        assert!(!escapes.regex.is_match("xyz42abc![7]3anything"));
+    }
+
+    #[test]
+    fn test_prefixes_and_modifiers() {
+        let cases = [
+            (
+                "%E%f:%l: %m",
+                PicklsErrorFormatKind::Start(Some('e')),
+                false,
+                false,
+            ),
+            (
+                "%W%m",
+                PicklsErrorFormatKind::Start(Some('w')),
+                false,
+                false,
+            ),
+            (
+                "%I%m",
+                PicklsErrorFormatKind::Start(Some('i')),
+                false,
+                false,
+            ),
+            (
+                "%N%m",
+                PicklsErrorFormatKind::Start(Some('n')),
+                false,
+                false,
+            ),
+            ("%A%m", PicklsErrorFormatKind::Start(None), false, false),
+            ("%C%m", PicklsErrorFormatKind::Continuation, false, false),
+            ("%+C%m", PicklsErrorFormatKind::Continuation, false, true),
+            ("%-Z%p^", PicklsErrorFormatKind::End, true, false),
+            ("%-G%.%#", PicklsErrorFormatKind::General, true, false),
+            ("%f: %m", PicklsErrorFormatKind::Single, false, false),
+        ];
+        for (format, kind, ignore, whole_line) in cases {
+            let compiled = compile(format);
+            assert_eq!(
+                (compiled.kind, compiled.ignore, compiled.whole_line),
+                (kind, ignore, whole_line),
+                "{format}"
+            );
+        }
+        // The prefix flag is not part of the pattern.
+        assert!(compile("%C  %m").regex.is_match("  continued"));
+    }
+
+    #[test]
+    fn test_unsupported_items() {
+        for (format, message) in [
+            (
+                "%D%*\\a%f'",
+                "%D (directory and file stack tracking) is not supported in '%D%*\\a%f''",
+            ),
+            (
+                "%Pfile %f",
+                "%P (directory and file stack tracking) is not supported in '%Pfile %f'",
+            ),
+            (
+                "%-f: %m",
+                "%- and %+ must be followed by a prefix flag in '%-f: %m'",
+            ),
+            ("%f: %q", "unsupported item %q in '%f: %q'"),
+            ("%f: %*x", "unsupported scanf item %*x in '%f: %*x'"),
+            ("%f: %*[^:", "unterminated %*[ in '%f: %*[^:'"),
+            ("%f: %[0-9", "unterminated %[ in '%f: %[0-9'"),
+            ("%f: %m%", "trailing % in '%f: %m%'"),
+        ] {
+            let error = PicklsErrorFormat::new(format).unwrap_err();
+            assert_eq!(error.field, "errorformat");
+            assert_eq!(error.message, message);
+        }
+        let error = PicklsErrorFormat::new("%f: %[z-a]").unwrap_err();
+        assert!(error.message.starts_with("could not compile '%f: %[z-a]'"));
+    }
+}
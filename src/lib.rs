@@ -1,3 +1,38 @@
+mod cache;
+mod capture_group;
+mod column_unit;
+mod command;
+mod compiled;
 mod config;
+mod diagnostic;
+mod diagnostic_tag;
+mod error;
+mod errorformat;
+mod parse;
+mod paths;
+mod presets;
+mod severity;
+mod substitution;
+mod symbol;
+mod symbol_kind;
+mod text_edit;
+mod version;
 
+pub use crate::cache::*;
+pub use crate::capture_group::*;
+pub use crate::column_unit::*;
+pub use crate::command::*;
+pub use crate::compiled::*;
 pub use crate::config::*;
+pub use crate::diagnostic::*;
+pub use crate::diagnostic_tag::*;
+pub use crate::error::*;
+pub use crate::errorformat::*;
+pub use crate::paths::*;
+pub use crate::presets::*;
+pub use crate::severity::*;
+pub use crate::substitution::*;
+pub use crate::symbol::*;
+pub use crate::symbol_kind::*;
+pub use crate::text_edit::*;
+pub use crate::version::*;
@@ -0,0 +1,2448 @@
+use crate::{
+    PicklsCaptureGroup, PicklsColumnUnit, PicklsCompiledLinter, PicklsConfigError,
+    PicklsDescriptionMatch, PicklsDiagnostic, PicklsErrorFormatKind, PicklsLinterConfig,
+    PicklsLinterFormat, PicklsPosition, PicklsRange, PicklsRelatedInformation, PicklsSeverity,
+    PicklsSubstitutions,
+};
+use regex::{Captures, Regex};
+use serde_json::Value;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+/// The pieces of a diagnostic pulled out of linter output, before they are resolved against the
+/// linter's configuration. Lines and columns are zero-based, and `end_col` is exclusive. Columns,
+/// including those of `related`, are in `column_unit` (or the linter's `column_unit` when None).
+#[derive(Default)]
+struct Captured {
+    file: Option<String>,
+    line: Option<u32>,
+    end_line: Option<u32>,
+    col: Option<u32>,
+    end_col: Option<u32>,
+    severity: Option<String>,
+    code: Option<String>,
+    url: Option<String>,
+    message: Option<String>,
+    tags: Option<String>,
+    fix_text: Option<String>,
+    fixable: bool,
+    related: Vec<PicklsRelatedInformation>,
+    column_unit: Option<PicklsColumnUnit>,
+}
+
+impl Captured {
+    /// Fill in whatever `self` is missing from `other`, appending its message on a new line.
+    fn merge(&mut self, other: Captured) {
+        fn fill<T>(field: &mut Option<T>, other: Option<T>) {
+            if field.is_none() {
+                *field = other;
+            }
+        }
+        fill(&mut self.file, other.file);
+        fill(&mut self.line, other.line);
+        fill(&mut self.end_line, other.end_line);
+        fill(&mut self.col, other.col);
+        fill(&mut self.end_col, other.end_col);
+        fill(&mut self.severity, other.severity);
+        fill(&mut self.code, other.code);
+        if let Some(message) = other.message {
+            append_line(&mut self.message, &message);
+        }
+        self.related.extend(other.related);
+    }
+}
+
+/// Where a linter ran, for resolving the paths in its output.
+#[derive(Clone, Copy)]
+struct Context<'a> {
+    filename: &'a Path,
+    working_dir: &'a Path,
+    root_dir: &'a Path,
+}
+
+impl Context<'_> {
+    fn dirname(&self) -> &Path {
+        self.filename.parent().unwrap_or(self.root_dir)
+    }
+}
+
+/// The lines of the files that diagnostics refer to, for converting captured columns into UTF-16
+/// positions. The document being linted is given up front, and other files are read from disk on
+/// first use. Lines are split on "\n" and do not include their line endings.
+struct Documents<'a> {
+    lines: HashMap<PathBuf, Option<Vec<Cow<'a, str>>>>,
+}
+
+impl<'a> Documents<'a> {
+    fn new(filename: &Path, text: &'a str) -> Self {
+        let lines = split_lines(text).map(Cow::Borrowed).collect();
+        Documents {
+            lines: HashMap::from([(filename.to_path_buf(), Some(lines))]),
+        }
+    }
+
+    /// The lines of `file`, or None when it is not the linted document and cannot be read.
+    fn lines(&mut self, file: &Path) -> Option<&[Cow<'a, str>]> {
+        self.lines
+            .entry(file.to_path_buf())
+            .or_insert_with(|| {
+                let text = std::fs::read_to_string(file).ok()?;
+                Some(
+                    split_lines(&text)
+                        .map(|line| line.to_string().into())
+                        .collect(),
+                )
+            })
+            .as_deref()
+    }
+
+    /// Convert the columns of `range` within `file` from `column_unit` into UTF-16. Columns on
+    /// lines that `file` does not have, or of files that cannot be read, are left as they are.
+    fn convert(
+        &mut self,
+        file: &Path,
+        column_unit: PicklsColumnUnit,
+        range: PicklsRange,
+    ) -> PicklsRange {
+        let Some(lines) = self.lines(file) else {
+            return range;
+        };
+        let line = |position: PicklsPosition| lines.get(position.line as usize);
+        let (start, end) = (range.start, range.end);
+        PicklsRange {
+            start: PicklsPosition {
+                character: line(start).map_or(start.character, |line| {
+                    column_unit.to_utf16(line, start.character)
+                }),
+                ..start
+            },
+            end: PicklsPosition {
+                character: line(end).map_or(end.character, |line| {
+                    column_unit.end_to_utf16(line, end.character)
+                }),
+                ..end
+            },
+        }
+    }
+
+    /// The position just past the last character of `file`, or the start of the file when it
+    /// cannot be read.
+    fn end(&mut self, file: &Path) -> PicklsPosition {
+        let Some((last, lines)) = self.lines(file).and_then(|lines| lines.split_last()) else {
+            return PicklsPosition::default();
+        };
+        PicklsPosition {
+            line: lines.len() as u32,
+            character: last.encode_utf16().count() as u32,
+        }
+    }
+}
+
+/// The lines of `text`, split on "\n" and without their line endings. Text that ends in a line
+/// ending has an empty last line.
+fn split_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+}
+
+/// Resolves diagnostics as they are captured, so that capturing can stop as soon as
+/// `max_diagnostics` is exceeded. The most recently captured diagnostic stays pending until the
+/// next one, since the lines that follow it may still add to it.
+struct Collector<'a> {
+    linter: &'a PicklsCompiledLinter,
+    context: Context<'a>,
+    documents: Documents<'a>,
+    pending: Option<Captured>,
+    diagnostics: Vec<PicklsDiagnostic>,
+}
+
+impl Collector<'_> {
+    fn push(&mut self, captured: Captured) {
+        self.flush();
+        self.pending = Some(captured);
+    }
+
+    /// The pending diagnostic, to which following lines may add.
+    fn last_mut(&mut self) -> Option<&mut Captured> {
+        self.pending.as_mut()
+    }
+
+    /// Resolve the pending diagnostic, if any.
+    fn flush(&mut self) {
+        if let Some(captured) = self.pending.take() {
+            let diagnostic = self
+                .linter
+                .finish(captured, self.context, &mut self.documents);
+            self.diagnostics.extend(diagnostic);
+        }
+    }
+
+    /// Whether more than `max_diagnostics` have been resolved, after which the rest of the output
+    /// would only be truncated away.
+    fn is_full(&self) -> bool {
+        let max_diagnostics = self.linter.config.max_diagnostics;
+        max_diagnostics.is_some_and(|max| self.diagnostics.len() > max)
+    }
+}
+
+impl PicklsLinterConfig {
+    /// Parse the output of this linter for `filename` into diagnostics, exactly as pickls does
+    /// after a run. Useful for checking a configuration against captured tool output. `text` is
+    /// the document that was linted, against whose lines captured columns are converted from
+    /// `column_unit` into UTF-16 positions; columns in other files are converted against their
+    /// contents on disk. `working_dir` is the directory the linter ran in and `root_dir` is the
+    /// root directory found via `root_markers`, against which relative filenames in the output are
+    /// resolved per `path_resolution`. Fails if this configuration is invalid, or if `format`
+    /// expects a structured document and `output` is not one.
+    pub fn parse_output(
+        &self,
+        filename: &Path,
+        text: &str,
+        working_dir: &Path,
+        root_dir: &Path,
+        output: &str,
+    ) -> Result<Vec<PicklsDiagnostic>, PicklsConfigError> {
+        self.compile()?
+            .parse_output(filename, text, working_dir, root_dir, output)
+    }
+
+    /// Decode `output` per `output_encoding` and parse it like [`Self::parse_output`].
+    pub fn parse_output_bytes(
+        &self,
+        filename: &Path,
+        text: &str,
+        working_dir: &Path,
+        root_dir: &Path,
+        output: &[u8],
+    ) -> Result<Vec<PicklsDiagnostic>, PicklsConfigError> {
+        self.compile()?
+            .parse_output_bytes(filename, text, working_dir, root_dir, output)
+    }
+}
+
+impl PicklsCompiledLinter {
+    /// Parse the output of this linter for `filename` into diagnostics. See
+    /// [`PicklsLinterConfig::parse_output`].
+    pub fn parse_output(
+        &self,
+        filename: &Path,
+        text: &str,
+        working_dir: &Path,
+        root_dir: &Path,
+        output: &str,
+    ) -> Result<Vec<PicklsDiagnostic>, PicklsConfigError> {
+        let context = Context {
+            filename,
+            working_dir,
+            root_dir,
+        };
+        let mut collector = Collector {
+            linter: self,
+            context,
+            documents: Documents::new(filename, text),
+            pending: None,
+            diagnostics: Vec::new(),
+        };
+        match (&self.config.format, &self.regex) {
+            (PicklsLinterFormat::Regex, Some(regex)) if self.config.multiline => {
+                self.capture_multiline(regex, output, &mut collector);
+                Ok(())
+            }
+            (PicklsLinterFormat::Regex, Some(regex)) => {
+                self.capture_lines(regex, output, &mut collector);
+                Ok(())
+            }
+            (PicklsLinterFormat::Regex, None) => {
+                self.capture_errorformat(output, &mut collector);
+                Ok(())
+            }
+            (PicklsLinterFormat::Json, _) => self.capture_json(output, &mut collector),
+            (PicklsLinterFormat::Sarif, _) => self.capture_sarif(output, &mut collector),
+            (PicklsLinterFormat::Checkstyle, _) => capture_checkstyle(output, &mut collector),
+        }
+        .map_err(|error| error.within(self.config.display_name()))?;
+        collector.flush();
+        let mut diagnostics = collector.diagnostics;
+        if let Some(max_diagnostics) = self.config.max_diagnostics {
+            if diagnostics.len() > max_diagnostics {
+                diagnostics.truncate(max_diagnostics);
+                diagnostics.push(self.notice(filename, self.config.truncation_notice()));
+            }
+        }
+        Ok(diagnostics)
+    }
+
+    /// Decode `output` per `output_encoding` and parse it like [`Self::parse_output`]. Fails,
+    /// naming this linter, when `output_encoding` is "utf-8" and `output` is not valid UTF-8.
+    pub fn parse_output_bytes(
+        &self,
+        filename: &Path,
+        text: &str,
+        working_dir: &Path,
+        root_dir: &Path,
+        output: &[u8],
+    ) -> Result<Vec<PicklsDiagnostic>, PicklsConfigError> {
+        let output = self
+            .config
+            .output_encoding
+            .decode(output)
+            .map_err(|error| {
+                output_error(format!(
+                    "not valid UTF-8 ({error}); consider setting output_encoding"
+                ))
+                .within(self.config.display_name())
+            })?;
+        self.parse_output(filename, text, working_dir, root_dir, &output)
+    }
+
+    /// Resolve a captured diagnostic against the configuration, or None if it is dropped.
+    fn finish(
+        &self,
+        captured: Captured,
+        context: Context,
+        documents: &mut Documents,
+    ) -> Option<PicklsDiagnostic> {
+        let config = &self.config;
+        let filename = context.filename;
+        let captured_file = match (&config.container, captured.file) {
+            (Some(container), Some(file)) => {
+                let substitutions = PicklsSubstitutions::new(filename, context.root_dir);
+                let file = container.unmap_path(Path::new(&file), &substitutions);
+                Some(file.to_string_lossy().into_owned())
+            }
+            (_, file) => file,
+        };
+        let file = config.diagnostic_path(
+            captured_file.as_deref(),
+            filename,
+            context.working_dir,
+            context.root_dir,
+        )?;
+        let code = captured.code.filter(|code| !code.is_empty());
+        let message = captured.message.unwrap_or_else(|| "error".to_string());
+        if code
+            .as_deref()
+            .is_some_and(|code| config.ignores_code(code))
+            || self
+                .ignore_message_regexes
+                .iter()
+                .any(|regex| regex.is_match(&message))
+        {
+            return None;
+        }
+        let column_unit = captured.column_unit.unwrap_or(config.column_unit);
+        let range = match captured.line {
+            Some(line) => {
+                let character = captured.col.unwrap_or(0);
+                let range = PicklsRange {
+                    start: PicklsPosition { line, character },
+                    end: PicklsPosition {
+                        line: captured.end_line.unwrap_or(line).max(line),
+                        character: captured.end_col.unwrap_or(character.saturating_add(1)),
+                    },
+                };
+                documents.convert(&file, column_unit, range)
+            }
+            None if config.whole_file => PicklsRange {
+                start: PicklsPosition::default(),
+                end: documents.end(&file),
+            },
+            None => PicklsRange::default(),
+        };
+        let related = captured
+            .related
+            .into_iter()
+            .map(|related| PicklsRelatedInformation {
+                range: documents.convert(&related.file, column_unit, related.range),
+                ..related
+            })
+            .collect();
+        Some(PicklsDiagnostic {
+            file,
+            range,
+            severity: config.resolve_severity(captured.severity.as_deref(), code.as_deref()),
+            code_url: config.code_url(captured.url.as_deref(), code.as_deref()),
+            tags: config.resolve_tags(captured.tags.as_deref(), code.as_deref()),
+            source: config.display_name().to_string(),
+            message: self.rewrite_message(&message),
+            related,
+            fix_text: captured.fix_text,
+            fixable: captured.fixable,
+            code,
+        })
+    }
+
+    /// An informational diagnostic at the start of `filename`.
+    fn notice(&self, filename: &Path, message: Option<String>) -> PicklsDiagnostic {
+        PicklsDiagnostic {
+            file: filename.to_path_buf(),
+            range: PicklsRange::default(),
+            severity: PicklsSeverity::Information,
+            code: None,
+            code_url: None,
+            source: self.config.display_name().to_string(),
+            message: message.unwrap_or_default(),
+            tags: Vec::new(),
+            related: Vec::new(),
+            fix_text: None,
+            fixable: false,
+        }
+    }
+
+    /// Pull the `*_match` groups out of a match of `pattern`.
+    fn capture_groups(&self, caps: &Captures) -> Captured {
+        let config = &self.config;
+        let text = |group: &Option<PicklsCaptureGroup>| {
+            group
+                .as_ref()
+                .and_then(|group| group.get(caps))
+                .map(|m| m.as_str().to_string())
+        };
+        let number = |group: &Option<PicklsCaptureGroup>| {
+            text(group).and_then(|text| text.trim().parse::<u32>().ok())
+        };
+        Captured {
+            file: text(&config.filename_match),
+            line: number(&config.line_match).map(|line| config.zero_based_line(line)),
+            end_line: number(&config.end_line_match).map(|line| config.zero_based_line(line)),
+            col: number(&config.start_col_match).map(|col| config.zero_based_column(col)),
+            end_col: number(&config.end_col_match)
+                .map(|col| config.zero_based_column(col).saturating_add(1)),
+            severity: text(&config.severity_match),
+            code: text(&config.code_match),
+            url: text(&config.url_match),
+            tags: text(&config.tags_match),
+            fix_text: text(&config.fix_text_match),
+            fixable: text(&config.fixable_match).is_some(),
+            ..Captured::default()
+        }
+    }
+
+    /// Match `pattern` against each line of `output`, handling the line-oriented options.
+    fn capture_lines(&self, regex: &Regex, output: &str, collector: &mut Collector) {
+        let config = &self.config;
+        let context = collector.context;
+        let lines: Vec<&str> = output.lines().collect();
+        let mut consumed = vec![false; lines.len()];
+        let mut continuing = false;
+        let mut continuation_lines = 0;
+        for (index, line) in lines.iter().enumerate() {
+            if collector.is_full() {
+                break;
+            }
+            if consumed[index] || is_match(&self.ignore_regex, line) {
+                continue;
+            }
+            if continuing && is_match(&self.continuation_regex, line) {
+                consumed[index] = true;
+                if config
+                    .max_continuation_lines
+                    .is_none_or(|max| continuation_lines < max)
+                {
+                    if let Some(last) = collector.last_mut() {
+                        append_line(&mut last.message, line);
+                    }
+                    continuation_lines += 1;
+                }
+                continue;
+            }
+            continuing = false;
+            if let Some(caps) = self
+                .note_regex
+                .as_ref()
+                .and_then(|note| note.captures(line))
+            {
+                if let Some(last) = collector.last_mut() {
+                    self.attach_note(last, &caps, line, context);
+                }
+                continue;
+            }
+            if let Some(caps) = self
+                .related_regex
+                .as_ref()
+                .and_then(|related| related.captures(line))
+            {
+                if let Some(last) = collector.last_mut() {
+                    self.attach_related(last, &caps, line, context);
+                }
+                continue;
+            }
+            let Some(caps) = regex.captures(line) else {
+                continue;
+            };
+            let mut diagnostic = self.capture_groups(&caps);
+            diagnostic.message = self.description(&caps, &lines, &mut consumed, index);
+            collector.push(diagnostic);
+            continuing = true;
+            continuation_lines = 0;
+        }
+    }
+
+    /// Match `pattern` against the entire `output`. Matches that start on a line matching
+    /// `ignore_pattern` are dropped.
+    fn capture_multiline(&self, regex: &Regex, output: &str, collector: &mut Collector) {
+        let lines: Vec<&str> = output.lines().collect();
+        let mut consumed = vec![false; lines.len()];
+        // Matches are found in order, so the line of each is counted on from the previous one.
+        let (mut index, mut counted) = (0, 0);
+        for caps in regex.captures_iter(output) {
+            if collector.is_full() {
+                break;
+            }
+            let start = caps.get(0).map_or(0, |m| m.start());
+            index += output[counted..start].matches('\n').count();
+            counted = start;
+            if lines
+                .get(index)
+                .is_some_and(|line| is_match(&self.ignore_regex, line))
+            {
+                continue;
+            }
+            let mut diagnostic = self.capture_groups(&caps);
+            diagnostic.message = self.description(&caps, &lines, &mut consumed, index);
+            collector.push(diagnostic);
+        }
+    }
+
+    /// The description of a diagnostic matched on line `index` of `lines`, per
+    /// `description_match` or `description_line_offset`. Lines after the match that are used as
+    /// descriptions are marked as consumed.
+    fn description(
+        &self,
+        caps: &Captures,
+        lines: &[&str],
+        consumed: &mut [bool],
+        index: usize,
+    ) -> Option<String> {
+        let mut offset_line = |offset: isize| {
+            let target = index.checked_add_signed(offset)?;
+            if *consumed.get(target)? {
+                return None;
+            }
+            if offset > 0 {
+                consumed[target] = true;
+            }
+            Some(lines[target].trim().to_string())
+        };
+        match (
+            &self.config.description_match,
+            self.config.description_line_offset,
+        ) {
+            (Some(PicklsDescriptionMatch::Group(group)), _) => {
+                group.get(caps).map(|m| m.as_str().to_string())
+            }
+            (Some(PicklsDescriptionMatch::LineOffset(offset)), _) => offset_line(*offset),
+            (None, Some(offset)) => offset_line(offset),
+            (None, None) => None,
+        }
+    }
+
+    /// Attach a `note_pattern` match to the diagnostic it follows.
+    fn attach_note(
+        &self,
+        diagnostic: &mut Captured,
+        caps: &Captures,
+        line: &str,
+        context: Context,
+    ) {
+        let config = &self.config;
+        let message = match &config.description_match {
+            Some(PicklsDescriptionMatch::Group(group)) => group.get(caps).map(|m| m.as_str()),
+            _ => None,
+        }
+        .unwrap_or(line.trim())
+        .to_string();
+        let get =
+            |group: &Option<PicklsCaptureGroup>| group.as_ref().and_then(|group| group.get(caps));
+        let Some(note_line) = get(&config.line_match).and_then(|m| m.as_str().parse().ok()) else {
+            append_line(&mut diagnostic.message, &message);
+            return;
+        };
+        let file = get(&config.filename_match).map(|m| m.as_str());
+        let col = get(&config.start_col_match).and_then(|m| m.as_str().parse().ok());
+        let related = self.related_location(file, diagnostic, note_line, col, message, context);
+        diagnostic.related.push(related);
+    }
+
+    /// Attach a `related.pattern` match to the diagnostic it follows.
+    fn attach_related(
+        &self,
+        diagnostic: &mut Captured,
+        caps: &Captures,
+        line: &str,
+        context: Context,
+    ) {
+        let Some(related) = &self.config.related else {
+            return;
+        };
+        let get = |group: &PicklsCaptureGroup| group.get(caps).map(|m| m.as_str());
+        let Some(related_line) = get(&related.line_match).and_then(|line| line.parse().ok()) else {
+            return;
+        };
+        let file = related.filename_match.as_ref().and_then(get);
+        let col = related
+            .start_col_match
+            .as_ref()
+            .and_then(get)
+            .and_then(|col| col.parse().ok());
+        let message = related
+            .description_match
+            .as_ref()
+            .and_then(get)
+            .unwrap_or(line.trim())
+            .to_string();
+        let related = self.related_location(file, diagnostic, related_line, col, message, context);
+        diagnostic.related.push(related);
+    }
+
+    /// A secondary location of `diagnostic` from captured (one-based by default) numbers.
+    /// Locations without a filename belong to the diagnostic's file.
+    fn related_location(
+        &self,
+        file: Option<&str>,
+        diagnostic: &Captured,
+        line: u32,
+        col: Option<u32>,
+        message: String,
+        context: Context,
+    ) -> PicklsRelatedInformation {
+        let config = &self.config;
+        let file = file
+            .or(diagnostic.file.as_deref())
+            .map(|file| self.resolve_path(file, context))
+            .unwrap_or_else(|| context.filename.to_path_buf());
+        let line = config.zero_based_line(line);
+        let character = col.map_or(0, |col| config.zero_based_column(col));
+        PicklsRelatedInformation {
+            file,
+            range: PicklsRange {
+                start: PicklsPosition { line, character },
+                end: PicklsPosition {
+                    line,
+                    character: character.saturating_add(1),
+                },
+            },
+            message,
+        }
+    }
+
+    /// Resolve a `file` captured from the output against the directories the linter ran in, per
+    /// `path_resolution`.
+    fn resolve_path(&self, file: &str, context: Context) -> PathBuf {
+        self.config.path_resolution.resolve(
+            file,
+            context.working_dir,
+            context.root_dir,
+            context.dirname(),
+        )
+    }
+
+    /// Match `errorformat` against each line of `output`, assembling multi-line diagnostics.
+    fn capture_errorformat(&self, output: &str, collector: &mut Collector) {
+        let config = &self.config;
+        let mut pending: Option<Captured> = None;
+        for line in output.lines() {
+            if collector.is_full() {
+                break;
+            }
+            if is_match(&self.ignore_regex, line) {
+                continue;
+            }
+            let Some((format, caps)) = self
+                .errorformats
+                .iter()
+                .find_map(|format| Some((format, format.regex.captures(line)?)))
+            else {
+                continue;
+            };
+            if format.kind == PicklsErrorFormatKind::General {
+                continue;
+            }
+            let text = |name: &str| caps.name(name).map(|m| m.as_str().to_string());
+            let number = |name: &str| text(name).and_then(|text| text.parse::<u32>().ok());
+            let col = number("col")
+                .map(|col| config.zero_based_column(col))
+                .or_else(|| caps.name("pointer").map(|pointer| pointer.len() as u32));
+            let mut diagnostic = Captured {
+                file: text("file"),
+                line: number("line").map(|line| config.zero_based_line(line)),
+                end_line: number("end_line").map(|line| config.zero_based_line(line)),
+                col,
+                end_col: number("end_col")
+                    .map(|col| config.zero_based_column(col).saturating_add(1)),
+                severity: text("type"),
+                code: text("code"),
+                // An ignored line still ends or continues its diagnostic, and may still carry
+                // its column, (ie: javac's "%-Z%p^").
+                message: match (format.ignore, format.whole_line) {
+                    (true, _) => None,
+                    (false, true) => Some(line.to_string()),
+                    (false, false) => text("message"),
+                },
+                ..Captured::default()
+            };
+            match format.kind {
+                PicklsErrorFormatKind::Single => {
+                    if let Some(pending) = pending.take() {
+                        collector.push(pending);
+                    }
+                    collector.push(diagnostic);
+                }
+                PicklsErrorFormatKind::Start(implied_type) => {
+                    if let Some(pending) = pending.take() {
+                        collector.push(pending);
+                    }
+                    if diagnostic.severity.is_none() {
+                        diagnostic.severity = implied_type.map(String::from);
+                    }
+                    pending = Some(diagnostic);
+                }
+                PicklsErrorFormatKind::Continuation => {
+                    if let Some(pending) = &mut pending {
+                        pending.merge(diagnostic);
+                    }
+                }
+                PicklsErrorFormatKind::End => {
+                    if let Some(mut pending) = pending.take() {
+                        pending.merge(diagnostic);
+                        collector.push(pending);
+                    }
+                }
+                PicklsErrorFormatKind::General => {}
+            }
+        }
+        if let Some(pending) = pending {
+            collector.push(pending);
+        }
+    }
+
+    /// Pull diagnostics out of a JSON document via the `*_path` fields.
+    fn capture_json(
+        &self,
+        output: &str,
+        collector: &mut Collector,
+    ) -> Result<(), PicklsConfigError> {
+        let config = &self.config;
+        let document: Value = serde_json::from_str(output)
+            .map_err(|error| output_error(format!("not valid JSON: {error}")))?;
+        // Each item is paired with the chain of values enclosing it, outermost first.
+        let mut items: Vec<Vec<&Value>> = vec![vec![&document]];
+        for segment in config.items_path.iter().flat_map(|path| path.split('.')) {
+            items = items
+                .into_iter()
+                .flat_map(|chain| {
+                    let current = chain[chain.len() - 1];
+                    let children: Vec<&Value> = match segment {
+                        "[]" => current.as_array().into_iter().flatten().collect(),
+                        key => json_child(current, key).into_iter().collect(),
+                    };
+                    children.into_iter().map(move |child| {
+                        let mut chain = chain.clone();
+                        chain.push(child);
+                        chain
+                    })
+                })
+                .collect();
+        }
+        let items = items.into_iter().flat_map(|chain| {
+            let current = chain[chain.len() - 1];
+            let elements: Vec<&Value> = match current {
+                Value::Array(elements) => elements.iter().collect(),
+                _ => vec![current],
+            };
+            elements.into_iter().map(move |element| {
+                let mut chain = chain.clone();
+                if !std::ptr::eq(element, current) {
+                    chain.push(element);
+                }
+                chain
+            })
+        });
+        for chain in items {
+            if collector.is_full() {
+                break;
+            }
+            let text = |path: &Option<String>| {
+                let path = path.as_deref()?;
+                chain
+                    .iter()
+                    .rev()
+                    .find_map(|value| json_lookup(value, path))
+                    .and_then(json_scalar)
+            };
+            let number =
+                |path: &Option<String>| text(path).and_then(|text| text.trim().parse::<u32>().ok());
+            collector.push(Captured {
+                file: text(&config.file_path),
+                line: number(&config.line_path).map(|line| config.zero_based_line(line)),
+                end_line: number(&config.end_line_path).map(|line| config.zero_based_line(line)),
+                col: number(&config.start_col_path).map(|col| config.zero_based_column(col)),
+                end_col: number(&config.end_col_path)
+                    .map(|col| config.zero_based_column(col).saturating_add(1)),
+                severity: text(&config.severity_path),
+                code: text(&config.code_path),
+                message: text(&config.message_path),
+                ..Captured::default()
+            });
+        }
+        Ok(())
+    }
+
+    /// Pull diagnostics out of a SARIF 2.1.0 document.
+    fn capture_sarif(
+        &self,
+        output: &str,
+        collector: &mut Collector,
+    ) -> Result<(), PicklsConfigError> {
+        let context = collector.context;
+        let invalid =
+            |message: &str| output_error(format!("not a valid SARIF document: {message}"));
+        let document: Value =
+            serde_json::from_str(output).map_err(|error| invalid(&error.to_string()))?;
+        let runs = document
+            .get("runs")
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid("missing runs"))?;
+        let results = runs.iter().flat_map(|run| {
+            let column_unit = match run.get("columnKind").and_then(Value::as_str) {
+                Some("unicodeCodePoints") => Some(PicklsColumnUnit::Chars),
+                Some("utf16CodeUnits") => Some(PicklsColumnUnit::Utf16),
+                _ => None,
+            };
+            let results = run.get("results").and_then(Value::as_array);
+            results
+                .into_iter()
+                .flatten()
+                .map(move |result| (result, run, column_unit))
+        });
+        for (result, run, column_unit) in results {
+            if collector.is_full() {
+                break;
+            }
+            let text = |value: Option<&Value>| value.and_then(Value::as_str).map(str::to_string);
+            let message = text(result.pointer("/message/text"));
+            let location = result.pointer("/locations/0/physicalLocation");
+            let (file, range) = location.map(sarif_location).unwrap_or_default();
+            let related = result
+                .get("relatedLocations")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|related| {
+                    let (file, range) = sarif_location(related.get("physicalLocation")?);
+                    Some(PicklsRelatedInformation {
+                        file: self.resolve_path(&file?, context),
+                        range: range?,
+                        message: text(related.pointer("/message/text")).unwrap_or_default(),
+                    })
+                })
+                .collect();
+            collector.push(Captured {
+                file,
+                line: range.map(|range| range.start.line),
+                end_line: range.map(|range| range.end.line),
+                col: range.map(|range| range.start.character),
+                end_col: range.map(|range| range.end.character),
+                severity: Some(
+                    text(
+                        result
+                            .get("level")
+                            .or_else(|| sarif_rule_level(run, result)),
+                    )
+                    .unwrap_or_else(|| "warning".to_string()),
+                ),
+                code: text(result.get("ruleId")),
+                message,
+                related,
+                column_unit,
+                ..Captured::default()
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The file and range of a SARIF `physicalLocation`. SARIF lines and columns are one-based, and
+/// `endColumn` is exclusive. Artifact URIs are percent-decoded, with any "file://" scheme removed.
+fn sarif_location(location: &Value) -> (Option<String>, Option<PicklsRange>) {
+    let file = location
+        .pointer("/artifactLocation/uri")
+        .and_then(Value::as_str)
+        .map(|uri| percent_decode(uri.strip_prefix("file://").unwrap_or(uri)));
+    let number = |name: &str| {
+        location
+            .pointer(&format!("/region/{name}"))
+            .and_then(Value::as_u64)
+            .map(|number| u32::try_from(number).unwrap_or(u32::MAX).saturating_sub(1))
+    };
+    let range = number("startLine").map(|line| {
+        let character = number("startColumn").unwrap_or(0);
+        PicklsRange {
+            start: PicklsPosition { line, character },
+            end: PicklsPosition {
+                line: number("endLine").unwrap_or(line),
+                character: number("endColumn").unwrap_or(character.saturating_add(1)),
+            },
+        }
+    });
+    (file, range)
+}
+
+/// The `defaultConfiguration.level` of the rule that `result` reports, found by its `ruleIndex`
+/// or else its `ruleId`, for results without a `level` of their own.
+fn sarif_rule_level<'a>(run: &'a Value, result: &Value) -> Option<&'a Value> {
+    let rules = run.pointer("/tool/driver/rules")?.as_array()?;
+    let rule = match result.get("ruleIndex").and_then(Value::as_u64) {
+        Some(index) => rules.get(usize::try_from(index).ok()?)?,
+        None => {
+            let id = result.get("ruleId")?;
+            rules.iter().find(|rule| rule.get("id") == Some(id))?
+        }
+    };
+    rule.pointer("/defaultConfiguration/level")
+}
+
+/// Decode the "%XX" escapes of a URI, (ie: "my%20file.py"). Malformed escapes are kept as they
+/// are, and decoded bytes that are not valid UTF-8 are replaced.
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| uri.get(index + 1..index + 3))
+            .flatten()
+            .filter(|hex| hex.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The `<file>` and `<error>` tags of a checkstyle document, with their attributes. Quoted
+/// attribute values may contain ">", (ie: phpcs's "Expected 1 space after '=>'").
+static CHECKSTYLE_TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"<(/?)(file|error)\b((?:[^>"']|"[^"]*"|'[^']*')*?)/?>"#).expect("valid regex")
+});
+
+/// A single attribute of a checkstyle tag, with its value in either kind of quotes.
+static CHECKSTYLE_ATTRIBUTE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"([\w:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).expect("valid regex")
+});
+
+/// An XML entity or character reference.
+static XML_ENTITY_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"&(#x[0-9a-fA-F]+|#[0-9]+|lt|gt|amp|quot|apos);").expect("valid regex")
+});
+
+/// Pull diagnostics out of a checkstyle XML document.
+fn capture_checkstyle(output: &str, collector: &mut Collector) -> Result<(), PicklsConfigError> {
+    if !output.contains("<checkstyle") {
+        return Err(output_error("not a checkstyle document"));
+    }
+    let mut file = None;
+    for tag in CHECKSTYLE_TAG_REGEX.captures_iter(output) {
+        if collector.is_full() {
+            break;
+        }
+        let attribute = |name: &str| {
+            CHECKSTYLE_ATTRIBUTE_REGEX
+                .captures_iter(&tag[3])
+                .find(|attribute| &attribute[1] == name)
+                .and_then(|attribute| attribute.get(2).or(attribute.get(3)))
+                .map(|value| xml_unescape(value.as_str()))
+        };
+        match (&tag[1], &tag[2]) {
+            ("/", "file") => file = None,
+            ("", "file") => file = attribute("name"),
+            ("", "error") => {
+                let number =
+                    |name: &str| attribute(name).and_then(|value| value.parse::<u32>().ok());
+                collector.push(Captured {
+                    file: file.clone(),
+                    line: number("line").map(|line| line.saturating_sub(1)),
+                    col: number("column").map(|col| col.saturating_sub(1)),
+                    severity: attribute("severity"),
+                    code: attribute("source"),
+                    message: attribute("message"),
+                    ..Captured::default()
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn xml_unescape(text: &str) -> String {
+    XML_ENTITY_REGEX
+        .replace_all(text, |caps: &Captures| {
+            let entity = &caps[1];
+            let decoded = match entity {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => match entity.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => entity[1..].parse().ok(),
+                }
+                .and_then(char::from_u32),
+            };
+            decoded.map_or_else(|| caps[0].to_string(), String::from)
+        })
+        .into_owned()
+}
+
+/// The child of `value` named by a single path segment: an object key or an array index.
+fn json_child<'a>(value: &'a Value, segment: &str) -> Option<&'a Value> {
+    match value {
+        Value::Object(fields) => fields.get(segment),
+        Value::Array(elements) => elements.get(segment.parse::<usize>().ok()?),
+        _ => None,
+    }
+}
+
+/// Look up a dotted `path` within `value`, (ie: "location.row").
+fn json_lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(value, |value, segment| json_child(value, segment))
+}
+
+fn json_scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    }
+}
+
+fn is_match(regex: &Option<Regex>, line: &str) -> bool {
+    regex.as_ref().is_some_and(|regex| regex.is_match(line))
+}
+
+fn append_line(message: &mut Option<String>, line: &str) {
+    match message {
+        Some(message) => {
+            message.push('\n');
+            message.push_str(line);
+        }
+        None => *message = Some(line.to_string()),
+    }
+}
+
+fn output_error(message: impl Into<String>) -> PicklsConfigError {
+    PicklsConfigError::new("output", message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PicklsOutputEncoding;
+    use serde_json::json;
+
+    fn linter(config: Value) -> PicklsLinterConfig {
+        serde_json::from_value(config).expect("valid linter config")
+    }
+
+    /// An ASCII document with room for every position in the fixtures, against which captured
+    /// columns convert to UTF-16 unchanged.
+    fn ascii_text() -> String {
+        format!("{}\n", " ".repeat(200)).repeat(2000)
+    }
+
+    /// Parse `output` for `/repo/<file>`, as though the linter ran in `/repo`.
+    fn parse_for(linter: &PicklsLinterConfig, file: &str, output: &str) -> Vec<PicklsDiagnostic> {
+        let root_dir = Path::new("/repo");
+        linter
+            .parse_output(
+                &root_dir.join(file),
+                &ascii_text(),
+                root_dir,
+                root_dir,
+                output,
+            )
+            .expect("output parses")
+    }
+
+    fn parse(linter: &PicklsLinterConfig, output: &str) -> Vec<PicklsDiagnostic> {
+        parse_for(linter, "src/main.rs", output)
+    }
+
+    fn position(diagnostic: &PicklsDiagnostic) -> (u32, u32) {
+        (
+            diagnostic.range.start.line,
+            diagnostic.range.start.character,
+        )
+    }
+
+    /// `cargo check` output for a crate with an unused variable and a type mismatch, with a
+    /// second file that has its own error.
+    const CARGO_CHECK_OUTPUT: &str = r#"    Checking demo v0.1.0 (/repo)
+warning: unused variable: `x`
+ --> src/main.rs:2:9
+  |
+2 |     let x = 5;
+  |         ^ help: if this is intentional, prefix it with an underscore: `_x`
+  |
+  = note: `#[warn(unused_variables)]` on by default
+
+error[E0308]: mismatched types
+ --> src/main.rs:3:18
+  |
+3 |     let y: u32 = "a";
+  |            ---   ^^^ expected `u32`, found `&str`
+  |            |
+  |            expected due to this
+
+error[E0425]: cannot find value `z` in this scope
+ --> src/lib.rs:1:13
+  |
+1 | pub fn f() { z }
+  |              ^ not found in this scope
+
+Some errors have detailed explanations: E0308, E0425.
+For more information about an error, try `rustc --explain E0308`.
+warning: `demo` (bin "demo") generated 1 warning
+error: could not compile `demo` (bin "demo") due to 2 previous errors; 1 warning emitted
+"#;
+
+    #[test]
+    fn test_multiline_cargo_check() {
+        let config = linter(json!({
+            "program": "cargo",
+            "multiline": true,
+            "pattern": r"^(?P<severity>warning|error)(?:\[(?P<code>E\d+)\])?: (?P<message>[^\n]+)\n\s*--> (?P<file>[^:\n]+):(?P<line>\d+):(?P<col>\d+)",
+            "filename_match": "file",
+            "line_match": "line",
+            "start_col_match": "col",
+            "severity_match": "severity",
+            "code_match": "code",
+            "description_match": "message",
+        }));
+        let diagnostics = parse(&config, CARGO_CHECK_OUTPUT);
+        assert_eq!(
+            diagnostics.len(),
+            2,
+            "src/lib.rs is dropped as a foreign file"
+        );
+        assert_eq!(position(&diagnostics[0]), (1, 8));
+        assert_eq!(diagnostics[0].severity, PicklsSeverity::Warning);
+        assert_eq!(diagnostics[0].code, None);
+        assert_eq!(diagnostics[0].message, "unused variable: `x`");
+        assert_eq!(position(&diagnostics[1]), (2, 17));
+        assert_eq!(diagnostics[1].severity, PicklsSeverity::Error);
+        assert_eq!(diagnostics[1].code.as_deref(), Some("E0308"));
+        assert_eq!(diagnostics[1].message, "mismatched types");
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.file == Path::new("/repo/src/main.rs")));
+    }
+
+    #[test]
+    fn test_multiline_matches_do_not_overlap() {
+        let config = linter(json!({
+            "program": "cargo",
+            "multiline": true,
+            "pattern": r"^(?P<severity>warning|error).*?--> [^:\n]+:(?P<line>\d+)",
+            "line_match": "line",
+            "severity_match": "severity",
+        }));
+        let lines: Vec<u32> = parse(&config, CARGO_CHECK_OUTPUT)
+            .iter()
+            .map(|diagnostic| diagnostic.range.start.line)
+            .collect();
+        assert_eq!(lines, [1, 2, 0]);
+
+        let config = PicklsLinterConfig {
+            multiline: false,
+            ..config
+        };
+        assert!(parse(&config, CARGO_CHECK_OUTPUT).is_empty());
+    }
+
+    #[test]
+    fn test_max_diagnostics() {
+        // Diagnostics dropped as foreign files do not count towards the limit.
+        let config = linter(json!({
+            "name": "lint",
+            "program": "lint",
+            "pattern": r"^(.+):(\d+): (.*)$",
+            "filename_match": 1,
+            "line_match": 2,
+            "description_match": 3,
+            "max_diagnostics": 2,
+        }));
+        let output = "src/lib.rs:1: a\nsrc/main.rs:2: b\nsrc/lib.rs:3: c\nsrc/main.rs:4: d\n";
+        let messages = |output: &str| -> Vec<String> {
+            parse(&config, output)
+                .into_iter()
+                .map(|diagnostic| diagnostic.message)
+                .collect()
+        };
+        assert_eq!(messages(output), ["b", "d"]);
+        let output = format!("{output}src/main.rs:5: e\nsrc/main.rs:6: f\n");
+        assert_eq!(
+            messages(&output),
+            ["b", "d", "lint: output truncated at 2 diagnostics"]
+        );
+
+        let config = linter(json!({
+            "program": "lint",
+            "errorformat": ["%f:%l: %m"],
+            "max_diagnostics": 1,
+        }));
+        let diagnostics = parse(&config, &output);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[1].severity, PicklsSeverity::Information);
+    }
+
+    /// `javac` output, with a caret under the column of each diagnostic.
+    const JAVAC_OUTPUT: &str = "src/Main.java:5: error: ';' expected
+        int x = 1
+                 ^
+src/Main.java:9: warning: [deprecation] stop() in Thread has been deprecated
+        t.stop();
+         ^
+src/Other.java:2: error: class, interface, or enum expected
+2 problems
+";
+
+    #[test]
+    fn test_errorformat_multiline_with_pointer() {
+        let config = linter(json!({
+            "program": "javac",
+            "errorformat": [
+                "%E%f:%l: error: %m",
+                "%W%f:%l: warning: %m",
+                "%-Z%p^",
+                "%-C%.%#",
+                "%-G%.%#",
+            ],
+        }));
+        let diagnostics = parse_for(&config, "src/Main.java", JAVAC_OUTPUT);
+        assert_eq!(
+            described(&diagnostics),
+            [
+                ((4, 17), "';' expected"),
+                ((8, 9), "[deprecation] stop() in Thread has been deprecated"),
+            ]
+        );
+        assert_eq!(
+            severities(&diagnostics),
+            [PicklsSeverity::Error, PicklsSeverity::Warning]
+        );
+    }
+
+    #[test]
+    fn test_errorformat_single_line() {
+        let config = linter(json!({
+            "program": "gcc",
+            "errorformat": ["%f:%l:%c: %t%*[^:]: %m", "%-G%.%#"],
+        }));
+        let output = "src/main.rs:3:5: warning: unused\nIn file included from x.h\nsrc/main.rs:7:1: error: bad\n";
+        let diagnostics = parse(&config, output);
+        assert_eq!(
+            described(&diagnostics),
+            [((2, 4), "unused"), ((6, 0), "bad")]
+        );
+        assert_eq!(
+            severities(&diagnostics),
+            [PicklsSeverity::Warning, PicklsSeverity::Error]
+        );
+    }
+
+    #[test]
+    fn test_errorformat_continuation_and_whole_lines() {
+        // %C lines are merged into the diagnostic until %Z ends it; %+ keeps whole lines.
+        let config = linter(json!({
+            "program": "lint",
+            "errorformat": ["%+E%f:%l: error: %m", "%+C  %.%#", "%C%*[ ]col %c", "%Z"],
+        }));
+        let output = "src/main.rs:3: error: bad\n  detail one\n col 4\n  detail two\n\n  stray\n";
+        let diagnostics = parse(&config, output);
+        assert_eq!(
+            described(&diagnostics),
+            [(
+                (2, 3),
+                "src/main.rs:3: error: bad\n  detail one\n  detail two"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_errorformat_and_pattern_are_exclusive() {
+        let error = linter(json!({
+            "program": "lint",
+            "pattern": "^(\\d+)$",
+            "line_match": 1,
+            "errorformat": ["%l"],
+        }))
+        .validate()
+        .unwrap_err();
+        assert_eq!(error.field, "errorformat");
+        assert_eq!(
+            error.message,
+            "pattern and errorformat are mutually exclusive"
+        );
+        let error = linter(json!({"program": "lint", "errorformat": ["%l: %q"]}))
+            .validate()
+            .unwrap_err();
+        assert_eq!(error.message, "unsupported item %q in '%l: %q'");
+    }
+
+    #[test]
+    fn test_multiline_description_line_offset() {
+        // Each match's line is counted on from the previous match.
+        let config = linter(json!({
+            "program": "lint",
+            "multiline": true,
+            "pattern": r"^E (\d+)$",
+            "line_match": 1,
+            "description_line_offset": 1,
+        }));
+        let output = "header\nE 1\nfirst\n\nE 4\nsecond\nE 6\nthird\n";
+        let messages: Vec<String> = parse(&config, output)
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+        assert_eq!(messages, ["first", "second", "third"]);
+    }
+
+    #[test]
+    fn test_multiline_ignore_pattern() {
+        // Matches that start on an ignored line are dropped, even though they span other lines.
+        let config = linter(json!({
+            "program": "cargo",
+            "multiline": true,
+            "pattern": r"^(?P<severity>warning|error).*?--> [^:\n]+:(?P<line>\d+)",
+            "line_match": "line",
+            "severity_match": "severity",
+            "ignore_pattern": r"^warning: unused",
+        }));
+        let lines: Vec<u32> = parse(&config, CARGO_CHECK_OUTPUT)
+            .iter()
+            .map(|diagnostic| diagnostic.range.start.line)
+            .collect();
+        assert_eq!(lines, [2, 0]);
+    }
+
+    #[test]
+    fn test_multiline_rejects_line_by_line_fields() {
+        for (field, value) in [
+            ("continuation_pattern", json!(r"^\s+")),
+            ("note_pattern", json!("note: (.*)")),
+            (
+                "related",
+                json!({"pattern": r"^  (\d+): defined here$", "line_match": 1}),
+            ),
+        ] {
+            let mut config = json!({
+                "program": "lint",
+                "multiline": true,
+                "pattern": r"^(\d+): (.*)$",
+                "line_match": 1,
+            });
+            config[field] = value;
+            let error = linter(config).validate().unwrap_err();
+            assert_eq!(error.field, field);
+        }
+    }
+
+    fn severities(diagnostics: &[PicklsDiagnostic]) -> Vec<PicklsSeverity> {
+        diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.severity)
+            .collect()
+    }
+
+    #[test]
+    fn test_default_severity_without_severity_match() {
+        let mut config = linter(json!({
+            "program": "codespell",
+            "pattern": r"^[^:]+:(\d+): (.*)$",
+            "line_match": 1,
+            "description_match": 2,
+        }));
+        let output = "src/main.rs:3: teh ==> the\nsrc/main.rs:9: recieve ==> receive\n";
+        assert_eq!(
+            severities(&parse(&config, output)),
+            [PicklsSeverity::Warning, PicklsSeverity::Warning]
+        );
+        config.default_severity = Some(PicklsSeverity::Error);
+        assert_eq!(
+            severities(&parse(&config, output)),
+            [PicklsSeverity::Error, PicklsSeverity::Error]
+        );
+    }
+
+    #[test]
+    fn test_default_severity_when_group_does_not_participate() {
+        let config = linter(json!({
+            "program": "golangci-lint",
+            "pattern": r"^[^:]+:(\d+):(?: (warning|info|bogus):)? (.*)$",
+            "line_match": 1,
+            "severity_match": 2,
+            "description_match": 3,
+            "severity_map": {"info": "information"},
+            "default_severity": "error",
+        }));
+        let output = "\
+main.go:1: warning: shadowed variable
+main.go:2: unchecked error
+main.go:3: info: consider a constant
+main.go:4: bogus: unknown severity
+";
+        assert_eq!(
+            severities(&parse(&config, output)),
+            [
+                PicklsSeverity::Warning,
+                PicklsSeverity::Error,
+                PicklsSeverity::Information,
+                PicklsSeverity::Error,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_base() {
+        let config = |line_base: u8| {
+            linter(json!({
+                "program": "checker",
+                "pattern": r"^(\d+)(?:-(\d+))?: (.*)$",
+                "line_match": 1,
+                "end_line_match": 2,
+                "description_match": 3,
+                "line_base": line_base,
+            }))
+        };
+        let lines = |diagnostics: Vec<PicklsDiagnostic>| -> Vec<(u32, u32)> {
+            diagnostics
+                .iter()
+                .map(|diagnostic| (diagnostic.range.start.line, diagnostic.range.end.line))
+                .collect()
+        };
+        let zero_based = parse(&config(0), "0: first\n4-6: range\n");
+        let one_based = parse(&config(1), "1: first\n5-7: range\n");
+        assert_eq!(zero_based, one_based);
+        assert_eq!(lines(one_based), [(0, 0), (4, 6)]);
+        assert_eq!(
+            lines(parse(&config(1), "0: clamped\n0-2: clamped range\n")),
+            [(0, 0), (0, 1)]
+        );
+        assert!(config(2).validate().is_err());
+    }
+
+    /// The description of each diagnostic, with each `(line, column)` it is at.
+    fn described(diagnostics: &[PicklsDiagnostic]) -> Vec<((u32, u32), &str)> {
+        diagnostics
+            .iter()
+            .map(|diagnostic| (position(diagnostic), diagnostic.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_description_on_previous_line() {
+        // gcc's source snippet carries the line number, and the message is the line before it.
+        let config = linter(json!({
+            "program": "gcc",
+            "pattern": r"^\s+(\d+) \| ",
+            "line_match": 1,
+            "description_match": -1,
+        }));
+        let output = "\
+main.c: In function 'main':
+main.c:5:12: error: 'y' undeclared (first use in this function)
+    5 |     return y;
+      |            ^
+";
+        assert_eq!(
+            described(&parse_for(&config, "main.c", output)),
+            [(
+                (4, 0),
+                "main.c:5:12: error: 'y' undeclared (first use in this function)"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_description_two_lines_before() {
+        // The message, then the offending source line, then a caret line with the location.
+        let config = linter(json!({
+            "program": "cc",
+            "pattern": r"^\s*\^ at (\d+):(\d+)$",
+            "line_match": 1,
+            "start_col_match": 2,
+            "description_match": -2,
+        }));
+        let output = "\
+main.c: error: 'y' undeclared (first use in this function)
+    return y;
+           ^ at 5:12
+main.c: warning: unused variable 'x'
+    int x;
+        ^ at 3:9
+";
+        assert_eq!(
+            described(&parse_for(&config, "main.c", output)),
+            [
+                (
+                    (4, 11),
+                    "main.c: error: 'y' undeclared (first use in this function)"
+                ),
+                ((2, 8), "main.c: warning: unused variable 'x'"),
+            ]
+        );
+        let config = PicklsLinterConfig {
+            description_match: None,
+            description_line_offset: Some(-2),
+            ..config
+        };
+        assert_eq!(
+            parse_for(&config, "main.c", output)[1].message,
+            "main.c: warning: unused variable 'x'"
+        );
+    }
+
+    #[test]
+    fn test_description_on_next_line() {
+        // The source line, then a caret line with the location, then the message.
+        let config = linter(json!({
+            "program": "cc",
+            "pattern": r"^\s*\^ (\d+):(\d+)$",
+            "line_match": 1,
+            "start_col_match": 2,
+            "description_line_offset": 1,
+        }));
+        let output = "\
+    return y;
+           ^ 5:12
+error: 'y' undeclared (first use in this function)
+    int x;
+        ^ 3:9
+warning: unused variable 'x'
+        ^ 9:9
+";
+        assert_eq!(
+            described(&parse_for(&config, "main.c", output)),
+            [
+                (
+                    (4, 11),
+                    "error: 'y' undeclared (first use in this function)"
+                ),
+                ((2, 8), "warning: unused variable 'x'"),
+                ((8, 8), "error"),
+            ]
+        );
+        assert!(linter(json!({
+            "program": "cc",
+            "pattern": r"^(\d+)$",
+            "line_match": 1,
+            "description_match": -1,
+            "description_line_offset": 1,
+        }))
+        .validate()
+        .is_err());
+    }
+
+    /// `cargo check` run from a member crate of a workspace, where the first borrow of a value
+    /// is in another file. rustc marks the snippet from the other file with ":::".
+    const CARGO_BORROW_OUTPUT: &str = r#"    Checking demo v0.1.0 (/repo/crates/demo)
+error[E0502]: cannot borrow `cache` as mutable because it is also borrowed as immutable
+ --> crates/demo/src/main.rs:7:5
+  |
+6 |     let entry = lookup(&cache);
+  |                        ------ immutable borrow occurs here
+7 |     cache.clear();
+  |     ^^^^^^^^^^^^^ mutable borrow occurs here
+  |
+ ::: crates/demo/src/lookup.rs:4:5
+  |
+4 |     cache.first()
+  |     ----- previously borrowed here
+
+error: could not compile `demo` (bin "demo") due to 1 previous error
+"#;
+
+    #[test]
+    fn test_related_information() {
+        let config = linter(json!({
+            "program": "cargo",
+            "pattern": r"^\s*--> (.+):(\d+):(\d+)$",
+            "filename_match": 1,
+            "line_match": 2,
+            "start_col_match": 3,
+            "description_match": -1,
+            "related": {
+                "pattern": r"^\s*::: (?P<file>.+):(?P<line>\d+):(?P<col>\d+)$",
+                "filename_match": "file",
+                "line_match": "line",
+                "start_col_match": "col",
+            },
+            "path_resolution": {"base": "rootdir"},
+        }));
+        let root_dir = Path::new("/repo");
+        let diagnostics = config
+            .parse_output(
+                &root_dir.join("crates/demo/src/main.rs"),
+                &ascii_text(),
+                &root_dir.join("crates/demo"),
+                root_dir,
+                CARGO_BORROW_OUTPUT,
+            )
+            .expect("output parses");
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = &diagnostics[0];
+        assert_eq!(position(diagnostic), (6, 4));
+        assert!(diagnostic
+            .message
+            .starts_with("error[E0502]: cannot borrow `cache`"));
+        assert_eq!(
+            diagnostic.related,
+            [PicklsRelatedInformation {
+                file: PathBuf::from("/repo/crates/demo/src/lookup.rs"),
+                range: PicklsRange {
+                    start: PicklsPosition {
+                        line: 3,
+                        character: 4,
+                    },
+                    end: PicklsPosition {
+                        line: 3,
+                        character: 5,
+                    },
+                },
+                message: "::: crates/demo/src/lookup.rs:4:5".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_container_paths_are_unmapped() {
+        // Paths reported from within the container are translated back before being matched
+        // against the file being linted.
+        let config = linter(json!({
+            "program": "ruff",
+            "args": ["check", "$filename"],
+            "pattern": r"^(.+):(\d+): (.*)$",
+            "filename_match": 1,
+            "line_match": 2,
+            "description_match": 3,
+            "container": {"image_or_name": "devbox", "workdir_map": {"$rootdir": "/workspace"}},
+        }));
+        let output = "/workspace/src/main.rs:3: bad\n/workspace/src/lib.rs:1: other\n";
+        let diagnostics = parse(&config, output);
+        assert_eq!(described(&diagnostics), [((2, 0), "bad")]);
+        assert_eq!(diagnostics[0].file, Path::new("/repo/src/main.rs"));
+    }
+
+    #[test]
+    fn test_related_information_defaults() {
+        // Without a filename group, a related location is in the diagnostic's file. Related lines
+        // before any diagnostic are dropped.
+        let config = linter(json!({
+            "program": "lint",
+            "pattern": r"^(\d+): (.*)$",
+            "line_match": 1,
+            "description_match": 2,
+            "related": {
+                "pattern": r"^  (\d+): (.*)$",
+                "line_match": 1,
+                "description_match": 2,
+            },
+        }));
+        let output = "  1: orphaned\n3: redefinition of `f`\n  1: first defined here\n";
+        let diagnostics = parse(&config, output);
+        assert_eq!(diagnostics.len(), 1);
+        let related: Vec<_> = diagnostics[0]
+            .related
+            .iter()
+            .map(|related| (&related.file, related.range.start.line, &*related.message))
+            .collect();
+        assert_eq!(
+            related,
+            [(&PathBuf::from("/repo/src/main.rs"), 0, "first defined here")]
+        );
+    }
+
+    #[test]
+    fn test_fix_text_and_fixable() {
+        // A linter that suggests replacements inline, and marks those that can be applied.
+        let config = linter(json!({
+            "program": "spellcheck",
+            "pattern": r"^(?P<line>\d+):(?P<col>\d+)-(?P<end>\d+): (?P<msg>[^\[]+?)(?: \[(?P<fix>\*)\] => '(?P<text>[^']*)')?$",
+            "line_match": "line",
+            "start_col_match": "col",
+            "end_col_match": "end",
+            "description_match": "msg",
+            "fix_text_match": "text",
+            "fixable_match": "fix",
+        }));
+        let output = "\
+3:5-11: misspelled word 'recieve' [*] => 'receive'
+4:1-3: empty replacement [*] => ''
+7:9-12: unknown word 'qux'
+";
+        let fixes: Vec<_> = parse(&config, output)
+            .iter()
+            .map(|d| {
+                let range = &d.range;
+                let span = (range.start.line, range.start.character, range.end.character);
+                (span, d.message.clone(), d.fix_text.clone(), d.fixable)
+            })
+            .collect();
+        assert_eq!(
+            fixes,
+            [
+                (
+                    (2, 4, 11),
+                    "misspelled word 'recieve'".to_string(),
+                    Some("receive".to_string()),
+                    true
+                ),
+                (
+                    (3, 0, 3),
+                    "empty replacement".to_string(),
+                    Some(String::new()),
+                    true
+                ),
+                ((6, 8, 12), "unknown word 'qux'".to_string(), None, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_code_urls() {
+        let config = linter(json!({
+            "program": "lint",
+            "pattern": r"^(\d+): ([\w-]+) (\S+)? ?(.*)$",
+            "line_match": 1,
+            "code_match": 2,
+            "url_match": 3,
+            "description_match": 4,
+            "code_url_template": "https://docs.example.com/rules/{{code}}",
+        }));
+        let output = "\
+1: no-undef https://eslint.org/docs/latest/rules/no-undef 'x' is not defined
+2: E501 docs/E501.md line too long
+3: W291  trailing whitespace
+";
+        let urls: Vec<_> = parse(&config, output)
+            .into_iter()
+            .map(|d| d.code_url)
+            .collect();
+        assert_eq!(
+            urls,
+            [
+                Some("https://eslint.org/docs/latest/rules/no-undef".to_string()),
+                Some("https://docs.example.com/rules/E501".to_string()),
+                Some("https://docs.example.com/rules/W291".to_string()),
+            ]
+        );
+        let config = PicklsLinterConfig {
+            code_url_template: None,
+            ..config
+        };
+        let urls: Vec<_> = parse(&config, output)
+            .into_iter()
+            .map(|d| d.code_url)
+            .collect();
+        assert_eq!(
+            urls,
+            [
+                Some("https://eslint.org/docs/latest/rules/no-undef".to_string()),
+                None,
+                None
+            ]
+        );
+    }
+
+    /// gcc output for a call with the wrong arguments, with two notes on one error and a note that
+    /// does not carry a location.
+    const GCC_NOTES_OUTPUT: &str = "\
+In file included from main.c:1:
+util.h:3:6: note: in expansion of macro 'CHECKED'
+main.c: In function 'main':
+main.c:6:5: error: too many arguments to function 'area'
+    6 |     area(1, 2, 3);
+      |     ^~~~
+In file included from main.c:1:
+shapes.h:4:5: note: declared here
+    4 | int area(int w, int h);
+      |     ^~~~
+main.c:2:5: note: previous call to 'area' here
+main.c:9:12: warning: unused variable 'y' [-Wunused-variable]
+    9 |     int y;
+      |         ^
+cc1: note: some warnings being treated as errors
+";
+
+    #[test]
+    fn test_gcc_notes() {
+        let config = linter(json!({
+            "program": "gcc",
+            "pattern": r"^(?P<file>[^:\s]+):(?P<line>\d+):(?P<col>\d+): (?P<severity>error|warning): (?P<message>.*)$",
+            "note_pattern": r"^(?:(?P<file>[^:\s]+):(?P<line>\d+):(?P<col>\d+)|[\w.]+): note: (?P<message>.*)$",
+            "filename_match": "file",
+            "line_match": "line",
+            "start_col_match": "col",
+            "severity_match": "severity",
+            "description_match": "message",
+        }));
+        let diagnostics = parse_for(&config, "main.c", GCC_NOTES_OUTPUT);
+        assert_eq!(
+            described(&diagnostics),
+            [
+                ((5, 4), "too many arguments to function 'area'"),
+                (
+                    (8, 11),
+                    "unused variable 'y' [-Wunused-variable]\nsome warnings being treated as errors"
+                ),
+            ]
+        );
+        let related: Vec<_> = diagnostics[0]
+            .related
+            .iter()
+            .map(|related| {
+                let file = related.file.to_str().expect("UTF-8 path");
+                (
+                    file,
+                    related.range.start.line,
+                    related.range.start.character,
+                    &*related.message,
+                )
+            })
+            .collect();
+        assert_eq!(
+            related,
+            [
+                ("/repo/shapes.h", 3, 4, "declared here"),
+                ("/repo/main.c", 1, 4, "previous call to 'area' here"),
+            ]
+        );
+        assert!(diagnostics[1].related.is_empty());
+    }
+
+    #[test]
+    fn test_ruff_json() {
+        let config = linter(json!({
+            "program": "ruff",
+            "args": ["check", "--output-format", "json", "--stdin-filename", "$filename", "-"],
+            "format": "json",
+            "file_path": "filename",
+            "line_path": "location.row",
+            "start_col_path": "location.column",
+            "end_line_path": "end_location.row",
+            "code_path": "code",
+            "message_path": "message",
+        }));
+        let output = r#"[
+  {
+    "cell": null,
+    "code": "F401",
+    "end_location": {"column": 10, "row": 1},
+    "filename": "/repo/src/app.py",
+    "fix": {
+      "applicability": "safe",
+      "edits": [{"content": "", "end_location": {"column": 1, "row": 2}, "location": {"column": 1, "row": 1}}],
+      "message": "Remove unused import: `os`"
+    },
+    "location": {"column": 8, "row": 1},
+    "message": "`os` imported but unused",
+    "noqa_row": 1,
+    "url": "https://docs.astral.sh/ruff/rules/unused-import"
+  },
+  {
+    "cell": null,
+    "code": "E741",
+    "end_location": {"column": 6, "row": 5},
+    "filename": "/repo/src/app.py",
+    "fix": null,
+    "location": {"column": 5, "row": 4},
+    "message": "Ambiguous variable name: `l`",
+    "noqa_row": 4,
+    "url": "https://docs.astral.sh/ruff/rules/ambiguous-variable-name"
+  },
+  {
+    "cell": null,
+    "code": "F821",
+    "end_location": {"column": 2, "row": 2},
+    "filename": "/repo/src/other.py",
+    "fix": null,
+    "location": {"column": 1, "row": 2},
+    "message": "Undefined name `x`",
+    "noqa_row": 2,
+    "url": "https://docs.astral.sh/ruff/rules/undefined-name"
+  }
+]"#;
+        let diagnostics = parse_for(&config, "src/app.py", output);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(position(&diagnostics[0]), (0, 7));
+        assert_eq!(diagnostics[0].code.as_deref(), Some("F401"));
+        assert_eq!(diagnostics[0].message, "`os` imported but unused");
+        assert_eq!(
+            diagnostics[1].range.start,
+            PicklsPosition {
+                line: 3,
+                character: 4
+            }
+        );
+        assert_eq!(diagnostics[1].range.end.line, 4);
+        assert!(parse_for(&config, "src/app.py", "[]").is_empty());
+    }
+
+    #[test]
+    fn test_nested_json_items() {
+        let config = linter(json!({
+            "program": "eslint",
+            "format": "json",
+            "items_path": "[].messages",
+            "file_path": "filePath",
+            "line_path": "line",
+            "start_col_path": "column",
+            "severity_path": "severity",
+            "severity_map": {"1": "warning", "2": "error"},
+            "code_path": "ruleId",
+            "message_path": "message",
+        }));
+        let output = r#"[
+  {"filePath": "/repo/src/app.js", "messages": [
+    {"ruleId": "no-unused-vars", "severity": 2, "message": "'x' is unused.", "line": 1, "column": 7},
+    {"ruleId": "no-console", "severity": 1, "message": "Unexpected console statement.", "line": 3, "column": 1}
+  ]},
+  {"filePath": "/repo/src/other.js", "messages": [
+    {"ruleId": "semi", "severity": 2, "message": "Missing semicolon.", "line": 1, "column": 9}
+  ]},
+  {"filePath": "/repo/src/app.js", "messages": []}
+]"#;
+        let diagnostics = parse_for(&config, "src/app.js", output);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(position(&diagnostics[0]), (0, 6));
+        assert_eq!(diagnostics[0].severity, PicklsSeverity::Error);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("no-unused-vars"));
+        assert_eq!(diagnostics[1].severity, PicklsSeverity::Warning);
+        assert_eq!(diagnostics[1].message, "Unexpected console statement.");
+
+        let config = PicklsLinterConfig {
+            items_path: Some("results".to_string()),
+            file_path: None,
+            ..config
+        };
+        let output = r#"{"results": [{"line": 2, "column": 1, "message": "m", "severity": 2}]}"#;
+        let diagnostics = parse_for(&config, "src/app.js", output);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(position(&diagnostics[0]), (1, 0));
+    }
+
+    #[test]
+    fn test_invalid_utf8_output() {
+        let mut config = linter(json!({
+            "program": "cc",
+            "pattern": r"^(\d+): (.*)$",
+            "line_match": 1,
+            "description_match": 2,
+        }));
+        let output = b"3: bad byte \xff in message\n5: fine\n";
+        let root_dir = Path::new("/repo");
+        let filename = Path::new("/repo/src/main.rs");
+
+        let diagnostics = config
+            .parse_output_bytes(filename, "", root_dir, root_dir, output)
+            .expect("lossy decoding never fails");
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, ["bad byte \u{fffd} in message", "fine"]);
+
+        config.output_encoding = PicklsOutputEncoding::Latin1;
+        let diagnostics = config
+            .parse_output_bytes(filename, "", root_dir, root_dir, output)
+            .unwrap();
+        assert_eq!(diagnostics[0].message, "bad byte \u{ff} in message");
+
+        config.output_encoding = PicklsOutputEncoding::Utf8;
+        let error = config
+            .parse_output_bytes(filename, "", root_dir, root_dir, output)
+            .unwrap_err();
+        assert_eq!(error.location, "cc");
+        assert_eq!(error.field, "output");
+        assert!(error
+            .to_string()
+            .starts_with("invalid cc.output: not valid UTF-8"));
+    }
+
+    #[test]
+    fn test_structured_output_errors_name_the_linter() {
+        let config = linter(json!({
+            "name": "ruff",
+            "program": "ruff",
+            "format": "json",
+            "line_path": "location.row",
+            "message_path": "message",
+        }));
+        let error = config
+            .parse_output(
+                Path::new("/repo/x.py"),
+                "",
+                Path::new("/repo"),
+                Path::new("/repo"),
+                "{",
+            )
+            .unwrap_err();
+        assert_eq!(error.location, "ruff");
+        assert_eq!(error.field, "output");
+    }
+
+    /// A document with an emoji and a CJK character on its second line and CJK on its third.
+    const WIDE_TEXT: &str =
+        "fn main() {}\nlet s = \"a\u{1F600}b\u{4E2D}c\"; x\n\u{65E5}\u{672C}\u{8A9E} y\n";
+
+    fn parse_wide(column_unit: &str, output: &str) -> Vec<PicklsRange> {
+        let config = linter(json!({
+            "program": "lint",
+            "pattern": r"^(\d+):(\d+)(?:-(\d+))?: (.*)$",
+            "line_match": 1,
+            "start_col_match": 2,
+            "end_col_match": 3,
+            "description_match": 4,
+            "column_unit": column_unit,
+        }));
+        let root_dir = Path::new("/repo");
+        config
+            .parse_output(
+                &root_dir.join("x.rs"),
+                WIDE_TEXT,
+                root_dir,
+                root_dir,
+                output,
+            )
+            .expect("output parses")
+            .iter()
+            .map(|diagnostic| diagnostic.range)
+            .collect()
+    }
+
+    fn range(line: u32, start: u32, end: u32) -> PicklsRange {
+        PicklsRange {
+            start: PicklsPosition {
+                line,
+                character: start,
+            },
+            end: PicklsPosition {
+                line,
+                character: end,
+            },
+        }
+    }
+
+    #[test]
+    fn test_columns_convert_to_utf16() {
+        // The emoji and "c" on line 2, and "y" on line 3, in each unit. Columns are one-based and
+        // end columns inclusive.
+        let expected = [range(1, 10, 12), range(1, 14, 15), range(2, 4, 5)];
+        let output = "2:11-14: emoji\n2:19: c\n3:11: y\n";
+        assert_eq!(parse_wide("utf8-bytes", output), expected);
+        let output = "2:11-11: emoji\n2:14: c\n3:5: y\n";
+        assert_eq!(parse_wide("chars", output), expected);
+        let output = "2:11-12: emoji\n2:15: c\n3:5: y\n";
+        assert_eq!(parse_wide("utf16", output), expected);
+    }
+
+    #[test]
+    fn test_columns_inside_a_character() {
+        // A byte range that starts and ends inside the emoji still covers all of it, and columns
+        // past the end of a line are clamped to it.
+        assert_eq!(
+            parse_wide("utf8-bytes", "2:12-12: emoji\n3:40: past the end\n"),
+            [range(1, 10, 12), range(2, 5, 5)]
+        );
+        // Lines the document does not have are left as reported.
+        assert_eq!(parse_wide("chars", "9:3: gone\n"), [range(8, 2, 3)]);
+    }
+
+    #[test]
+    fn test_related_columns_convert_to_utf16() {
+        let config = linter(json!({
+            "program": "lint",
+            "pattern": r"^(\d+):(\d+): (.*)$",
+            "line_match": 1,
+            "start_col_match": 2,
+            "description_match": 3,
+            "column_unit": "utf8-bytes",
+            "related": {
+                "pattern": r"^  (\d+):(\d+): (.*)$",
+                "line_match": 1,
+                "start_col_match": 2,
+                "description_match": 3,
+            },
+        }));
+        let root_dir = Path::new("/repo");
+        let output = "3:11: y\n  2:19: c\n";
+        let diagnostics = config
+            .parse_output(
+                &root_dir.join("x.rs"),
+                WIDE_TEXT,
+                root_dir,
+                root_dir,
+                output,
+            )
+            .expect("output parses");
+        assert_eq!(diagnostics[0].range, range(2, 4, 5));
+        assert_eq!(diagnostics[0].related[0].range, range(1, 14, 15));
+    }
+
+    #[test]
+    fn test_whole_file_ends_at_the_end_of_the_document() {
+        let config = linter(json!({
+            "program": "gofmt",
+            "pattern": r"^(.*\.go)$",
+            "filename_match": 1,
+            "whole_file": true,
+        }));
+        let root_dir = Path::new("/repo");
+        let text = "package main\n\nfunc \u{4E2D}() {}";
+        let diagnostics = config
+            .parse_output(&root_dir.join("x.go"), text, root_dir, root_dir, "x.go\n")
+            .expect("output parses");
+        assert_eq!(diagnostics[0].range.start, PicklsPosition::default());
+        assert_eq!(
+            diagnostics[0].range.end,
+            PicklsPosition {
+                line: 2,
+                character: 11,
+            }
+        );
+        let diagnostics = config
+            .parse_output(
+                &root_dir.join("x.go"),
+                "x\r\n",
+                root_dir,
+                root_dir,
+                "x.go\n",
+            )
+            .expect("output parses");
+        assert_eq!(
+            diagnostics[0].range.end,
+            PicklsPosition {
+                line: 1,
+                character: 0,
+            }
+        );
+    }
+
+    /// `semgrep --sarif` output for two findings in app/views.py and one in app/models.py, which
+    /// leaves the severity of its results to the `defaultConfiguration` of their rules.
+    const SEMGREP_SARIF_OUTPUT: &str = r#"{
+  "$schema": "https://docs.oasis-open.org/sarif/sarif/v2.1.0/os/schemas/sarif-schema-2.1.0.json",
+  "runs": [
+    {
+      "invocations": [{"executionSuccessful": true, "toolExecutionNotifications": []}],
+      "results": [
+        {
+          "fingerprints": {"matchBasedId/v1": "2c0f5a0d5b7e"},
+          "locations": [
+            {
+              "physicalLocation": {
+                "artifactLocation": {"uri": "app/views.py", "uriBaseId": "%SRCROOT%"},
+                "region": {
+                  "endColumn": 29,
+                  "endLine": 12,
+                  "snippet": {"text": "    return eval(request.args[\"q\"])"},
+                  "startColumn": 12,
+                  "startLine": 12
+                }
+              }
+            }
+          ],
+          "message": {"text": "Detected the use of eval(). eval() can be dangerous if used to evaluate dynamic content."},
+          "properties": {},
+          "ruleId": "python.lang.security.audit.eval-detected.eval-detected"
+        },
+        {
+          "fingerprints": {"matchBasedId/v1": "91d4c3e8a6f1"},
+          "locations": [
+            {
+              "physicalLocation": {
+                "artifactLocation": {"uri": "app/views.py", "uriBaseId": "%SRCROOT%"},
+                "region": {
+                  "endColumn": 44,
+                  "endLine": 20,
+                  "snippet": {"text": "    requests.get(url, verify=False)"},
+                  "startColumn": 5,
+                  "startLine": 19
+                }
+              }
+            }
+          ],
+          "message": {"text": "Detected a request with certificate verification disabled."},
+          "properties": {},
+          "ruleId": "python.requests.security.disabled-cert-validation.disabled-cert-validation"
+        },
+        {
+          "fingerprints": {"matchBasedId/v1": "5e7a9b2c4d10"},
+          "locations": [
+            {
+              "physicalLocation": {
+                "artifactLocation": {"uri": "app/models.py", "uriBaseId": "%SRCROOT%"},
+                "region": {"endColumn": 40, "endLine": 3, "startColumn": 1, "startLine": 3}
+              }
+            }
+          ],
+          "message": {"text": "Detected the use of eval()."},
+          "properties": {},
+          "ruleId": "python.lang.security.audit.eval-detected.eval-detected"
+        }
+      ],
+      "tool": {
+        "driver": {
+          "name": "Semgrep OSS",
+          "rules": [
+            {
+              "defaultConfiguration": {"level": "warning"},
+              "fullDescription": {"text": "Detected a request with certificate verification disabled."},
+              "id": "python.requests.security.disabled-cert-validation.disabled-cert-validation",
+              "name": "python.requests.security.disabled-cert-validation.disabled-cert-validation",
+              "shortDescription": {"text": "Semgrep Finding: python.requests.security.disabled-cert-validation.disabled-cert-validation"}
+            },
+            {
+              "defaultConfiguration": {"level": "error"},
+              "fullDescription": {"text": "Detected the use of eval()."},
+              "id": "python.lang.security.audit.eval-detected.eval-detected",
+              "name": "python.lang.security.audit.eval-detected.eval-detected",
+              "shortDescription": {"text": "Semgrep Finding: python.lang.security.audit.eval-detected.eval-detected"}
+            }
+          ],
+          "semanticVersion": "1.85.0"
+        }
+      }
+    }
+  ],
+  "version": "2.1.0"
+}"#;
+
+    fn sarif_linter() -> PicklsLinterConfig {
+        linter(json!({
+            "program": "semgrep",
+            "args": ["--sarif", "--config", "auto", "$filename"],
+            "format": "sarif",
+            "path_resolution": {"base": "rootdir"},
+        }))
+    }
+
+    #[test]
+    fn test_semgrep_sarif() {
+        let diagnostics = parse_for(&sarif_linter(), "app/views.py", SEMGREP_SARIF_OUTPUT);
+        assert_eq!(diagnostics.len(), 2, "app/models.py is a foreign file");
+        let eval = &diagnostics[0];
+        assert_eq!(
+            eval.code.as_deref(),
+            Some("python.lang.security.audit.eval-detected.eval-detected")
+        );
+        assert_eq!(eval.severity, PicklsSeverity::Error);
+        assert_eq!(eval.range, range(11, 11, 28));
+        assert!(eval.message.starts_with("Detected the use of eval()."));
+        let request = &diagnostics[1];
+        assert_eq!(request.severity, PicklsSeverity::Warning);
+        assert_eq!(
+            (request.range.start, request.range.end),
+            (
+                PicklsPosition {
+                    line: 18,
+                    character: 4,
+                },
+                PicklsPosition {
+                    line: 19,
+                    character: 43,
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_sarif_levels_and_uris() {
+        // A result's own level wins over its rule's, and "note" is informational. URIs may be
+        // absolute and percent-encoded.
+        let result = |uri: &str, level: &str| {
+            json!({
+                "ruleId": "R1",
+                "level": level,
+                "message": {"text": level},
+                "locations": [{"physicalLocation": {
+                    "artifactLocation": {"uri": uri},
+                    "region": {"startLine": 2, "startColumn": 3},
+                }}],
+            })
+        };
+        let output = json!({
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {"driver": {"name": "lint", "rules": [
+                    {"id": "R1", "defaultConfiguration": {"level": "error"}},
+                ]}},
+                "results": [
+                    result("file:///repo/src/my%20file.py", "note"),
+                    result("src/my%20file.py", "warning"),
+                    result("src/my file%2Epy", "error"),
+                    result("file:///repo/src/other.py", "error"),
+                ],
+            }],
+        });
+        let diagnostics = parse_for(&sarif_linter(), "src/my file.py", &output.to_string());
+        let severities: Vec<PicklsSeverity> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.severity)
+            .collect();
+        assert_eq!(
+            severities,
+            [
+                PicklsSeverity::Information,
+                PicklsSeverity::Warning,
+                PicklsSeverity::Error
+            ]
+        );
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.range == range(1, 2, 3)));
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("my%20file%2Epy"), "my file.py");
+        assert_eq!(percent_decode("caf%C3%A9.py"), "caf\u{e9}.py");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz%2_%+1"), "%zz%2_%+1");
+    }
+
+    #[test]
+    fn test_malformed_sarif_names_the_linter() {
+        for (output, message) in [
+            ("<html>", "not a valid SARIF document: expected value"),
+            (
+                r#"{"version": "2.1.0"}"#,
+                "not a valid SARIF document: missing runs",
+            ),
+        ] {
+            let error = sarif_linter()
+                .parse_output(
+                    Path::new("/repo/x.py"),
+                    "",
+                    Path::new("/repo"),
+                    Path::new("/repo"),
+                    output,
+                )
+                .unwrap_err();
+            assert_eq!(error.location, "semgrep");
+            assert!(
+                error
+                    .to_string()
+                    .starts_with(&format!("invalid semgrep.output: {message}")),
+                "{error}"
+            );
+        }
+    }
+
+    /// `phpcs --report=checkstyle` output, whose messages may quote a raw ">".
+    const PHPCS_CHECKSTYLE_OUTPUT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<checkstyle version="3.10.3">
+<file name="/repo/src/Controller.php">
+ <error line="12" column="18" severity="error" message="Expected 1 space after '=>'; 0 found" source="Squiz.WhiteSpace.OperatorSpacing.NoSpaceAfter"/>
+ <error line="30" column="5" severity="warning" message="Line exceeds 120 characters; contains 134 characters" source="Generic.Files.LineLength.TooLong"/>
+</file>
+<file name="/repo/src/Model.php">
+ <error line="1" column="1" severity="error" message="Missing file doc comment" source="PEAR.Commenting.FileComment.Missing"/>
+</file>
+</checkstyle>
+"#;
+
+    /// `ktlint --reporter=checkstyle` output, with filenames relative to where it ran.
+    const KTLINT_CHECKSTYLE_OUTPUT: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<checkstyle version="8.0">
+    <file name="src/main/kotlin/App.kt">
+        <error line="3" column="1" severity="error" message="Unexpected blank line(s) before &quot;}&quot;" source="standard:no-blank-line-before-rbrace" />
+        <error line="7" column="13" severity="error" message="Missing spacing around &quot;-&gt;&quot;" source="standard:curly-spacing" />
+    </file>
+</checkstyle>
+"#;
+
+    fn checkstyle_linter(program: &str) -> PicklsLinterConfig {
+        linter(json!({"program": program, "format": "checkstyle"}))
+    }
+
+    #[test]
+    fn test_phpcs_checkstyle() {
+        let diagnostics = parse_for(
+            &checkstyle_linter("phpcs"),
+            "src/Controller.php",
+            PHPCS_CHECKSTYLE_OUTPUT,
+        );
+        assert_eq!(
+            described(&diagnostics),
+            [
+                ((11, 17), "Expected 1 space after '=>'; 0 found"),
+                (
+                    (29, 4),
+                    "Line exceeds 120 characters; contains 134 characters"
+                ),
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].code.as_deref(),
+            Some("Squiz.WhiteSpace.OperatorSpacing.NoSpaceAfter")
+        );
+        assert_eq!(
+            severities(&diagnostics),
+            [PicklsSeverity::Error, PicklsSeverity::Warning]
+        );
+    }
+
+    #[test]
+    fn test_ktlint_checkstyle() {
+        let diagnostics = parse_for(
+            &checkstyle_linter("ktlint"),
+            "src/main/kotlin/App.kt",
+            KTLINT_CHECKSTYLE_OUTPUT,
+        );
+        assert_eq!(
+            described(&diagnostics),
+            [
+                ((2, 0), "Unexpected blank line(s) before \"}\""),
+                ((6, 12), "Missing spacing around \"->\""),
+            ]
+        );
+        assert_eq!(
+            diagnostics[1].code.as_deref(),
+            Some("standard:curly-spacing")
+        );
+    }
+
+    #[test]
+    fn test_checkstyle_unknown_severity_is_a_warning() {
+        let output = r#"<checkstyle><file name="src/A.java"><error line="2" severity="blocker" message="x"/><error line='3' message='y'/></file></checkstyle>"#;
+        let diagnostics = parse_for(&checkstyle_linter("pmd"), "src/A.java", output);
+        assert_eq!(
+            severities(&diagnostics),
+            [PicklsSeverity::Warning, PicklsSeverity::Warning]
+        );
+        assert_eq!(described(&diagnostics), [((1, 0), "x"), ((2, 0), "y")]);
+    }
+
+    #[test]
+    fn test_xml_unescape() {
+        assert_eq!(
+            xml_unescape("&lt;a&gt; &amp;&quot;&apos; &#65;&#x42; &bogus; &#xzz;"),
+            "<a> &\"' AB &bogus; &#xzz;"
+        );
+    }
+}
@@ -0,0 +1,299 @@
+use crate::{PicklsConfigError, PicklsSubstitutions};
+use glob::{MatchOptions, Pattern};
+use std::{
+    ffi::{OsStr, OsString},
+    path::{Component, Path, PathBuf},
+};
+
+/// Whether any of `patterns` (ie: "package.json" or ".eslintrc*") names an existing file in
+/// `start_dir` or any of its ancestors up to and including `root_dir`. When `start_dir` is not
+/// within `root_dir`, only `start_dir` is searched.
+pub fn any_file_exists_between(patterns: &[String], start_dir: &Path, root_dir: &Path) -> bool {
+    let dirs = start_dir.ancestors();
+    let dirs: Vec<&Path> = if start_dir.starts_with(root_dir) {
+        dirs.take_while(|dir| dir.starts_with(root_dir)).collect()
+    } else {
+        dirs.take(1).collect()
+    };
+    dirs.into_iter().any(|dir| {
+        let Some(dir) = dir.to_str() else {
+            return false;
+        };
+        patterns.iter().any(|pattern| {
+            let pattern = format!("{}/{pattern}", Pattern::escape(dir));
+            glob::glob(&pattern)
+                .map(|mut paths| paths.any(|path| path.is_ok()))
+                .unwrap_or(false)
+        })
+    })
+}
+
+/// Whether `path` matches any of `globs`, (ie: "vendor/**" or "**/*_pb2.py"). `*` does not match
+/// across path separators, while `**` matches any number of directories. Invalid globs never
+/// match.
+pub fn matches_any_glob(globs: &[String], path: &Path) -> bool {
+    let options = MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+    globs.iter().any(|glob| {
+        Pattern::new(glob)
+            .map(|pattern| pattern.matches_path_with(path, options))
+            .unwrap_or(false)
+    })
+}
+
+/// Check that each of `globs` is a valid glob pattern. `field` names the configuration field that
+/// holds them, for error reporting.
+pub fn validate_globs(field: &str, globs: &[String]) -> Result<(), PicklsConfigError> {
+    for glob in globs {
+        Pattern::new(glob).map_err(|error| {
+            PicklsConfigError::new(field, format!("invalid glob '{glob}': {error}"))
+        })?;
+    }
+    Ok(())
+}
+
+/// Lexically normalize `path` by removing `.` components and resolving `..` components against
+/// the components before them, (ie: "/repo/./src/../x.py" becomes "/repo/x.py"). The filesystem
+/// is not consulted, so symlinks are not resolved.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Whether `a` and `b` refer to the same file, after normalizing both with [`normalize_path`].
+/// When `canonicalize` is set, paths that exist are first resolved through the filesystem, so
+/// that a path through a symlinked checkout matches the real path. When `case_insensitive` is
+/// set, paths that differ only in case match.
+pub fn paths_match(a: &Path, b: &Path, canonicalize: bool, case_insensitive: bool) -> bool {
+    let resolve = |path: &Path| {
+        let path = normalize_path(path);
+        match canonicalize {
+            true => path.canonicalize().unwrap_or(path),
+            false => path,
+        }
+    };
+    let (a, b) = (resolve(a), resolve(b));
+    if case_insensitive {
+        a.to_string_lossy().to_lowercase() == b.to_string_lossy().to_lowercase()
+    } else {
+        a == b
+    }
+}
+
+/// Find the executable that running `program` would use, searching `path` (the value of a `PATH`
+/// environment variable) when `program` is not itself a path. Returns None when no executable
+/// file is found.
+pub fn find_program(program: &str, path: Option<&OsStr>) -> Option<PathBuf> {
+    let program_path = Path::new(program);
+    if program_path.components().count() > 1 {
+        return is_executable(program_path).then(|| program_path.to_path_buf());
+    }
+    std::env::split_paths(path?)
+        .map(|dir| dir.join(program))
+        .find(|candidate| is_executable(candidate))
+}
+
+/// Find `program` within one of `tool_dirs` (ie: "node_modules/.bin") of `start_dir` or any of its
+/// ancestors up to and including `root_dir`, nearest first. `program` must be a bare name, since
+/// paths are run as given. When `start_dir` is not within `root_dir`, only `start_dir` is searched.
+pub fn find_local_program(
+    program: &str,
+    tool_dirs: &[&str],
+    start_dir: &Path,
+    root_dir: &Path,
+) -> Option<PathBuf> {
+    if Path::new(program).components().count() != 1 {
+        return None;
+    }
+    let dirs = start_dir.ancestors();
+    let dirs: Vec<&Path> = if start_dir.starts_with(root_dir) {
+        dirs.take_while(|dir| dir.starts_with(root_dir)).collect()
+    } else {
+        dirs.take(1).collect()
+    };
+    dirs.into_iter().find_map(|dir| {
+        tool_dirs
+            .iter()
+            .map(|tool_dir| dir.join(tool_dir).join(program))
+            .find(|candidate| is_executable(candidate))
+    })
+}
+
+/// A `PATH` made of the directories in `extra_path` followed by those in `path` (the value of the
+/// inherited `PATH` environment variable). Each entry of `extra_path` undergoes substitutions, and
+/// a leading "~/" refers to the user's home directory. Entries that do not name an existing
+/// directory are skipped. Returns None only when there are no directories at all.
+pub fn search_path<'a>(
+    extra_path: impl IntoIterator<Item = &'a String>,
+    substitutions: &PicklsSubstitutions,
+    path: Option<&OsStr>,
+) -> Option<OsString> {
+    let home = std::env::var_os("HOME");
+    let extra_dirs = extra_path.into_iter().filter_map(|dir| {
+        let dir = substitutions.apply(dir);
+        let dir = match (dir.strip_prefix("~/"), &home) {
+            (Some(rest), Some(home)) => Path::new(home).join(rest),
+            _ => PathBuf::from(dir),
+        };
+        dir.is_dir().then_some(dir)
+    });
+    let dirs: Vec<PathBuf> = extra_dirs
+        .chain(path.into_iter().flat_map(std::env::split_paths))
+        .filter(|dir| std::env::join_paths([dir]).is_ok())
+        .collect();
+    if dirs.is_empty() {
+        return None;
+    }
+    std::env::join_paths(dirs).ok()
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file() || path.with_extension("exe").is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PicklsPathBase, PicklsPathResolution};
+    use std::fs;
+
+    /// An empty directory under the system temp directory, unique to `name` and this process.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pickls-config-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("scratch dir is created");
+        dir
+    }
+
+    #[cfg(unix)]
+    fn write_executable(path: &Path) {
+        use std::os::unix::fs::PermissionsExt;
+        fs::write(path, "#!/bin/sh\n").expect("file is written");
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755)).expect("file is executable");
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        let normalize = |path: &str| normalize_path(Path::new(path));
+        assert_eq!(normalize("/repo/./src/../x.py"), Path::new("/repo/x.py"));
+        assert_eq!(normalize("./src/x.py"), Path::new("src/x.py"));
+        assert_eq!(normalize("../x.py"), Path::new("../x.py"));
+        assert_eq!(normalize("a/../../b"), Path::new("../b"));
+    }
+
+    #[test]
+    fn test_relative_and_absolute_paths_match() {
+        let resolution = PicklsPathResolution {
+            base: PicklsPathBase::Cwd,
+            canonicalize: false,
+            case_insensitive: false,
+        };
+        let working_dir = Path::new("/abs/path");
+        let current = Path::new("/abs/path/src/x.py");
+        for captured in [
+            "./src/x.py",
+            "src/x.py",
+            "src/../src/x.py",
+            "/abs/path/src/x.py",
+        ] {
+            let resolved =
+                resolution.resolve(captured, working_dir, Path::new("/"), Path::new("/"));
+            assert!(resolution.is_same_file(&resolved, current), "{captured}");
+        }
+        let resolved = resolution.resolve("./src/y.py", working_dir, working_dir, working_dir);
+        assert!(!resolution.is_same_file(&resolved, current));
+        assert!(paths_match(
+            Path::new("/abs/path/./src/x.py"),
+            current,
+            false,
+            false
+        ));
+    }
+
+    #[test]
+    fn test_case_insensitive_paths_match() {
+        let (a, b) = (
+            Path::new("/Users/me/Src/X.py"),
+            Path::new("/users/me/src/x.py"),
+        );
+        assert!(paths_match(a, b, false, true));
+        assert!(!paths_match(a, b, false, false));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_checkout_paths_match() {
+        let dir = scratch_dir("symlinked-checkout");
+        let checkout = dir.join("checkout");
+        let link = dir.join("link");
+        fs::create_dir_all(checkout.join("src")).expect("checkout is created");
+        fs::write(checkout.join("src/x.py"), "").expect("file is written");
+        std::os::unix::fs::symlink(&checkout, &link).expect("symlink is created");
+        let (real, linked) = (checkout.join("src/x.py"), link.join("./src/x.py"));
+        assert!(paths_match(&linked, &real, true, false));
+        assert!(!paths_match(&linked, &real, false, false));
+        // Paths that do not exist still compare lexically.
+        assert!(paths_match(
+            &link.join("gone.py"),
+            &link.join("src/../gone.py"),
+            true,
+            false
+        ));
+        fs::remove_dir_all(&dir).expect("scratch dir is removed");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_program() {
+        let dir = scratch_dir("find-program");
+        let (first, second) = (dir.join("first"), dir.join("second"));
+        fs::create_dir_all(&first).expect("dir is created");
+        fs::create_dir_all(&second).expect("dir is created");
+        write_executable(&second.join("ruff"));
+        write_executable(&first.join("mypy"));
+        write_executable(&second.join("mypy"));
+        fs::write(first.join("ruff"), "").expect("file is written");
+        fs::create_dir_all(first.join("eslint")).expect("dir is created");
+        let path =
+            std::env::join_paths([&first, &dir.join("missing"), &second]).expect("path is joined");
+        let find = |program: &str| find_program(program, Some(&path));
+        // Files that are not executable, and directories, are passed over.
+        assert_eq!(find("ruff"), Some(second.join("ruff")));
+        assert_eq!(find("mypy"), Some(first.join("mypy")));
+        assert_eq!(find("eslint"), None);
+        assert_eq!(find("shellcheck"), None);
+        assert_eq!(find_program("ruff", None), None);
+        // Paths are used as given, without searching `PATH`.
+        let program = second.join("ruff");
+        let program = program.to_str().expect("UTF-8 path");
+        assert_eq!(find_program(program, None), Some(second.join("ruff")));
+        let program = first.join("ruff");
+        assert_eq!(find(program.to_str().expect("UTF-8 path")), None);
+        fs::remove_dir_all(&dir).expect("scratch dir is removed");
+    }
+}
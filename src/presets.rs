@@ -0,0 +1,516 @@
+use crate::{PicklsFormatterConfig, PicklsLinterConfig};
+use serde::{de::Error as _, Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Built-in linter configurations, addressable by name via `preset`. Each is the JSON form of a
+/// [`PicklsLinterConfig`].
+const LINTER_PRESETS: &[(&str, &str)] = &[
+    (
+        "ruff",
+        r#"{
+            "program": "ruff",
+            "args": ["check", "--output-format", "concise", "--stdin-filename", "$filename", "-"],
+            "use_stdin": true,
+            "pattern": "^(?P<file>.+?):(?P<line>\\d+):(?P<col>\\d+): (?P<code>[A-Z]+[0-9]+) (?P<fixable>\\[\\*\\] )?(?P<message>.*)$",
+            "filename_match": "file",
+            "line_match": "line",
+            "start_col_match": "col",
+            "code_match": "code",
+            "fixable_match": "fixable",
+            "description_match": "message",
+            "code_tags": {"F401": "unnecessary", "F841": "unnecessary"},
+            "success_exit_codes": [0, 1]
+        }"#,
+    ),
+    (
+        "mypy",
+        r#"{
+            "program": "mypy",
+            "args": [
+                "--show-column-numbers",
+                "--show-error-codes",
+                "--no-error-summary",
+                "--no-pretty",
+                "--shadow-file",
+                "$filename",
+                "$tmpfilename",
+                "$filename"
+            ],
+            "use_stdin": false,
+            "pattern": "^(?P<file>.+?):(?P<line>\\d+):(?:(?P<col>\\d+):)? (?P<severity>error|warning|note): (?P<message>.*?)(?:  \\[(?P<code>[a-z0-9-]+)\\])?$",
+            "filename_match": "file",
+            "line_match": "line",
+            "start_col_match": "col",
+            "severity_match": "severity",
+            "code_match": "code",
+            "description_match": "message",
+            "success_exit_codes": [0, 1]
+        }"#,
+    ),
+    (
+        "eslint",
+        r#"{
+            "program": "eslint",
+            "args": ["--format", "unix", "--stdin", "--stdin-filename", "$filename"],
+            "use_stdin": true,
+            "pattern": "^(?P<file>.+?):(?P<line>\\d+):(?P<col>\\d+): (?P<message>.*) \\[(?P<severity>Error|Warning)(?:/(?P<code>[^\\]]+))?\\]$",
+            "filename_match": "file",
+            "line_match": "line",
+            "start_col_match": "col",
+            "severity_match": "severity",
+            "code_match": "code",
+            "description_match": "message",
+            "success_exit_codes": [0, 1]
+        }"#,
+    ),
+    (
+        "shellcheck",
+        r#"{
+            "program": "shellcheck",
+            "args": ["--format", "gcc", "-"],
+            "use_stdin": true,
+            "pattern": "^.+?:(?P<line>\\d+):(?P<col>\\d+): (?P<severity>error|warning|note|style): (?P<message>.*) \\[(?P<code>SC\\d+)\\]$",
+            "line_match": "line",
+            "start_col_match": "col",
+            "severity_match": "severity",
+            "severity_map": {"style": "hint"},
+            "code_match": "code",
+            "description_match": "message",
+            "code_url_template": "https://www.shellcheck.net/wiki/{{code}}",
+            "success_exit_codes": [0, 1]
+        }"#,
+    ),
+    (
+        "golangci-lint",
+        r#"{
+            "program": "golangci-lint",
+            "args": ["run", "--out-format", "line-number", "$dirname"],
+            "use_stdin": false,
+            "pattern": "^(?P<file>.+?):(?P<line>\\d+)(?::(?P<col>\\d+))?: (?P<message>.*) \\((?P<code>[a-z0-9-]+)\\)$",
+            "filename_match": "file",
+            "line_match": "line",
+            "start_col_match": "col",
+            "code_match": "code",
+            "description_match": "message",
+            "success_exit_codes": [0, 1]
+        }"#,
+    ),
+];
+
+/// The names of the built-in linter presets.
+pub fn linter_preset_names() -> impl Iterator<Item = &'static str> {
+    LINTER_PRESETS.iter().map(|(name, _)| *name)
+}
+
+pub(crate) fn linter_preset_value(name: &str) -> Option<Value> {
+    LINTER_PRESETS
+        .iter()
+        .find(|(preset, _)| *preset == name)
+        .map(|(_, json)| {
+            serde_json::from_str(json).expect("built-in linter presets are valid JSON")
+        })
+}
+
+impl PicklsLinterConfig {
+    /// Look up a built-in linter preset by name, (ie: "ruff"). See [`linter_preset_names`].
+    pub fn preset(name: &str) -> Option<PicklsLinterConfig> {
+        linter_preset_value(name)
+            .map(|value| serde_json::from_value(value).expect("built-in linter presets are valid"))
+    }
+}
+
+/// Expand `preset` within a linter's configuration by overlaying the explicitly-set fields onto
+/// the named preset.
+fn expand_linter_preset(value: Value) -> Result<Value, String> {
+    let Value::Object(mut fields) = value else {
+        return Ok(value);
+    };
+    let Some(preset) = fields.get("preset") else {
+        return Ok(Value::Object(fields));
+    };
+    let Some(name) = preset.as_str() else {
+        return Err(format!("invalid preset {preset}, expected a string"));
+    };
+    let Some(Value::Object(mut base)) = linter_preset_value(name) else {
+        return Err(format!(
+            "unknown linter preset '{name}' (expected one of: {})",
+            linter_preset_names().collect::<Vec<_>>().join(", ")
+        ));
+    };
+    base.append(&mut fields);
+    Ok(Value::Object(base))
+}
+
+/// Deserialize a linter's configuration, expanding `preset` and remembering which fields were
+/// explicitly set.
+fn linter_from_value(value: Value) -> Result<PicklsLinterConfig, String> {
+    let value = expand_linter_preset(value)?;
+    let fields = match &value {
+        Value::Object(fields) => fields.clone(),
+        _ => Map::new(),
+    };
+    let mut linter: PicklsLinterConfig =
+        serde_json::from_value(value).map_err(|error| error.to_string())?;
+    linter.fields = fields;
+    Ok(linter)
+}
+
+/// Deserialize a formatter's configuration, remembering which fields were explicitly set.
+/// There are no formatter presets, so `preset` is rejected rather than ignored.
+fn formatter_from_value(value: Value) -> Result<PicklsFormatterConfig, String> {
+    let fields = match &value {
+        Value::Object(fields) => fields.clone(),
+        _ => Map::new(),
+    };
+    if fields.contains_key("preset") {
+        return Err(
+            "formatters do not support preset; it applies to linters and to a formatter's \
+             diagnostics"
+                .to_string(),
+        );
+    }
+    let mut formatter: PicklsFormatterConfig =
+        serde_json::from_value(value).map_err(|error| error.to_string())?;
+    formatter.fields = fields;
+    Ok(formatter)
+}
+
+/// An entry in a language's `linters` or `formatters`: either an inline configuration or the name
+/// of a definition, which is shorthand for `{"extends": name}`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Entry {
+    Reference(String),
+    Inline(Value),
+}
+
+impl Entry {
+    fn into_value(self) -> Value {
+        match self {
+            Entry::Reference(name) => serde_json::json!({ "extends": name }),
+            Entry::Inline(value) => value,
+        }
+    }
+}
+
+pub(crate) fn deserialize_linters<'de, D>(
+    deserializer: D,
+) -> Result<Vec<PicklsLinterConfig>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<Entry>::deserialize(deserializer)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            linter_from_value(entry.into_value())
+                .map_err(|error| D::Error::custom(format!("linters[{index}]: {error}")))
+        })
+        .collect()
+}
+
+pub(crate) fn deserialize_formatters<'de, D>(
+    deserializer: D,
+) -> Result<Vec<PicklsFormatterConfig>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<Entry>::deserialize(deserializer)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            formatter_from_value(entry.into_value())
+                .map_err(|error| D::Error::custom(format!("formatters[{index}]: {error}")))
+        })
+        .collect()
+}
+
+pub(crate) fn deserialize_fallbacks<'de, D>(
+    deserializer: D,
+) -> Result<Vec<PicklsFormatterConfig>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<Value>::deserialize(deserializer)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| {
+            formatter_from_value(value)
+                .map_err(|error| D::Error::custom(format!("fallbacks[{index}]: {error}")))
+        })
+        .collect()
+}
+
+pub(crate) fn deserialize_formatter_diagnostics<'de, D>(
+    deserializer: D,
+) -> Result<Option<PicklsLinterConfig>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<Value>::deserialize(deserializer)?
+        .map(|value| {
+            linter_from_value(value)
+                .map_err(|error| D::Error::custom(format!("diagnostics: {error}")))
+        })
+        .transpose()
+}
+
+pub(crate) fn deserialize_organize_imports<'de, D>(
+    deserializer: D,
+) -> Result<Option<PicklsFormatterConfig>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<Entry>::deserialize(deserializer)?
+        .map(|entry| {
+            formatter_from_value(entry.into_value())
+                .map_err(|error| D::Error::custom(format!("organize_imports: {error}")))
+        })
+        .transpose()
+}
+
+pub(crate) fn deserialize_linter_definitions<'de, D>(
+    deserializer: D,
+) -> Result<HashMap<String, PicklsLinterConfig>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    HashMap::<String, Value>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(name, value)| {
+            let linter = linter_from_value(value)
+                .map_err(|error| D::Error::custom(format!("linter_definitions.{name}: {error}")))?;
+            Ok((name, linter))
+        })
+        .collect()
+}
+
+pub(crate) fn deserialize_formatter_definitions<'de, D>(
+    deserializer: D,
+) -> Result<HashMap<String, PicklsFormatterConfig>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    HashMap::<String, Value>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(name, value)| {
+            let formatter = formatter_from_value(value).map_err(|error| {
+                D::Error::custom(format!("formatter_definitions.{name}: {error}"))
+            })?;
+            Ok((name, formatter))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PicklsDiagnostic, PicklsDiagnosticTag, PicklsLanguageConfig, PicklsSeverity};
+    use serde_json::json;
+    use std::path::Path;
+
+    fn language(config: Value) -> Result<PicklsLanguageConfig, serde_json::Error> {
+        serde_json::from_value(config)
+    }
+
+    /// Parse `output` for `/repo/<file>` with the named preset, run from `/repo`.
+    fn parse_preset(name: &str, file: &str, output: &str) -> Vec<PicklsDiagnostic> {
+        let compiled = PicklsLinterConfig::preset(name)
+            .expect("preset exists")
+            .compile()
+            .expect("preset compiles");
+        // An ASCII document with room for every fixture position, so columns convert unchanged.
+        let text = format!("{}\n", " ".repeat(200)).repeat(2000);
+        let root_dir = Path::new("/repo");
+        compiled
+            .parse_output(&root_dir.join(file), &text, root_dir, root_dir, output)
+            .expect("output parses")
+    }
+
+    fn position(diagnostic: &PicklsDiagnostic) -> (u32, u32) {
+        (
+            diagnostic.range.start.line,
+            diagnostic.range.start.character,
+        )
+    }
+
+    #[test]
+    fn test_every_preset_compiles() {
+        for name in linter_preset_names() {
+            let preset = PicklsLinterConfig::preset(name).unwrap();
+            assert!(preset.compile().is_ok(), "{name}");
+            assert_eq!(preset.display_name(), name);
+        }
+        assert!(PicklsLinterConfig::preset("nope").is_none());
+    }
+
+    #[test]
+    fn test_ruff_preset() {
+        let output = "\
+src/app.py:1:8: F401 [*] `os` imported but unused
+src/app.py:4:5: E741 Ambiguous variable name: `l`
+src/other.py:2:1: F821 Undefined name `x`
+Found 3 errors.
+[*] 1 fixable with the `--fix` option.
+";
+        let diagnostics = parse_preset("ruff", "src/app.py", output);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(position(&diagnostics[0]), (0, 7));
+        assert_eq!(diagnostics[0].code.as_deref(), Some("F401"));
+        assert_eq!(diagnostics[0].message, "`os` imported but unused");
+        assert!(diagnostics[0].fixable);
+        assert_eq!(diagnostics[0].tags, [PicklsDiagnosticTag::Unnecessary]);
+        assert_eq!(position(&diagnostics[1]), (3, 4));
+        assert_eq!(diagnostics[1].code.as_deref(), Some("E741"));
+        assert!(!diagnostics[1].fixable);
+    }
+
+    #[test]
+    fn test_mypy_preset() {
+        let output = "\
+src/app.py:12:12: error: Incompatible return value type (got \"int\", expected \"str\")  [return-value]
+src/app.py:20: note: See https://mypy.rtfd.io/en/stable/_refs.html#code-union-attr
+src/app.py:31:5: warning: Unused \"type: ignore\" comment  [unused-ignore]
+";
+        let diagnostics = parse_preset("mypy", "src/app.py", output);
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(position(&diagnostics[0]), (11, 11));
+        assert_eq!(diagnostics[0].severity, PicklsSeverity::Error);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("return-value"));
+        assert_eq!(
+            diagnostics[0].message,
+            "Incompatible return value type (got \"int\", expected \"str\")"
+        );
+        assert_eq!(position(&diagnostics[1]), (19, 0));
+        assert_eq!(diagnostics[1].severity, PicklsSeverity::Information);
+        assert_eq!(diagnostics[1].code, None);
+        assert_eq!(diagnostics[2].severity, PicklsSeverity::Warning);
+        assert_eq!(diagnostics[2].code.as_deref(), Some("unused-ignore"));
+    }
+
+    #[test]
+    fn test_eslint_preset() {
+        let output = "\
+/repo/src/app.js:1:7: 'x' is assigned a value but never used. [Error/no-unused-vars]
+/repo/src/app.js:3:1: Unexpected console statement. [Warning/no-console]
+/repo/src/app.js:5:10: Parsing error: Unexpected token ) [Error]
+
+3 problems
+";
+        let diagnostics = parse_preset("eslint", "src/app.js", output);
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(position(&diagnostics[0]), (0, 6));
+        assert_eq!(diagnostics[0].severity, PicklsSeverity::Error);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("no-unused-vars"));
+        assert_eq!(
+            diagnostics[0].message,
+            "'x' is assigned a value but never used."
+        );
+        assert_eq!(diagnostics[1].severity, PicklsSeverity::Warning);
+        assert_eq!(diagnostics[1].code.as_deref(), Some("no-console"));
+        assert_eq!(diagnostics[2].code, None);
+        assert_eq!(diagnostics[2].message, "Parsing error: Unexpected token )");
+    }
+
+    #[test]
+    fn test_shellcheck_preset() {
+        let output = "\
+-:3:6: warning: Quote this to prevent word splitting. [SC2046]
+-:5:1: error: Couldn't parse this if expression. [SC1073]
+-:7:3: note: Double quote to prevent globbing and word splitting. [SC2086]
+-:9:1: style: Use $(...) notation instead of legacy backticks `...`. [SC2006]
+";
+        let diagnostics = parse_preset("shellcheck", "run.sh", output);
+        let severities: Vec<PicklsSeverity> = diagnostics.iter().map(|d| d.severity).collect();
+        assert_eq!(
+            severities,
+            [
+                PicklsSeverity::Warning,
+                PicklsSeverity::Error,
+                PicklsSeverity::Information,
+                PicklsSeverity::Hint,
+            ]
+        );
+        assert_eq!(position(&diagnostics[0]), (2, 5));
+        assert_eq!(diagnostics[0].file, Path::new("/repo/run.sh"));
+        assert_eq!(diagnostics[0].code.as_deref(), Some("SC2046"));
+        assert_eq!(
+            diagnostics[0].code_url.as_deref(),
+            Some("https://www.shellcheck.net/wiki/SC2046")
+        );
+        assert_eq!(
+            diagnostics[0].message,
+            "Quote this to prevent word splitting."
+        );
+    }
+
+    #[test]
+    fn test_golangci_lint_preset() {
+        let output = "\
+main.go:10:2: ineffectual assignment to err (ineffassign)
+main.go:14: File is not `gofmt`-ed with `-s` (gofmt)
+util.go:3:1: exported function Foo should have comment or be unexported (revive)
+";
+        let diagnostics = parse_preset("golangci-lint", "main.go", output);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(position(&diagnostics[0]), (9, 1));
+        assert_eq!(diagnostics[0].code.as_deref(), Some("ineffassign"));
+        assert_eq!(diagnostics[0].message, "ineffectual assignment to err");
+        assert_eq!(position(&diagnostics[1]), (13, 0));
+        assert_eq!(diagnostics[1].code.as_deref(), Some("gofmt"));
+    }
+
+    #[test]
+    fn test_preset_in_formatter_diagnostics() {
+        let language = language(json!({
+            "formatters": [{
+                "program": "ruff",
+                "args": ["format", "-"],
+                "diagnostics": {"preset": "ruff", "code_match": null},
+                "fallbacks": [{
+                    "program": "black",
+                    "diagnostics": {"preset": "mypy"},
+                }],
+            }],
+        }))
+        .expect("valid language config");
+        let formatter = &language.formatters[0];
+        let diagnostics = formatter.diagnostics.as_ref().unwrap();
+        let ruff = PicklsLinterConfig::preset("ruff").unwrap();
+        assert_eq!(diagnostics.pattern, ruff.pattern);
+        assert!(diagnostics.code_match.is_none());
+        let fallback_diagnostics = formatter.fallbacks[0].diagnostics.as_ref().unwrap();
+        assert_eq!(
+            fallback_diagnostics.pattern,
+            PicklsLinterConfig::preset("mypy").unwrap().pattern
+        );
+    }
+
+    #[test]
+    fn test_unknown_preset_in_formatter_diagnostics() {
+        let error = language(json!({
+            "formatters": [{"program": "black", "diagnostics": {"preset": "nope"}}],
+        }))
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("diagnostics: unknown linter preset 'nope'"));
+    }
+
+    #[test]
+    fn test_formatter_preset_is_rejected() {
+        for formatter in [
+            json!({"preset": "ruff"}),
+            json!({"program": "ruff", "fallbacks": [{"preset": "black"}]}),
+        ] {
+            let error = language(json!({"formatters": [formatter]})).unwrap_err();
+            assert!(
+                error
+                    .to_string()
+                    .contains("formatters do not support preset"),
+                "{error}"
+            );
+        }
+    }
+}
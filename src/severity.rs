@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// The severity of a diagnostic, mirroring LSP's `DiagnosticSeverity`.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PicklsSeverity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+impl PicklsSeverity {
+    /// Interpret a severity as commonly printed by linters, (ie: "error", "WARN", "note" or "e").
+    /// Matching is case-insensitive. Returns None for unrecognized severities.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "error" | "err" | "e" | "fatal" => Some(PicklsSeverity::Error),
+            "warning" | "warn" | "w" => Some(PicklsSeverity::Warning),
+            "information" | "info" | "i" | "note" | "n" => Some(PicklsSeverity::Information),
+            "hint" | "h" => Some(PicklsSeverity::Hint),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::path::Path;
+
+/// The `$variables` that may be used in linter and formatter arguments (and in the other fields
+/// that document substitutions):
+///
+/// - `$filename`: the absolute path to the real file.
+/// - `$tmpfilename`: the path to a temp file holding the buffer contents.
+/// - `$rootdir`: the root directory found via `root_markers`.
+/// - `$dirname`: the directory containing the real file.
+/// - `$basename`: the final component of the real file's path, (ie: "main.rs").
+/// - `$ext`: the extension of the real file without its leading dot, (ie: "rs").
+/// - `$startline` and `$endline`: the 1-based, inclusive lines of the range being formatted, only
+///   in a formatter's `range_args`.
+///
+/// Linters and formatters share these variables, and both report unknown ones via
+/// [`crate::PicklsConfig::warnings`]. There is no `$abspath`: `$filename` is already absolute.
+pub const SUBSTITUTION_VARIABLES: &[&str] = &[
+    "filename",
+    "tmpfilename",
+    "rootdir",
+    "dirname",
+    "basename",
+    "ext",
+    "startline",
+    "endline",
+];
+
+/// Values for the `$variables` listed in [`SUBSTITUTION_VARIABLES`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PicklsSubstitutions {
+    pub filename: Option<String>,
+    pub tmpfilename: Option<String>,
+    pub rootdir: Option<String>,
+    pub dirname: Option<String>,
+    pub basename: Option<String>,
+    pub ext: Option<String>,
+    pub startline: Option<String>,
+    pub endline: Option<String>,
+}
+
+impl PicklsSubstitutions {
+    /// Derive the substitutions for linting or formatting `filename` within `rootdir`.
+    pub fn new(filename: &Path, rootdir: &Path) -> Self {
+        let to_string = |path: &Path| path.to_string_lossy().into_owned();
+        PicklsSubstitutions {
+            filename: Some(to_string(filename)),
+            tmpfilename: None,
+            rootdir: Some(to_string(rootdir)),
+            dirname: filename.parent().map(to_string),
+            basename: filename.file_name().map(|name| to_string(Path::new(name))),
+            ext: filename.extension().map(|ext| to_string(Path::new(ext))),
+            startline: None,
+            endline: None,
+        }
+    }
+
+    /// Set the path substituted for `$tmpfilename`.
+    pub fn with_tmpfilename(mut self, tmpfilename: &Path) -> Self {
+        self.tmpfilename = Some(tmpfilename.to_string_lossy().into_owned());
+        self
+    }
+
+    /// Set the lines substituted for `$startline` and `$endline`, which are 1-based and inclusive.
+    pub fn with_range(mut self, startline: u32, endline: u32) -> Self {
+        self.startline = Some(startline.to_string());
+        self.endline = Some(endline.to_string());
+        self
+    }
+
+    fn value(&self, variable: &str) -> Option<&str> {
+        match variable {
+            "filename" => self.filename.as_deref(),
+            "tmpfilename" => self.tmpfilename.as_deref(),
+            "rootdir" => self.rootdir.as_deref(),
+            "dirname" => self.dirname.as_deref(),
+            "basename" => self.basename.as_deref(),
+            "ext" => self.ext.as_deref(),
+            "startline" => self.startline.as_deref(),
+            "endline" => self.endline.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Replace each known `$variable` in `template` with its value. Unknown variables, and known
+    /// variables without a value, are left untouched. A variable name runs until the first
+    /// character that is not alphanumeric or "_", so "$filename.bak" substitutes "$filename" while
+    /// "$extension" is an unknown variable rather than "$ext" followed by "ension".
+    pub fn apply(&self, template: &str) -> String {
+        let mut result = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('$') {
+            result.push_str(&rest[..start]);
+            rest = &rest[start..];
+            match known_variable(&rest[1..]).and_then(|name| Some((name, self.value(name)?))) {
+                Some((name, value)) => {
+                    result.push_str(value);
+                    rest = &rest[1 + name.len()..];
+                }
+                None => {
+                    result.push('$');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+}
+
+/// The `$variables` used in `template` that are not in [`SUBSTITUTION_VARIABLES`].
+pub fn unknown_variables(template: &str) -> Vec<&str> {
+    template
+        .match_indices('$')
+        .filter_map(|(start, _)| {
+            let name = identifier(&template[start + 1..]);
+            (!name.is_empty() && known_variable(name).is_none())
+                .then(|| &template[start..start + 1 + name.len()])
+        })
+        .collect()
+}
+
+/// The variables from [`SUBSTITUTION_VARIABLES`] used in `template`, without their leading `$`.
+pub fn substituted_variables(template: &str) -> Vec<&'static str> {
+    template
+        .match_indices('$')
+        .filter_map(|(start, _)| known_variable(&template[start + 1..]))
+        .collect()
+}
+
+/// The known variable whose name is the identifier that `text` starts with.
+fn known_variable(text: &str) -> Option<&'static str> {
+    let name = identifier(text);
+    SUBSTITUTION_VARIABLES
+        .iter()
+        .copied()
+        .find(|variable| *variable == name)
+}
+
+/// The leading run of ASCII alphanumerics and underscores in `text`.
+fn identifier(text: &str) -> &str {
+    let end = text
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    &text[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_variables_compare_whole_names() {
+        assert_eq!(
+            unknown_variables("$extension $filenames $dirname2 $filename.bak $ext"),
+            ["$extension", "$filenames", "$dirname2"]
+        );
+        assert!(unknown_variables("$ $1").contains(&"$1"));
+    }
+
+    #[test]
+    fn test_apply_requires_a_name_boundary() {
+        let substitutions =
+            PicklsSubstitutions::new(Path::new("/repo/src/main.rs"), Path::new("/repo"));
+        assert_eq!(
+            substitutions.apply("$filename.bak $extension $ext $dirname2 $basename"),
+            "/repo/src/main.rs.bak $extension rs $dirname2 main.rs"
+        );
+        assert_eq!(
+            substituted_variables("$ext $extension $rootdir/x"),
+            ["ext", "rootdir"]
+        );
+    }
+}
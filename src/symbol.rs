@@ -0,0 +1,770 @@
+use crate::{PicklsConfigError, PicklsRegexSymbolConfig, PicklsSymbolKind, PicklsSymbolsConfig};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufRead, BufReader, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+/// The extension fields of a tags file that name the scope of a tag, (ie: "class:Parser").
+const TAG_SCOPE_FIELDS: &[&str] = &[
+    "class",
+    "enum",
+    "function",
+    "implementation",
+    "interface",
+    "module",
+    "namespace",
+    "struct",
+    "union",
+];
+
+/// A symbol found by a symbols source, (ie: a function definition).
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+pub struct PicklsSymbol {
+    pub name: String,
+    pub file: PathBuf,
+    /// The zero-based line of the symbol's definition.
+    pub line: u32,
+    /// The kind of the symbol, when the source reports one.
+    pub kind: Option<PicklsSymbolKind>,
+    /// The name of the symbol that contains this one, (ie: its class), when known.
+    pub container: Option<String>,
+}
+
+/// Parse the cross-reference output of GNU Global (`global -x` or `global -fx`), in which each
+/// line holds a symbol's name, its 1-based line number, its path and the text of that line,
+/// separated by whitespace:
+///
+/// ```text
+/// main              12 src/main.c       int main(int argc, char **argv)
+/// ```
+///
+/// Relative paths are resolved against `root_dir`, where `global` is run. Lines that do not have
+/// this shape are skipped. Global does not report kinds, so `kind` is always None.
+pub fn parse_global_output(output: &str, root_dir: &Path) -> Vec<PicklsSymbol> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?;
+            let line = fields.next()?.parse::<u32>().ok()?.checked_sub(1)?;
+            let file = fields.next()?;
+            Some(PicklsSymbol {
+                name: name.to_string(),
+                file: root_dir.join(file),
+                line,
+                kind: None,
+                container: None,
+            })
+        })
+        .collect()
+}
+
+/// A validated entry of the "regex" symbols source with its `pattern` compiled. See
+/// [`PicklsRegexSymbolConfig::compile`].
+#[derive(Clone, Debug)]
+pub struct PicklsCompiledRegexSymbol {
+    pub config: PicklsRegexSymbolConfig,
+    pub regex: Regex,
+}
+
+impl PicklsRegexSymbolConfig {
+    /// Compile `pattern` and check that `name_match` and `container_match` refer to its groups.
+    pub fn compile(&self) -> Result<PicklsCompiledRegexSymbol, PicklsConfigError> {
+        let regex = Regex::new(&self.pattern)
+            .map_err(|error| PicklsConfigError::new("pattern", error.to_string()))?;
+        self.name_match.validate("name_match", &regex)?;
+        if let Some(container_match) = &self.container_match {
+            container_match.validate("container_match", &regex)?;
+        }
+        Ok(PicklsCompiledRegexSymbol {
+            config: self.clone(),
+            regex,
+        })
+    }
+}
+
+/// Find the symbols of `text`, the contents of `file`, by matching each of `entries` against
+/// each of its lines. A line may produce a symbol for each entry that matches it, in order.
+/// Matches whose `name_match` group did not participate are skipped.
+pub fn find_regex_symbols(
+    entries: &[PicklsCompiledRegexSymbol],
+    file: &Path,
+    text: &str,
+) -> Vec<PicklsSymbol> {
+    let mut symbols = Vec::new();
+    for (line_number, line) in text.lines().enumerate() {
+        for entry in entries {
+            let Some(caps) = entry.regex.captures(line) else {
+                continue;
+            };
+            let Some(name) = entry.config.name_match.get(&caps) else {
+                continue;
+            };
+            let container = entry
+                .config
+                .container_match
+                .as_ref()
+                .and_then(|group| group.get(&caps))
+                .map(|container| container.as_str().to_string());
+            symbols.push(PicklsSymbol {
+                name: name.as_str().to_string(),
+                file: file.to_path_buf(),
+                line: line_number as u32,
+                kind: Some(entry.config.kind),
+                container,
+            });
+        }
+    }
+    symbols
+}
+
+impl PicklsSymbolsConfig {
+    /// Parse a line of a tags file in `tags_dir`, in either the classic format (`name<TAB>file<TAB>
+    /// address`) or the extended format, whose address is followed by `;"` and extension fields
+    /// such as `kind:function`, `line:12`, `language:Python` and `class:Parser` (or, with the "Z"
+    /// field, `scope:class:Parser`). Relative files are resolved against `tags_dir`. The line
+    /// comes from the `line` field or else a numeric address; tags with only a search pattern as
+    /// their address are placed at line 0, so tags files should be generated with `--fields=+n`
+    /// or `--excmd=number`. Kinds are mapped per [`PicklsSymbolsConfig::symbol_kind`]. A trailing
+    /// "\r" or "\n" is ignored. Returns None for `!_TAG_` headers and malformed lines.
+    pub fn parse_tags_line(&self, line: &str, tags_dir: &Path) -> Option<PicklsSymbol> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.starts_with("!_TAG_") {
+            return None;
+        }
+        let mut fields = line.splitn(3, '\t');
+        let name = fields.next().filter(|name| !name.is_empty())?;
+        let file = fields.next()?;
+        let rest = fields.next()?;
+        let (address, extension_fields) = match rest.rsplit_once(";\"") {
+            Some((address, extension_fields)) => (address, extension_fields),
+            None => (rest, ""),
+        };
+        let mut line_number = address.parse::<u32>().ok();
+        let (mut kind, mut language, mut container) = (None, None, None);
+        for field in extension_fields
+            .split('\t')
+            .filter(|field| !field.is_empty())
+        {
+            match field.split_once(':') {
+                Some(("kind", value)) => kind = Some(value),
+                Some(("line", value)) => line_number = value.parse().ok().or(line_number),
+                Some(("language", value)) => language = Some(value),
+                Some(("scope", value)) => {
+                    container = Some(value.split_once(':').map_or(value, |(_, scope)| scope));
+                }
+                Some((key, value)) if TAG_SCOPE_FIELDS.contains(&key) => container = Some(value),
+                Some(_) => {}
+                None => kind = Some(field),
+            }
+        }
+        Some(PicklsSymbol {
+            name: name.to_string(),
+            file: tags_dir.join(file),
+            line: line_number.unwrap_or(1).saturating_sub(1),
+            kind: kind.map(|kind| self.symbol_kind(language, kind)),
+            container: container.map(str::to_string),
+        })
+    }
+
+    /// Parse a line of ctags JSON output (`--output-format=json`), (ie: `{"_type": "tag", "name":
+    /// "parse", "path": "p.py", "line": 34, "kind": "member", "scope": "Parser", "scopeKind":
+    /// "class", "language": "Python"}`). Relative paths are resolved against `root_dir`. Returns
+    /// None for pseudo-tags and malformed lines.
+    pub fn parse_ctags_json_line(&self, line: &str, root_dir: &Path) -> Option<PicklsSymbol> {
+        let tag: Value = serde_json::from_str(line).ok()?;
+        if tag.get("_type").and_then(Value::as_str) != Some("tag") {
+            return None;
+        }
+        let field = |name: &str| tag.get(name).and_then(Value::as_str);
+        let line = tag.get("line").and_then(Value::as_u64).unwrap_or(1);
+        Some(PicklsSymbol {
+            name: field("name")?.to_string(),
+            file: root_dir.join(field("path")?),
+            line: u32::try_from(line).ok()?.saturating_sub(1),
+            kind: field("kind").map(|kind| self.symbol_kind(field("language"), kind)),
+            container: field("scope").map(str::to_string),
+        })
+    }
+}
+
+/// The lines of the tags file at `path` whose tag names start with `prefix`, up to `max_results`
+/// of them, without their line endings (either "\n" or "\r\n"). When the file's
+/// `!_TAG_FILE_SORTED` header says it is sorted (ie: "1"), the first match is found by binary
+/// search, so that large tags files are never read in full. Otherwise, including for files sorted
+/// case-insensitively ("2"), the whole file is scanned.
+pub fn find_tags_with_prefix(
+    path: &Path,
+    prefix: &str,
+    max_results: usize,
+) -> io::Result<Vec<String>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut sorted = false;
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 && line.starts_with("!_TAG_") {
+        if let Some(value) = line.strip_prefix("!_TAG_FILE_SORTED\t") {
+            sorted = value.starts_with('1');
+        }
+        line.clear();
+    }
+    let start = match sorted {
+        true => first_line_not_before(&mut reader, prefix)?,
+        false => 0,
+    };
+    reader.seek(SeekFrom::Start(start))?;
+    let mut tags = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if tags.len() >= max_results {
+            break;
+        }
+        if line.starts_with("!_TAG_") {
+            continue;
+        }
+        if line.starts_with(prefix) {
+            tags.push(line.trim_end_matches('\r').to_string());
+        } else if sorted && tag_name(&line) > prefix {
+            break;
+        }
+    }
+    Ok(tags)
+}
+
+/// The offset of the first line of the sorted tags file behind `reader` whose tag name is not
+/// less than `prefix`.
+fn first_line_not_before(reader: &mut BufReader<File>, prefix: &str) -> io::Result<u64> {
+    let (mut low, mut high) = (0, reader.get_ref().metadata()?.len());
+    while low < high {
+        let middle = low + (high - low) / 2;
+        match line_at_or_after(reader, middle)? {
+            Some((start, line)) if tag_name(&line) < prefix => {
+                low = start + line.len() as u64;
+            }
+            _ => high = middle,
+        }
+    }
+    Ok(low)
+}
+
+/// The first line that starts at or after `offset`, with its offset, including its newline.
+fn line_at_or_after(
+    reader: &mut BufReader<File>,
+    offset: u64,
+) -> io::Result<Option<(u64, String)>> {
+    let mut start = offset;
+    if offset > 0 {
+        reader.seek(SeekFrom::Start(offset - 1))?;
+        let mut partial = Vec::new();
+        start = offset - 1 + reader.read_until(b'\n', &mut partial)? as u64;
+    } else {
+        reader.seek(SeekFrom::Start(0))?;
+    }
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some((start, String::from_utf8_lossy(&line).into_owned())))
+}
+
+fn tag_name(line: &str) -> &str {
+    line.split('\t')
+        .next()
+        .unwrap_or(line)
+        .trim_end_matches(['\r', '\n'])
+}
+
+/// A symbol with the symbols nested within it, (ie: a class and its methods). See
+/// [`nest_symbols`].
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+pub struct PicklsDocumentSymbol {
+    pub symbol: PicklsSymbol,
+    pub children: Vec<PicklsDocumentSymbol>,
+}
+
+/// Nest the symbols of a single document by resolving each symbol's `container`, a scope chain
+/// such as "Outer.Inner" or "outer::Inner", to its parent: the symbol named by the last element
+/// of the chain whose own `container` is the rest of it. When several symbols qualify, (ie: a
+/// Rust struct and its impl block), the nearest one at or before the child's line is chosen.
+/// Symbols whose parent is not found are kept at the top level. Siblings are ordered by line.
+pub fn nest_symbols(symbols: Vec<PicklsSymbol>) -> Vec<PicklsDocumentSymbol> {
+    let mut by_scope: HashMap<(&str, Option<&str>), Vec<usize>> = HashMap::new();
+    for (index, symbol) in symbols.iter().enumerate() {
+        by_scope
+            .entry((symbol.name.as_str(), symbol.container.as_deref()))
+            .or_default()
+            .push(index);
+    }
+    let mut parents: Vec<Option<usize>> = symbols
+        .iter()
+        .enumerate()
+        .map(|(index, symbol)| {
+            let (scope, name) = split_scope(symbol.container.as_deref()?);
+            let candidates = by_scope.get(&(name, scope))?;
+            let candidates = candidates.iter().copied().filter(|&other| other != index);
+            let preceding = candidates
+                .clone()
+                .filter(|&other| symbols[other].line <= symbol.line)
+                .max_by_key(|&other| symbols[other].line);
+            preceding.or_else(|| candidates.min_by_key(|&other| symbols[other].line))
+        })
+        .collect();
+    // Break any cycles, so that every symbol is reachable from the top level.
+    for index in 0..symbols.len() {
+        let mut ancestor = parents[index];
+        let mut steps = 0;
+        while let Some(current) = ancestor {
+            if current == index || steps > symbols.len() {
+                parents[index] = None;
+                break;
+            }
+            ancestor = parents[current];
+            steps += 1;
+        }
+    }
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); symbols.len()];
+    let mut roots = Vec::new();
+    for (index, parent) in parents.iter().enumerate() {
+        match parent {
+            Some(parent) => children[*parent].push(index),
+            None => roots.push(index),
+        }
+    }
+    let mut symbols: Vec<Option<PicklsSymbol>> = symbols.into_iter().map(Some).collect();
+    build_document_symbols(&roots, &children, &mut symbols)
+}
+
+fn build_document_symbols(
+    indexes: &[usize],
+    children: &[Vec<usize>],
+    symbols: &mut [Option<PicklsSymbol>],
+) -> Vec<PicklsDocumentSymbol> {
+    let mut document_symbols: Vec<PicklsDocumentSymbol> = indexes
+        .iter()
+        .filter_map(|&index| {
+            let symbol = symbols[index].take()?;
+            Some(PicklsDocumentSymbol {
+                symbol,
+                children: build_document_symbols(&children[index], children, symbols),
+            })
+        })
+        .collect();
+    document_symbols.sort_by_key(|document_symbol| document_symbol.symbol.line);
+    document_symbols
+}
+
+/// Split a scope chain into the scope of its last element and that element's name, (ie:
+/// "a::B.c" into "a::B" and "c").
+fn split_scope(scope: &str) -> (Option<&str>, &str) {
+    let dot = scope.rfind('.').map(|index| (index, 1));
+    let colons = scope.rfind("::").map(|index| (index, 2));
+    match dot.max(colons) {
+        Some((index, len)) => (Some(&scope[..index]), &scope[index + len..]),
+        None => (None, scope),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+
+    /// A tags file holding `contents` under the system temp directory, unique to `name` and this
+    /// process.
+    fn tags_file(name: &str, contents: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pickls-config-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("scratch dir is created");
+        let path = dir.join("tags");
+        fs::write(&path, contents).expect("tags file is written");
+        path
+    }
+
+    /// The contents of a tags file whose `!_TAG_FILE_SORTED` header is `sorted`, holding a tag for
+    /// each of `names` in order.
+    fn tags_contents(sorted: &str, names: &[&str], newline: &str) -> String {
+        let mut contents = format!(
+            "!_TAG_FILE_FORMAT\t2\t/extended format/{newline}\
+             !_TAG_FILE_SORTED\t{sorted}\t/0=unsorted, 1=sorted, 2=foldcase/{newline}"
+        );
+        for (index, name) in names.iter().enumerate() {
+            contents.push_str(&format!(
+                "{name}\tsrc/lib.rs\t{};\"\tkind:function{newline}",
+                index + 1
+            ));
+        }
+        contents
+    }
+
+    /// Many tag names in byte order, so that the binary search takes several steps.
+    fn sorted_names() -> Vec<String> {
+        let mut names: Vec<String> = (0..500).map(|index| format!("tag{index:04}")).collect();
+        names.extend(["Alpha", "Beta", "alpha", "alphabet", "beta", "zeta"].map(String::from));
+        names.sort();
+        names
+    }
+
+    fn tag_names(tags: &[String]) -> Vec<&str> {
+        tags.iter().map(|tag| tag_name(tag)).collect()
+    }
+
+    fn ctags_symbols(output: &str) -> Vec<PicklsSymbol> {
+        let config: PicklsSymbolsConfig =
+            serde_json::from_value(json!({"source": "universal-ctags", "hierarchical": true}))
+                .expect("valid symbols config");
+        output
+            .lines()
+            .filter_map(|line| config.parse_ctags_json_line(line, Path::new("/repo")))
+            .collect()
+    }
+
+    /// The names of `symbols` and their descendants, indented by depth.
+    fn outline(symbols: &[PicklsDocumentSymbol]) -> Vec<String> {
+        fn walk(symbols: &[PicklsDocumentSymbol], depth: usize, outline: &mut Vec<String>) {
+            for symbol in symbols {
+                let line = symbol.symbol.line + 1;
+                outline.push(format!(
+                    "{}{}:{line}",
+                    "  ".repeat(depth),
+                    symbol.symbol.name
+                ));
+                walk(&symbol.children, depth + 1, outline);
+            }
+        }
+        let mut lines = Vec::new();
+        walk(symbols, 0, &mut lines);
+        lines
+    }
+
+    /// `ctags --output-format=json --fields=+nKsZ -f - parser.py` for:
+    ///
+    /// ```python
+    /// class Parser:
+    ///     def __init__(self, text):
+    ///         self.text = text
+    ///
+    ///     def parse(self):
+    ///         return Token(self.text)
+    ///
+    ///     class Token:
+    ///         def __init__(self, text):
+    ///             self.text = text
+    ///
+    /// def main():
+    ///     Parser("x").parse()
+    /// ```
+    const PYTHON_CTAGS_JSON: &str = r#"{"_type": "ptag", "name": "JSON_OUTPUT_VERSION", "path": "0.0", "pattern": "in development"}
+{"_type": "ptag", "name": "TAG_PROGRAM_NAME", "path": "Universal Ctags", "pattern": "Derived from Exuberant Ctags"}
+{"_type": "tag", "name": "Parser", "path": "parser.py", "pattern": "/^class Parser:$/", "language": "Python", "line": 1, "kind": "class"}
+{"_type": "tag", "name": "__init__", "path": "parser.py", "pattern": "/^    def __init__(self, text):$/", "language": "Python", "line": 2, "kind": "member", "scope": "Parser", "scopeKind": "class"}
+{"_type": "tag", "name": "parse", "path": "parser.py", "pattern": "/^    def parse(self):$/", "language": "Python", "line": 5, "kind": "member", "scope": "Parser", "scopeKind": "class"}
+{"_type": "tag", "name": "Token", "path": "parser.py", "pattern": "/^    class Token:$/", "language": "Python", "line": 8, "kind": "class", "scope": "Parser", "scopeKind": "class"}
+{"_type": "tag", "name": "__init__", "path": "parser.py", "pattern": "/^        def __init__(self, text):$/", "language": "Python", "line": 9, "kind": "member", "scope": "Parser.Token", "scopeKind": "class"}
+{"_type": "tag", "name": "main", "path": "parser.py", "pattern": "/^def main():$/", "language": "Python", "line": 12, "kind": "function"}
+"#;
+
+    /// `ctags --output-format=json --fields=+nKsZ -f - point.rs` for:
+    ///
+    /// ```rust
+    /// struct Point {
+    ///     x: f64,
+    /// }
+    ///
+    /// impl Point {
+    ///     fn new(x: f64) -> Self {
+    ///         Point { x }
+    ///     }
+    /// }
+    ///
+    /// impl fmt::Display for Point {
+    ///     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    ///         write!(f, "{}", self.x)
+    ///     }
+    /// }
+    /// ```
+    const RUST_CTAGS_JSON: &str = r#"{"_type": "tag", "name": "Point", "path": "point.rs", "pattern": "/^struct Point {$/", "language": "Rust", "line": 1, "kind": "struct"}
+{"_type": "tag", "name": "x", "path": "point.rs", "pattern": "/^    x: f64,$/", "language": "Rust", "line": 2, "kind": "field", "scope": "Point", "scopeKind": "struct"}
+{"_type": "tag", "name": "Point", "path": "point.rs", "pattern": "/^impl Point {$/", "language": "Rust", "line": 5, "kind": "implementation"}
+{"_type": "tag", "name": "new", "path": "point.rs", "pattern": "/^    fn new(x: f64) -> Self {$/", "language": "Rust", "line": 6, "kind": "method", "scope": "Point", "scopeKind": "implementation"}
+{"_type": "tag", "name": "Point", "path": "point.rs", "pattern": "/^impl fmt::Display for Point {$/", "language": "Rust", "line": 11, "kind": "implementation"}
+{"_type": "tag", "name": "fmt", "path": "point.rs", "pattern": "/^    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {$/", "language": "Rust", "line": 12, "kind": "method", "scope": "Point", "scopeKind": "implementation"}
+"#;
+
+    #[test]
+    fn test_parse_ctags_json_line() {
+        let symbols = ctags_symbols(PYTHON_CTAGS_JSON);
+        assert_eq!(symbols.len(), 6, "pseudo-tags are skipped");
+        assert_eq!(
+            symbols[1],
+            PicklsSymbol {
+                name: "__init__".to_string(),
+                file: PathBuf::from("/repo/parser.py"),
+                line: 1,
+                kind: Some(PicklsSymbolKind::Method),
+                container: Some("Parser".to_string()),
+            }
+        );
+        assert_eq!(symbols[0].kind, Some(PicklsSymbolKind::Class));
+        assert_eq!(symbols[4].container.as_deref(), Some("Parser.Token"));
+    }
+
+    #[test]
+    fn test_nest_python_class() {
+        assert_eq!(
+            outline(&nest_symbols(ctags_symbols(PYTHON_CTAGS_JSON))),
+            [
+                "Parser:1",
+                "  __init__:2",
+                "  parse:5",
+                "  Token:8",
+                "    __init__:9",
+                "main:12",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nest_rust_impl_blocks() {
+        assert_eq!(
+            outline(&nest_symbols(ctags_symbols(RUST_CTAGS_JSON))),
+            ["Point:1", "  x:2", "Point:5", "  new:6", "Point:11", "  fmt:12",]
+        );
+    }
+
+    #[test]
+    fn test_nest_orphans_at_top_level() {
+        let output = PYTHON_CTAGS_JSON
+            .lines()
+            .filter(|line| !line.contains(r#""name": "Parser""#))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(
+            outline(&nest_symbols(ctags_symbols(&output))),
+            [
+                "__init__:2",
+                "parse:5",
+                "Token:8",
+                "  __init__:9",
+                "main:12"
+            ]
+        );
+    }
+
+    /// `global -x parse`, followed by a warning about a stale index.
+    const GLOBAL_X_OUTPUT: &str = "\
+parse              42 src/parser/parser.cc   Node *Parser::parse(const Token &token) {
+parse             118 src/parser/parser.cc   Node *Parser::parse(std::istream &in) {
+parse               7 include/parser.h    Node *parse(std::istream &in);
+global: warning: index is older than 'src/lexer.cc'
+";
+
+    #[test]
+    fn test_parse_global_output() {
+        let symbols = parse_global_output(GLOBAL_X_OUTPUT, Path::new("/repo"));
+        let found: Vec<_> = symbols
+            .iter()
+            .map(|symbol| {
+                let file = symbol.file.to_str().expect("UTF-8 path");
+                (symbol.name.as_str(), file, symbol.line)
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("parse", "/repo/src/parser/parser.cc", 41),
+                ("parse", "/repo/src/parser/parser.cc", 117),
+                ("parse", "/repo/include/parser.h", 6),
+            ]
+        );
+        assert!(symbols
+            .iter()
+            .all(|symbol| symbol.kind.is_none() && symbol.container.is_none()));
+    }
+
+    #[test]
+    fn test_parse_global_file_output() {
+        // `global -fx src/main.c`, with an absolute path as printed under `--absolute`.
+        let output = "\
+Config             10 /repo/src/main.c  struct Config {
+load_config        24 /repo/src/main.c  static int load_config(struct Config *config)
+main               51 /repo/src/main.c  int main(int argc, char **argv)
+";
+        let symbols = parse_global_output(output, Path::new("/elsewhere"));
+        let names: Vec<_> = symbols
+            .iter()
+            .map(|symbol| (symbol.name.as_str(), symbol.line))
+            .collect();
+        assert_eq!(names, [("Config", 9), ("load_config", 23), ("main", 50)]);
+        assert!(symbols
+            .iter()
+            .all(|symbol| symbol.file == Path::new("/repo/src/main.c")));
+        assert!(parse_global_output("", Path::new("/repo")).is_empty());
+        assert!(parse_global_output("main 0 src/main.c\nmain\n", Path::new("/repo")).is_empty());
+    }
+
+    #[test]
+    fn test_parse_tags_line() {
+        let config: PicklsSymbolsConfig =
+            serde_json::from_value(json!({"source": "tags-file"})).expect("valid symbols config");
+        let parse = |line: &str| config.parse_tags_line(line, Path::new("/repo"));
+        // The classic format has no extension fields.
+        assert_eq!(
+            parse("main\tsrc/main.c\t51"),
+            Some(PicklsSymbol {
+                name: "main".to_string(),
+                file: PathBuf::from("/repo/src/main.c"),
+                line: 50,
+                kind: None,
+                container: None,
+            })
+        );
+        let symbol = parse("main\tsrc/main.c\t/^int main(int argc, char **argv)$/").expect("tag");
+        assert_eq!((symbol.line, symbol.kind), (0, None));
+        // The extended format, with the line in a field rather than the address.
+        assert_eq!(
+            parse(concat!(
+                "parse\tparser.py\t/^    def parse(self):$/;\"",
+                "\tkind:member\tline:34\tlanguage:Python\tclass:Parser",
+            )),
+            Some(PicklsSymbol {
+                name: "parse".to_string(),
+                file: PathBuf::from("/repo/parser.py"),
+                line: 33,
+                kind: Some(PicklsSymbolKind::Method),
+                container: Some("Parser".to_string()),
+            })
+        );
+        let symbol = parse("Inner\t/abs/outer.rs\t12;\"\tstruct\tscope:module:outer").expect("tag");
+        assert_eq!(symbol.file, Path::new("/abs/outer.rs"));
+        assert_eq!(symbol.line, 11);
+        assert_eq!(symbol.kind, Some(PicklsSymbolKind::Struct));
+        assert_eq!(symbol.container.as_deref(), Some("outer"));
+        // Line endings are ignored, from either platform.
+        for newline in ["\n", "\r\n"] {
+            let symbol = parse(&format!(
+                "parse\tp.py\t/^def parse():$/;\"\tkind:function\tline:3{newline}"
+            ))
+            .expect("tag");
+            assert_eq!(symbol.line, 2);
+            assert_eq!(symbol.kind, Some(PicklsSymbolKind::Function));
+        }
+        for line in [
+            "!_TAG_FILE_FORMAT\t2\t/extended format/",
+            "!_TAG_FILE_SORTED\t1\t/0=unsorted, 1=sorted, 2=foldcase/",
+            "",
+            "main",
+            "main\tsrc/main.c",
+            "\tsrc/main.c\t51",
+        ] {
+            assert_eq!(parse(line), None, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn test_find_sorted_tags_with_prefix() {
+        let names = sorted_names();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        for newline in ["\n", "\r\n"] {
+            let path = tags_file(
+                &format!("sorted-tags-{}", newline.len()),
+                &tags_contents("1", &names, newline),
+            );
+            let find = |prefix: &str, max_results: usize| {
+                find_tags_with_prefix(&path, prefix, max_results).expect("tags file is read")
+            };
+            let tags = find("tag012", 100);
+            assert_eq!(
+                tag_names(&tags),
+                (120..130)
+                    .map(|index| format!("tag{index:04}"))
+                    .collect::<Vec<_>>()
+            );
+            assert!(
+                tags.iter().all(|tag| !tag.ends_with(['\r', '\n'])),
+                "{tags:?}"
+            );
+            assert_eq!(tag_names(&find("alpha", 100)), ["alpha", "alphabet"]);
+            assert_eq!(tag_names(&find("Beta", 100)), ["Beta"]);
+            assert_eq!(tag_names(&find("zeta", 100)), ["zeta"]);
+            // Prefixes that sort before the first tag or after the last.
+            assert_eq!(tag_names(&find("Aardvark", 100)), Vec::<&str>::new());
+            assert_eq!(tag_names(&find("0", 100)), Vec::<&str>::new());
+            assert_eq!(tag_names(&find("zzz", 100)), Vec::<&str>::new());
+            assert_eq!(tag_names(&find("A", 100)), ["Alpha"]);
+            // At most `max_results`, taken in order.
+            assert_eq!(
+                tag_names(&find("tag", 3)),
+                ["tag0000", "tag0001", "tag0002"]
+            );
+            assert_eq!(tag_names(&find("", 2)), ["Alpha", "Beta"]);
+            assert!(find("tag", 0).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_find_unsorted_tags_with_prefix() {
+        // Unsorted and case-folded files are scanned in full, so every match is found in file
+        // order even though a byte-order binary search would miss some of them.
+        for (sorted, names) in [
+            ("0", ["zeta", "beta", "Beta2", "alpha", "beta3", "Alpha2"]),
+            ("2", ["alpha", "Alpha2", "beta", "Beta2", "beta3", "zeta"]),
+        ] {
+            let path = tags_file(
+                &format!("unsorted-tags-{sorted}"),
+                &tags_contents(sorted, &names, "\r\n"),
+            );
+            let find = |prefix: &str, max_results: usize| {
+                find_tags_with_prefix(&path, prefix, max_results).expect("tags file is read")
+            };
+            let betas: Vec<_> = names
+                .iter()
+                .copied()
+                .filter(|name| name.starts_with("beta"))
+                .collect();
+            assert_eq!(tag_names(&find("beta", 100)), betas, "{sorted}");
+            assert_eq!(tag_names(&find("Alpha", 100)), ["Alpha2"], "{sorted}");
+            assert_eq!(tag_names(&find("zeta", 100)), ["zeta"], "{sorted}");
+            assert!(find("omega", 100).is_empty(), "{sorted}");
+            assert_eq!(tag_names(&find("beta", 1)), &betas[..1], "{sorted}");
+            assert!(find("beta", 100)
+                .iter()
+                .all(|tag| !tag.ends_with(['\r', '\n'])));
+        }
+        let missing = std::env::temp_dir().join("pickls-config-missing-tags");
+        assert!(find_tags_with_prefix(&missing, "main", 10).is_err());
+    }
+
+    #[test]
+    fn test_first_line_not_before() {
+        let names = sorted_names();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        for newline in ["\n", "\r\n"] {
+            let contents = tags_contents("1", &names, newline);
+            let path = tags_file(&format!("first-line-{}", newline.len()), &contents);
+            let mut reader = BufReader::new(File::open(&path).expect("tags file is opened"));
+            let offset_of = |name: &str| {
+                contents
+                    .find(&format!("{newline}{name}\t"))
+                    .map(|offset| (offset + newline.len()) as u64)
+                    .expect("tag is in the file")
+            };
+            let mut first_line = |prefix: &str| {
+                first_line_not_before(&mut reader, prefix).expect("tags file is read")
+            };
+            // The headers sort before every tag name that starts with a letter.
+            assert_eq!(first_line("A"), offset_of("Alpha"));
+            assert_eq!(first_line("Alpha"), offset_of("Alpha"));
+            assert_eq!(first_line("Alphb"), offset_of("Beta"));
+            assert_eq!(first_line("tag0250"), offset_of("tag0250"));
+            assert_eq!(first_line("tag02501"), offset_of("tag0251"));
+            assert_eq!(first_line("tag9"), offset_of("zeta"));
+            assert_eq!(first_line("zzz"), contents.len() as u64);
+            assert_eq!(first_line("!"), 0);
+        }
+    }
+}
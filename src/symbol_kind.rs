@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+
+/// The kind of a symbol, mirroring LSP's `SymbolKind`.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PicklsSymbolKind {
+    File,
+    Module,
+    Namespace,
+    Package,
+    Class,
+    Method,
+    Property,
+    Field,
+    Constructor,
+    Enum,
+    Interface,
+    Function,
+    Variable,
+    Constant,
+    String,
+    Number,
+    Boolean,
+    Array,
+    Object,
+    Key,
+    Null,
+    EnumMember,
+    Struct,
+    Event,
+    Operator,
+    TypeParameter,
+}
+
+/// The built-in mapping from ctags kinds to symbol kinds, which `kind_map` in
+/// [`crate::PicklsSymbolsConfig`] overrides. Keys are either a kind (ie: "function") or a kind
+/// scoped to a language (ie: "python:member"); scoped keys take precedence.
+pub const CTAGS_KIND_MAP: &[(&str, PicklsSymbolKind)] = &[
+    ("class", PicklsSymbolKind::Class),
+    ("constant", PicklsSymbolKind::Constant),
+    ("enum", PicklsSymbolKind::Enum),
+    ("enumerator", PicklsSymbolKind::EnumMember),
+    ("field", PicklsSymbolKind::Field),
+    ("function", PicklsSymbolKind::Function),
+    ("interface", PicklsSymbolKind::Interface),
+    ("macro", PicklsSymbolKind::Constant),
+    ("member", PicklsSymbolKind::Field),
+    ("method", PicklsSymbolKind::Method),
+    ("module", PicklsSymbolKind::Module),
+    ("namespace", PicklsSymbolKind::Namespace),
+    ("package", PicklsSymbolKind::Package),
+    ("property", PicklsSymbolKind::Property),
+    ("struct", PicklsSymbolKind::Struct),
+    ("typedef", PicklsSymbolKind::TypeParameter),
+    ("union", PicklsSymbolKind::Struct),
+    ("variable", PicklsSymbolKind::Variable),
+    ("go:talias", PicklsSymbolKind::TypeParameter),
+    ("go:type", PicklsSymbolKind::Class),
+    ("python:member", PicklsSymbolKind::Method),
+    ("rust:implementation", PicklsSymbolKind::Class),
+    ("rust:typedef", PicklsSymbolKind::TypeParameter),
+];
+
+/// The symbol kind of the ctags `kind` in `language` (ie: "Python", matched case-insensitively)
+/// per [`CTAGS_KIND_MAP`], or None when it is not mapped.
+pub fn builtin_symbol_kind(language: Option<&str>, kind: &str) -> Option<PicklsSymbolKind> {
+    lookup_symbol_kind(CTAGS_KIND_MAP.iter().copied(), language, kind)
+}
+
+/// Look up `kind` in `entries`, preferring the key scoped to `language` over the bare kind.
+pub(crate) fn lookup_symbol_kind<'a>(
+    entries: impl Iterator<Item = (&'a str, PicklsSymbolKind)> + Clone,
+    language: Option<&str>,
+    kind: &str,
+) -> Option<PicklsSymbolKind> {
+    let find = |key: &str| {
+        entries
+            .clone()
+            .find(|(entry, _)| entry.eq_ignore_ascii_case(key))
+            .map(|(_, symbol_kind)| symbol_kind)
+    };
+    language
+        .and_then(|language| find(&format!("{language}:{kind}")))
+        .or_else(|| find(kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_symbol_kind() {
+        assert_eq!(
+            builtin_symbol_kind(None, "function"),
+            Some(PicklsSymbolKind::Function)
+        );
+        assert_eq!(
+            builtin_symbol_kind(Some("C"), "member"),
+            Some(PicklsSymbolKind::Field)
+        );
+        // Keys scoped to the language take precedence, whatever the case of the language.
+        for language in ["Python", "python"] {
+            assert_eq!(
+                builtin_symbol_kind(Some(language), "member"),
+                Some(PicklsSymbolKind::Method)
+            );
+        }
+        assert_eq!(
+            builtin_symbol_kind(Some("Go"), "talias"),
+            Some(PicklsSymbolKind::TypeParameter)
+        );
+        assert_eq!(
+            builtin_symbol_kind(Some("Go"), "type"),
+            Some(PicklsSymbolKind::Class)
+        );
+        // Scoped keys only apply to their language.
+        assert_eq!(builtin_symbol_kind(Some("C"), "talias"), None);
+        assert_eq!(builtin_symbol_kind(None, "talias"), None);
+        assert_eq!(builtin_symbol_kind(Some("Go"), "label"), None);
+    }
+}
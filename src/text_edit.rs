@@ -0,0 +1,496 @@
+use crate::{PicklsNewline, PicklsPosition, PicklsRange};
+use serde::Serialize;
+
+/// The most line comparisons to spend diffing before falling back to a single edit covering
+/// every changed line.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// A replacement of `range` within a document by `new_text`. Unlike diagnostics, the
+/// `character` of each position is measured in UTF-16 code units, as LSP expects by default.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize)]
+pub struct PicklsTextEdit {
+    pub range: PicklsRange,
+    pub new_text: String,
+}
+
+/// The edits that turn `original` into `formatted`, covering only the regions that changed. Lines
+/// are diffed first, and then each changed region is narrowed to the characters that differ.
+/// Edits are ordered and do not overlap, and never split a "\r\n". See `minimal_diff` in
+/// [`crate::PicklsLanguageConfig`].
+pub fn minimal_text_edits(original: &str, formatted: &str) -> Vec<PicklsTextEdit> {
+    let old_lines: Vec<&str> = original.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = formatted.split_inclusive('\n').collect();
+    let prefix = old_lines
+        .iter()
+        .zip(&new_lines)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old_lines[prefix..old_lines.len() - suffix];
+    let new_middle = &new_lines[prefix..new_lines.len() - suffix];
+    diff_hunks(old_middle, new_middle)
+        .into_iter()
+        .filter_map(|hunk| {
+            let old_start = prefix + hunk.old.start;
+            let old_text = old_lines[old_start..prefix + hunk.old.end].concat();
+            let new_text = new_lines[prefix + hunk.new.start..prefix + hunk.new.end].concat();
+            narrowed_edit(old_start as u32, &old_text, &new_text)
+        })
+        .collect()
+}
+
+/// Apply `edits`, as produced by [`minimal_text_edits`], to `text`. Edits must not overlap.
+/// Positions beyond the end of a line or of `text` are clamped.
+pub fn apply_text_edits(text: &str, edits: &[PicklsTextEdit]) -> String {
+    let mut edits: Vec<&PicklsTextEdit> = edits.iter().collect();
+    edits.sort_by_key(|edit| (edit.range.start.line, edit.range.start.character));
+    let mut result = String::with_capacity(text.len());
+    let mut offset = 0;
+    for edit in edits {
+        let start = byte_offset(text, edit.range.start).max(offset);
+        let end = byte_offset(text, edit.range.end).max(start);
+        result.push_str(&text[offset..start]);
+        result.push_str(&edit.new_text);
+        offset = end;
+    }
+    result.push_str(&text[offset..]);
+    result
+}
+
+/// Normalize the line endings of `formatted`, the output of formatting `original`, per `newline`
+/// and `ensure_final_newline`. With "preserve", line endings become "\r\n" when `original` has
+/// more of them than bare "\n", and "\n" otherwise. When `ensure_final_newline` is true, a
+/// non-empty result ends with a line ending; when false, trailing line endings are removed. Lone
+/// "\r" characters are left alone.
+pub fn normalize_newlines(
+    original: &str,
+    formatted: &str,
+    newline: PicklsNewline,
+    ensure_final_newline: Option<bool>,
+) -> String {
+    let line_ending = match newline {
+        PicklsNewline::Preserve => dominant_line_ending(original),
+        PicklsNewline::Lf => Some("\n"),
+        PicklsNewline::Crlf => Some("\r\n"),
+    };
+    let mut result = match line_ending {
+        Some(line_ending) => formatted.replace("\r\n", "\n").replace('\n', line_ending),
+        None => formatted.to_string(),
+    };
+    match ensure_final_newline {
+        Some(true) if !result.is_empty() && !result.ends_with('\n') => {
+            let line_ending = line_ending
+                .or_else(|| dominant_line_ending(&result))
+                .unwrap_or("\n");
+            result.push_str(line_ending);
+        }
+        Some(false) => {
+            while let Some(rest) = result
+                .strip_suffix("\r\n")
+                .or_else(|| result.strip_suffix('\n'))
+            {
+                result.truncate(rest.len());
+            }
+        }
+        _ => {}
+    }
+    result
+}
+
+/// The more common of "\r\n" and bare "\n" in `text`, or None when it has no line endings.
+fn dominant_line_ending(text: &str) -> Option<&'static str> {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    match (crlf, lf) {
+        (0, 0) => None,
+        (crlf, lf) if crlf > lf => Some("\r\n"),
+        _ => Some("\n"),
+    }
+}
+
+/// A run of changed lines: `old` is replaced by `new`, as ranges of line indexes.
+struct Hunk {
+    old: std::ops::Range<usize>,
+    new: std::ops::Range<usize>,
+}
+
+/// The changed runs of lines between `old` and `new`, via their longest common subsequence.
+fn diff_hunks(old: &[&str], new: &[&str]) -> Vec<Hunk> {
+    if old.is_empty() && new.is_empty() {
+        return Vec::new();
+    }
+    if old.len().saturating_mul(new.len()) > MAX_DIFF_CELLS {
+        return vec![Hunk {
+            old: 0..old.len(),
+            new: 0..new.len(),
+        }];
+    }
+    // lcs[i][j] is the length of the longest common subsequence of old[i..] and new[j..].
+    let width = new.len() + 1;
+    let mut lcs = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i * width + j] = if old[i] == new[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+    let mut hunks = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let mut start: Option<(usize, usize)> = None;
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            if let Some((old_start, new_start)) = start.take() {
+                hunks.push(Hunk {
+                    old: old_start..i,
+                    new: new_start..j,
+                });
+            }
+            i += 1;
+            j += 1;
+            continue;
+        }
+        start.get_or_insert((i, j));
+        if j < new.len() && (i == old.len() || lcs[i * width + j + 1] >= lcs[(i + 1) * width + j]) {
+            j += 1;
+        } else {
+            i += 1;
+        }
+    }
+    if let Some((old_start, new_start)) = start {
+        hunks.push(Hunk {
+            old: old_start..old.len(),
+            new: new_start..new.len(),
+        });
+    }
+    hunks
+}
+
+/// The edit replacing `old_text`, which begins at the start of line `line`, by `new_text`,
+/// trimmed to the characters that differ. None when they are identical.
+fn narrowed_edit(line: u32, old_text: &str, new_text: &str) -> Option<PicklsTextEdit> {
+    let mut prefix = common_prefix_len(old_text, new_text);
+    if old_text[..prefix].ends_with('\r') {
+        prefix -= 1;
+    }
+    let (old_rest, new_rest) = (&old_text[prefix..], &new_text[prefix..]);
+    let mut suffix = common_suffix_len(old_rest, new_rest);
+    if suffix > 0 && old_rest[old_rest.len() - suffix..].starts_with('\n') {
+        let before = &old_rest[..old_rest.len() - suffix];
+        if before.ends_with('\r') {
+            suffix -= 1;
+        }
+    }
+    let old_end = old_text.len() - suffix;
+    let new_end = new_text.len() - suffix;
+    if prefix == old_end && prefix == new_end {
+        return None;
+    }
+    Some(PicklsTextEdit {
+        range: PicklsRange {
+            start: position_after(line, &old_text[..prefix]),
+            end: position_after(line, &old_text[..old_end]),
+        },
+        new_text: new_text[prefix..new_end].to_string(),
+    })
+}
+
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, a), b)| a != b)
+        .map_or(a.len().min(b.len()), |((index, _), _)| index)
+}
+
+fn common_suffix_len(a: &str, b: &str) -> usize {
+    a.chars()
+        .rev()
+        .zip(b.chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum()
+}
+
+/// The position just past `text`, which begins at the start of line `line`.
+fn position_after(line: u32, text: &str) -> PicklsPosition {
+    let last = text.rfind('\n').map_or(text, |index| &text[index + 1..]);
+    PicklsPosition {
+        line: line + text.matches('\n').count() as u32,
+        character: last.encode_utf16().count() as u32,
+    }
+}
+
+/// The byte offset of `position` within `text`.
+fn byte_offset(text: &str, position: PicklsPosition) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(index) => line_start += index + 1,
+            None => return text.len(),
+        }
+    }
+    let line = &text[line_start..];
+    let line = match line.find('\n') {
+        Some(index) => line[..index].strip_suffix('\r').unwrap_or(&line[..index]),
+        None => line,
+    };
+    let mut units = 0;
+    for (index, c) in line.char_indices() {
+        if units >= position.character {
+            return line_start + index;
+        }
+        units += c.len_utf16() as u32;
+    }
+    line_start + line.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check that the edits from `original` to `formatted` apply back to `formatted`, and that no
+    /// edit begins or ends between the "\r" and "\n" of a line ending in `original`.
+    fn check_round_trip(original: &str, formatted: &str) -> Vec<PicklsTextEdit> {
+        let edits = minimal_text_edits(original, formatted);
+        assert_eq!(
+            apply_text_edits(original, &edits),
+            formatted,
+            "{original:?} to {formatted:?} via {edits:?}"
+        );
+        let lines: Vec<&str> = original.split_inclusive('\n').collect();
+        for edit in &edits {
+            for position in [edit.range.start, edit.range.end] {
+                let line = lines.get(position.line as usize).copied().unwrap_or("");
+                let content = line
+                    .strip_suffix("\r\n")
+                    .or_else(|| line.strip_suffix('\n'))
+                    .unwrap_or(line);
+                assert!(
+                    position.character <= content.encode_utf16().count() as u32,
+                    "{position:?} splits a line ending of {original:?} in {edits:?}"
+                );
+            }
+        }
+        for pair in edits.windows(2) {
+            let (a, b) = (&pair[0].range.end, &pair[1].range.start);
+            assert!((a.line, a.character) <= (b.line, b.character), "{edits:?}");
+        }
+        edits
+    }
+
+    /// A small xorshift generator, so that the randomized tests are reproducible.
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+    }
+
+    const PIECES: &[&str] = &[
+        "a", "b", " ", "\t", "é", "中", "😀", "\n", "\r\n", "\r\n", "\r", "fn x() {", "}",
+    ];
+
+    fn random_text(rng: &mut Rng, len: usize) -> String {
+        (0..len).map(|_| PIECES[rng.below(PIECES.len())]).collect()
+    }
+
+    /// `text` with a few pieces inserted, removed or replaced, and its line endings sometimes
+    /// converted, as a formatter might.
+    fn mutate(rng: &mut Rng, text: &str) -> String {
+        let mut pieces: Vec<String> = text
+            .split_inclusive('\n')
+            .flat_map(|line| {
+                let (content, ending) = match line.strip_suffix("\r\n") {
+                    Some(content) => (content, "\r\n"),
+                    None => match line.strip_suffix('\n') {
+                        Some(content) => (content, "\n"),
+                        None => (line, ""),
+                    },
+                };
+                let mut pieces: Vec<String> = content.chars().map(String::from).collect();
+                pieces.extend((!ending.is_empty()).then(|| ending.to_string()));
+                pieces
+            })
+            .collect();
+        for _ in 0..rng.below(4) {
+            let piece = PIECES[rng.below(PIECES.len())].to_string();
+            let index = rng.below(pieces.len() + 1);
+            match rng.below(4) {
+                0 | 1 => pieces.insert(index, piece),
+                2 if index < pieces.len() => drop(pieces.remove(index)),
+                _ if index < pieces.len() => pieces[index] = piece,
+                _ => {}
+            }
+        }
+        for piece in pieces.iter_mut() {
+            match (rng.below(6), piece.as_str()) {
+                (0, "\r\n") => *piece = "\n".to_string(),
+                (1, "\n") => *piece = "\r\n".to_string(),
+                _ => {}
+            }
+        }
+        pieces.concat()
+    }
+
+    #[test]
+    fn test_identical_texts_have_no_edits() {
+        for text in ["", "a", "a\r\nb\r\n", "a\nb\r\nc"] {
+            assert!(check_round_trip(text, text).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_crlf() {
+        let edits = check_round_trip("a = 1\r\nb=2\r\nc = 3\r\n", "a = 1\r\nb = 2\r\nc = 3\r\n");
+        assert_eq!(edits.len(), 1);
+        check_round_trip("x\r\ny\r\n", "x\ny\n");
+        check_round_trip("x\ny\n", "x\r\ny\r\n");
+        check_round_trip("x\r\n", "x\r\r\n");
+        check_round_trip("x\r\r\n", "x\r\n");
+        check_round_trip("x\r\ny", "x\r\n\r\ny");
+        check_round_trip("x\ry\r\n", "x\r\ny\r\n");
+    }
+
+    #[test]
+    fn test_no_trailing_newline() {
+        check_round_trip("a\nb", "a\nb\n");
+        check_round_trip("a\nb\n", "a\nb");
+        check_round_trip("a\r\nb", "a\r\nb\r\n");
+        check_round_trip("a\r\nb\r\n", "a\r\nb");
+        check_round_trip("", "a\n");
+        check_round_trip("a\n", "");
+    }
+
+    #[test]
+    fn test_mixed_line_endings() {
+        check_round_trip("a\r\nb\nc\r\nd\n", "a\nb\nc\nd\n");
+        check_round_trip("a\r\nb\nc\r\nd\n", "a\r\nb\r\nc\r\nd\r\n");
+        check_round_trip("a\r\nb\nc\r\nd", "a\r\nB\nc\nd\r\n");
+    }
+
+    #[test]
+    fn test_multibyte() {
+        let edits = check_round_trip("let s = \"😀中\";\n", "let s = \"😀 中\";\n");
+        assert_eq!(
+            edits,
+            [PicklsTextEdit {
+                range: PicklsRange {
+                    start: PicklsPosition {
+                        line: 0,
+                        character: 11,
+                    },
+                    end: PicklsPosition {
+                        line: 0,
+                        character: 11,
+                    },
+                },
+                new_text: " ".to_string(),
+            }]
+        );
+        check_round_trip("é😀\r\n中\r\n", "e😀\r\n中文\r\n");
+        check_round_trip("😀😀\n", "😀\n");
+    }
+
+    #[test]
+    fn test_random_round_trips() {
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+        for _ in 0..2000 {
+            let len = rng.below(24);
+            let original = random_text(&mut rng, len);
+            let formatted = match rng.below(5) {
+                0 => random_text(&mut rng, len),
+                _ => mutate(&mut rng, &original),
+            };
+            check_round_trip(&original, &formatted);
+        }
+    }
+
+    #[test]
+    fn test_normalize_newlines_preserves_dominant_line_ending() {
+        let preserve = |original: &str, formatted: &str| {
+            normalize_newlines(original, formatted, PicklsNewline::Preserve, None)
+        };
+        // Mostly CRLF, so the formatter's LF output is converted back.
+        assert_eq!(preserve("a\r\nb\r\nc\n", "a\nb\r\nc\n"), "a\r\nb\r\nc\r\n");
+        // Mostly LF, so stray CRLFs from the formatter are converted.
+        assert_eq!(preserve("a\nb\nc\r\n", "a\r\nb\nc\r\n"), "a\nb\nc\n");
+        // Ties go to LF.
+        assert_eq!(preserve("a\r\nb\n", "a\r\nb\r\n"), "a\nb\n");
+        // Without any line endings in the original, the output is left alone.
+        assert_eq!(preserve("a", "a\r\nb\n"), "a\r\nb\n");
+        // Lone CRs count as neither, and are kept.
+        assert_eq!(preserve("a\rb\r\n", "a\rb\n"), "a\rb\r\n");
+    }
+
+    #[test]
+    fn test_normalize_newlines_to_lf_and_crlf() {
+        let mixed = "a\r\nb\nc\r\n";
+        assert_eq!(
+            normalize_newlines("", mixed, PicklsNewline::Lf, None),
+            "a\nb\nc\n"
+        );
+        assert_eq!(
+            normalize_newlines("", mixed, PicklsNewline::Crlf, None),
+            "a\r\nb\r\nc\r\n"
+        );
+        assert_eq!(
+            normalize_newlines("", "a\r\r\n", PicklsNewline::Crlf, None),
+            "a\r\r\n"
+        );
+    }
+
+    #[test]
+    fn test_ensure_final_newline() {
+        let normalize = |original: &str, formatted: &str, newline, ensure_final_newline| {
+            normalize_newlines(original, formatted, newline, Some(ensure_final_newline))
+        };
+        use PicklsNewline::{Crlf, Lf, Preserve};
+        assert_eq!(
+            normalize("a\r\nb\r\n", "a\r\nb", Preserve, true),
+            "a\r\nb\r\n"
+        );
+        assert_eq!(
+            normalize("a\nb\r\nc\r\n", "a\nb\nc", Preserve, true),
+            "a\r\nb\r\nc\r\n"
+        );
+        assert_eq!(normalize("a", "a\r\nb", Preserve, true), "a\r\nb\r\n");
+        assert_eq!(normalize("a", "a", Preserve, true), "a\n");
+        assert_eq!(normalize("", "", Lf, true), "");
+        assert_eq!(normalize("", "a\n", Crlf, true), "a\r\n");
+        assert_eq!(normalize("", "a\r\n\n\r\n", Preserve, false), "a");
+        assert_eq!(normalize("", "a\n\n", Crlf, false), "a");
+        assert_eq!(normalize("", "a\r", Lf, false), "a\r");
+    }
+
+    #[test]
+    fn test_large_diff_falls_back_to_one_edit() {
+        // Every line changes, so no line is common and the diff would need more than
+        // MAX_DIFF_CELLS comparisons.
+        let lines = 2100;
+        assert!(lines * lines > MAX_DIFF_CELLS);
+        let original: String = (0..lines).map(|i| format!("item_{i}=1\r\n")).collect();
+        let formatted: String = (0..lines).map(|i| format!("item_{i} = 1\r\n")).collect();
+        let edits = check_round_trip(&original, &formatted);
+        assert_eq!(edits.len(), 1);
+        assert_eq!(
+            edits[0].range.start,
+            PicklsPosition {
+                line: 0,
+                character: 6
+            }
+        );
+        let formatted = format!("# header\n{}", original.replace("\r\n", "\n"));
+        check_round_trip(&original, &formatted);
+    }
+}
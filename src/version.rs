@@ -0,0 +1,102 @@
+use std::{cmp::Ordering, fmt};
+
+/// A loosely-semver version number as printed by `--version`, (ie: "0.4.10" or "v1.2"). Only the
+/// leading dot-separated numeric components are kept, so "1.2.3-beta.1" is 1.2.3. Missing
+/// components compare as zero, so "1.2" == "1.2.0".
+#[derive(Clone, Debug)]
+pub struct PicklsVersion {
+    pub components: Vec<u64>,
+}
+
+impl PicklsVersion {
+    /// Parse a version, ignoring a leading "v". Returns None if `text` does not start with a
+    /// number.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let text = text.strip_prefix(['v', 'V']).unwrap_or(text);
+        let mut components = Vec::new();
+        for component in text.split('.') {
+            let digits = component
+                .find(|c: char| !c.is_ascii_digit())
+                .map_or(component, |end| &component[..end]);
+            let Ok(number) = digits.parse() else {
+                break;
+            };
+            components.push(number);
+            if digits.len() < component.len() {
+                break;
+            }
+        }
+        (!components.is_empty()).then_some(PicklsVersion { components })
+    }
+}
+
+impl Ord for PicklsVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.components.len().max(other.components.len());
+        let component = |version: &Self, index| version.components.get(index).copied().unwrap_or(0);
+        (0..len)
+            .map(|index| component(self, index).cmp(&component(other, index)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for PicklsVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for PicklsVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for PicklsVersion {}
+
+impl fmt::Display for PicklsVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let components: Vec<String> = self.components.iter().map(u64::to_string).collect();
+        write!(f, "{}", components.join("."))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(text: &str) -> PicklsVersion {
+        PicklsVersion::parse(text).expect("text is a version")
+    }
+
+    #[test]
+    fn test_parse() {
+        let components = |text: &str| version(text).components;
+        assert_eq!(components("0.4.10"), [0, 4, 10]);
+        assert_eq!(components(" v1.2\n"), [1, 2]);
+        assert_eq!(components("V3"), [3]);
+        assert_eq!(components("1.2.3-beta.1"), [1, 2, 3]);
+        assert_eq!(components("2.0rc1"), [2, 0]);
+        assert_eq!(components("1.2.x"), [1, 2]);
+        assert_eq!(components("10.20.30.40"), [10, 20, 30, 40]);
+        assert_eq!(version("v0.04.1").to_string(), "0.4.1");
+        assert!(PicklsVersion::parse("").is_none());
+        assert!(PicklsVersion::parse("ruff 0.4.10").is_none());
+        assert!(PicklsVersion::parse("vx").is_none());
+        assert!(PicklsVersion::parse(".1").is_none());
+    }
+
+    #[test]
+    fn test_compare() {
+        assert!(version("0.4.10") > version("0.4.9"));
+        assert!(version("0.10") > version("0.9.99"));
+        assert!(version("1") > version("0.99"));
+        assert!(version("1.2.3-beta") < version("1.2.4"));
+        assert_eq!(version("1.2"), version("1.2.0"));
+        assert_eq!(version("1.2.0.0"), version("v1.2"));
+        assert_ne!(version("1.2"), version("1.2.1"));
+        assert_eq!(version("1.2").cmp(&version("1.2.0")), Ordering::Equal);
+    }
+}